@@ -1,15 +1,11 @@
-#[cfg(feature="bevy_support")]
 use bevy::prelude::*;
 
 use nalgebra::Vector3;
 use corrosive_physics::engine::{PhysicsEngine};
-use corrosive_physics::system::object::{PhyEntity, PhyEntityID};
-use corrosive_physics::volume::BVIntersector;
-use corrosive_physics::volume::tlas::TLASElement;
+use corrosive_physics::system::object::{PhyEntity, PhyEntityBuilder, PhyEntityID};
 
 
 
-#[cfg(feature="bevy_support")]
 fn main() {
     println!("String test case 'Cubes'...");
     App::new()
@@ -20,64 +16,24 @@ fn main() {
         .run();
 }
 
-#[cfg(feature="bevy_support")]
 #[derive(Component)]
 struct Rotator;
 
-#[cfg(feature="bevy_support")]
 fn update(
     time: Res<Time>,
     mut query: Query<(&PhyEntityID, &mut Transform)>
 ) {
-    let mut engine = PhysicsEngine::global_mut();
+    // only stepping needs exclusive access; the transforms are synced under a read lock
+    PhysicsEngine::global_mut().step(time.delta_seconds_f64());
 
+    let engine = PhysicsEngine::global();
     for (id, mut trans) in query.iter_mut() {
-        // eprintln!("ids {:?}  @  {:?}", id.entity_id, trans.translation);
-
-        if id.entity_id != 0 {
-            // query for potential colliders
-            let colliders = engine.query_colliders(id.clone());
-            // let floor = &engine[PhyEntityID { world_id: 0, chunk_id: 0, entity_id: 0 }];
-
-            if colliders.is_empty() || (colliders.len() == 1 && colliders[0].id.entity_id == id.entity_id) {
-                // if !engine.world.nodes()[1].aabb().intersects(engine[id.clone()].bounding_volume()) {
-                // update
-                let entity: &mut PhyEntity<f64> = &mut engine[id.clone()];
-                entity.is.integrate(time.delta_seconds_f64());
-                entity.sync();
-
-                // refit TLAS to the updated bounds (faster than a full rebuild)
-                engine.world.refit();
-            }
-        }
-
-
-
         // sync
         let entity: &PhyEntity<f64> = &engine[id.clone()];
-        let transform: &mut Transform = &mut trans;
-
-        transform.translation.x = entity.is.state.pos.x as f32;
-        transform.translation.y = entity.is.state.pos.y as f32;
-        transform.translation.z = entity.is.state.pos.z as f32;
-
-        transform.rotation = Quat::from_xyzw(
-            entity.is.state.rot.i as f32,
-            entity.is.state.rot.j as f32,
-            entity.is.state.rot.k as f32,
-            entity.is.state.rot.w as f32,
-        );
-
-        transform.scale.x = entity.is.state.scale.x as f32;
-        transform.scale.y = entity.is.state.scale.y as f32;
-        transform.scale.z = entity.is.state.scale.z as f32;
+        *trans = (&entity.is.state).into();
     }
-
-    // rebuild the tree properly for the next tick
-    engine.world.build();
 }
 
-#[cfg(feature="bevy_support")]
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -111,60 +67,48 @@ fn setup(
 
     // create engine and physical shadows of renderable objects
     let mut engine = PhysicsEngine::<f64>::new();
-    let mut count = 0usize;
-    let floor_id = PhyEntityID {
+    let entity_id = PhyEntityID {
         world_id: 0,
         chunk_id: 0,
-        entity_id: count,
+        entity_id: 0,
+        generation: 0,
     };
-    count += 1;
-
-    let mut floor = PhyEntity::cube(
-        floor_id.clone(), Vector3::new(20.0, 1.0, 20.0));
 
-    floor.is.state.pos = Vector3::new(0.0, 0.0, 0.0);
-    floor.is.momentum = Vector3::new(0.0, 0.0, 0.0);
-    floor.sync();
-    engine.world.blas_mut().push(floor);
+    let floor = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::new(20.0, 1.0, 20.0)))
+        .is_static(true)
+        .build();
+    let floor_id = engine.spawn(floor);
 
 
     let spacing = 2.0;
+    let mut cubes = Vec::new();
     for y in 0..5 {
         for x in 0..3 {
             for z in 0..3 {
 
-                let cube_id = PhyEntityID {
-                    world_id: 0,
-                    chunk_id: 0,
-                    entity_id: count
-                };
-                count += 1;
-
-                let mut entity = PhyEntity::cube(
-                    cube_id.clone(), Vector3::repeat(1.0)
-                );
-                entity.is.state.pos = Vector3::new(
-                    x  as f64 * spacing - 5.0,
-                    5.0 + y as f64 * spacing,
-                    z as f64 * spacing - 5.0
-                );
-                entity.is.momentum = Vector3::new(0.0, -1.0, 0.0);
-                entity.sync();
-
-                commands
-                    .spawn_bundle(PbrBundle {
-                        mesh: cube_handle.clone(),
-                        material: cube_material_handle.clone(),
-                        transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                        ..default()
-                    })
-                    .insert(cube_id);
-
-                engine.world.blas_mut().push(entity);
+                let entity = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::repeat(1.0)))
+                    .position(Vector3::new(
+                        x  as f64 * spacing - 5.0,
+                        5.0 + y as f64 * spacing,
+                        z as f64 * spacing - 5.0
+                    ))
+                    .linear_velocity(Vector3::new(0.0, -1.0, 0.0))
+                    .build();
+                cubes.push(entity);
             }
         }
     }
-    engine.world.build();
+
+    for cube_id in engine.spawn_batch(cubes) {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: cube_handle.clone(),
+                material: cube_material_handle.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                ..default()
+            })
+            .insert(cube_id);
+    }
 
 
     unsafe {
@@ -195,9 +139,3 @@ fn setup(
         .. default()
     });
 }
-
-#[cfg(not(feature="bevy_support"))]
-fn main() -> Result<(), ()> {
-    println!("Program was not compiled with bevy feature enabled");
-    Err(())
-}
\ No newline at end of file
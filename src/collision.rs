@@ -1,7 +1,11 @@
+pub mod ccd;
 mod collider;
-mod collision_primitive;
-mod intersection;
-mod model;
+pub mod constraint;
+pub mod contact;
+pub mod collision_primitive;
+pub mod intersection;
+pub mod model;
+pub mod solver;
 
 use nalgebra::{UnitQuaternion, Vector3};
 use crate::helper::BaseFloat;
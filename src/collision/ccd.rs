@@ -0,0 +1,141 @@
+use nalgebra::Vector3;
+use num::Signed;
+use crate::helper::BaseFloat;
+use crate::volume::BoundingVolume;
+use crate::volume::oriented::OBB;
+
+/// Maximal number of advancement steps of `toi_obb_obb`, which guards against slow convergence due
+/// to rounding.
+const MAX_ADVANCEMENT_STEPS: usize = 32;
+
+/// Returns the time of impact of the boxes `a` and `b`, which move with the velocities `va` and
+/// `vb` during a step of the length `dt`. The time of impact is returned as the fraction of the
+/// step in `[0, 1]` at which the boxes first touch, or `None` if they do not touch during the
+/// step. Boxes that already overlap at the start of the step return zero.
+///
+/// The boxes only translate during the step; their orientations are kept. The time of impact is
+/// found by conservative advancement along the relative velocity of the boxes: as long as the boxes
+/// are separated along one of the axes of the separating axis theorem, they cannot touch before
+/// the gap along that axis is closed. Each step advances to the latest of these times, which never
+/// steps past the impact, such that thin and fast boxes do not tunnel through each other. Axes
+/// along which the gap does not shrink separate the boxes for the rest of the step.
+pub fn toi_obb_obb<T: BaseFloat>(
+    a: &OBB<T>,
+    va: &Vector3<T>,
+    b: &OBB<T>,
+    vb: &Vector3<T>,
+    dt: T,
+) -> Option<T> {
+    let axes_a = [a.transform.right(), a.transform.up(), a.transform.forward()];
+    let axes_b = [b.transform.right(), b.transform.up(), b.transform.forward()];
+    let (sa, sb) = (a.world_half_size(), b.world_half_size());
+    let radius = |axes: &[Vector3<T>; 3], s: &Vector3<T>, axis: &Vector3<T>| {
+        (0..3).fold(T::zero(), |r, k| r + s[k] * <T as Signed>::abs(&axes[k].dot(axis)))
+    };
+
+    // face axes of both boxes, followed by the edge-edge axes; parallel edges do not span an axis
+    let eps: T = nalgebra::convert(1e-6);
+    let mut axes = Vec::with_capacity(15);
+    axes.extend(axes_a.iter().chain(axes_b.iter()).copied());
+    for ea in &axes_a {
+        for eb in &axes_b {
+            let axis = ea.cross(eb);
+            let len = axis.norm();
+            if len > eps {
+                axes.push(axis / len);
+            }
+        }
+    }
+
+    // displacement of B relative to A over the whole step
+    let disp = (vb - va) * dt;
+    let rel = b.center() - a.center();
+    let tolerance = T::default_epsilon().sqrt();
+    let mut t = T::zero();
+    for _ in 0..MAX_ADVANCEMENT_STEPS {
+        let rel = rel + disp * t;
+        let mut advance = T::zero();
+        let mut separated = false;
+        for axis in &axes {
+            let dist = axis.dot(&rel);
+            let gap = <T as Signed>::abs(&dist) - radius(&axes_a, &sa, axis) - radius(&axes_b, &sb, axis);
+            if gap <= tolerance {
+                continue;
+            }
+            // rate at which the gap along the axis shrinks over the step
+            let closing = if dist < T::zero() { disp.dot(axis) } else { -disp.dot(axis) };
+            if closing <= T::zero() {
+                return None;
+            }
+            separated = true;
+            advance = T::max(advance, gap / closing);
+        }
+
+        if !separated {
+            return Some(t);
+        }
+        t += advance;
+        if t > T::one() {
+            return None;
+        }
+    }
+    Some(t)
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::collision::ccd::toi_obb_obb;
+    use crate::test_util::{cuboid, unit_box};
+
+    #[test]
+    fn head_on() {
+        let a = unit_box(Vector3::zeros(), UnitQuaternion::identity());
+        let b = unit_box(Vector3::new(5.0, 0.0, 0.0), UnitQuaternion::identity());
+        let (va, vb) = (Vector3::new(10.0, 0.0, 0.0), Vector3::new(-10.0, 0.0, 0.0));
+
+        // the gap of 4 is closed at 20 per unit of time
+        let toi = toi_obb_obb(&a, &va, &b, &vb, 0.5).unwrap();
+        assert!((toi - 0.4).abs() < 1e-9);
+        assert_eq!(toi_obb_obb(&a, &va, &b, &vb, 0.1), None);
+        assert_eq!(toi_obb_obb(&a, &-va, &b, &-vb, 0.5), None);
+
+        // the corner of a rotated box is hit earlier
+        let eighth = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4);
+        let b = unit_box(Vector3::new(5.0, 0.0, 0.0), eighth);
+        let toi = toi_obb_obb(&a, &va, &b, &vb, 0.5).unwrap();
+        assert!((toi - (4.5 - 0.5f64.sqrt()) / 10.0).abs() < 1e-9);
+
+        // overlapping boxes touch at the start of the step
+        let b = unit_box(Vector3::new(0.5, 0.5, 0.0), eighth);
+        assert_eq!(toi_obb_obb(&a, &va, &b, &vb, 0.5), Some(0.0));
+    }
+
+    #[test]
+    fn thin_and_fast() {
+        // the box moves 10 units per step through a plate that is 0.02 units thick, such that it
+        // would tunnel through the plate with discrete steps
+        let plate = cuboid(Vector3::new(5.0, 0.0, 0.0), UnitQuaternion::identity(), Vector3::new(0.01, 2.0, 2.0));
+        let bullet = unit_box(Vector3::zeros(), UnitQuaternion::identity());
+        let toi = toi_obb_obb(&bullet, &Vector3::new(100.0, 0.0, 0.0), &plate, &Vector3::zeros(), 0.1).unwrap();
+        assert!((toi - 0.449).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pass_by() {
+        let a = unit_box(Vector3::zeros(), UnitQuaternion::identity());
+        let (va, vb) = (Vector3::new(10.0, 0.0, 0.0), Vector3::new(-10.0, 0.0, 0.0));
+
+        // the boxes pass each other on parallel lanes
+        let b = unit_box(Vector3::new(5.0, 3.0, 0.0), UnitQuaternion::identity());
+        assert_eq!(toi_obb_obb(&a, &va, &b, &vb, 1.0), None);
+
+        // the rotated box crosses the path of A diagonally, but only after A has passed; the
+        // bounds of the motions of both boxes overlap nevertheless
+        let eighth = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4);
+        let b = unit_box(Vector3::new(2.0, -4.0, 0.0), eighth);
+        assert_eq!(toi_obb_obb(&a, &va, &b, &Vector3::new(-2.0, 4.0, 0.0), 1.0), None);
+    }
+}
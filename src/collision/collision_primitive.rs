@@ -1,17 +1,285 @@
-use nalgebra::SVector;
-use crate::collision::intersection::Ray;
-use crate::collision::model::VertexBuffer;
+use nalgebra::{SVector, Vector3};
+use crate::collision::contact::{ContactManifold, ContactPoint};
+use crate::collision::intersection::{Ray, RayIntersection};
+use crate::collision::model::{IndexBuffer, VertexBuffer};
+use crate::helper::{BaseFloat, gjk};
+use crate::system::inertia::Transformer;
 use crate::volume::aabb::AABB;
 
 pub type Edge = (usize, usize);
 
 pub trait CollisionPrimitive<T, const DIM: usize> {
+    /// Returns the local corner indices of a single primitive. The corner `k` of the primitive with
+    /// id `id` is located at `vbo[ibo[id * indices().len() + k]]`.
     fn indices(&self) -> &[usize];
+
+    /// Returns the edges of a single primitive as pairs of local corner indices. The edges of a
+    /// face should follow its winding order, so that neighbouring faces of a consistently wound
+    /// mesh traverse their shared edges in opposite directions.
     fn edges(&self) -> &[Edge];
 
-    fn centroid(&self, id: usize, vbo: &VertexBuffer<T, DIM>) -> SVector<T, DIM>;
-    fn wrap(&self, id: usize, vbo: &VertexBuffer<T, DIM>) -> AABB<T, DIM>;
-    fn intersect_ray(&self, id: usize, vbo: &VertexBuffer<T, DIM>, ray: &mut Ray<T, DIM>);
+    fn centroid(&self, id: usize, vbo: &VertexBuffer<T, DIM>, ibo: &IndexBuffer) -> SVector<T, DIM>;
+    fn wrap(&self, id: usize, vbo: &VertexBuffer<T, DIM>, ibo: &IndexBuffer) -> AABB<T, DIM>;
+    fn intersect_ray(&self, id: usize, vbo: &VertexBuffer<T, DIM>, ibo: &IndexBuffer, ray: &mut Ray<T, DIM>);
+
+    /// Returns the point of the primitive with id `id` that lies closest to the point `p`.
+    fn closest_point(&self, id: usize, vbo: &VertexBuffer<T, DIM>, ibo: &IndexBuffer, p: &SVector<T, DIM>) -> SVector<T, DIM>;
 }
 
 
+/// Triangle primitive for triangle meshes. Each triangle consists of three consecutive indices
+/// in the index buffer of the mesh.
+pub struct Triangle {}
+
+impl Triangle {
+    /// Returns the three corners of the triangle with id `id`.
+    fn corners<'a, T>(
+        id: usize,
+        vbo: &'a VertexBuffer<T, 3>,
+        ibo: &IndexBuffer
+    ) -> (&'a Vector3<T>, &'a Vector3<T>, &'a Vector3<T>) {
+        (&vbo[ibo[id * 3]], &vbo[ibo[id * 3 + 1]], &vbo[ibo[id * 3 + 2]])
+    }
+}
+
+impl<T: BaseFloat + From<u32>> CollisionPrimitive<T, 3> for Triangle {
+    fn indices(&self) -> &[usize] {
+        &[0, 1, 2]
+    }
+
+    fn edges(&self) -> &[Edge] {
+        &[(0, 1), (1, 2), (2, 0)]
+    }
+
+    fn centroid(&self, id: usize, vbo: &VertexBuffer<T, 3>, ibo: &IndexBuffer) -> Vector3<T> {
+        let (a, b, c) = Self::corners(id, vbo, ibo);
+        (a + b + c) / T::from(3u32)
+    }
+
+    fn wrap(&self, id: usize, vbo: &VertexBuffer<T, 3>, ibo: &IndexBuffer) -> AABB<T, 3> {
+        let (a, b, c) = Self::corners(id, vbo, ibo);
+        let mut aabb = AABB::new();
+        aabb.grow(a);
+        aabb.grow(b);
+        aabb.grow(c);
+        aabb
+    }
+
+    /// Intersects the ray with the triangle using the Möller–Trumbore algorithm. If the triangle
+    /// is hit closer to the ray origin than `ray.d`, the distance of the ray is shortened to the hit
+    /// and the intersection is stored in the ray. The normal of the intersection is the geometric
+    /// normal of the triangle, which points to the side from which the triangle appears
+    /// counter-clockwise.
+    fn intersect_ray(&self, id: usize, vbo: &VertexBuffer<T, 3>, ibo: &IndexBuffer, ray: &mut Ray<T, 3>) {
+        let (v0, v1, v2) = Self::corners(id, vbo, ibo);
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+
+        let p = ray.dir.cross(&e2);
+        let det = e1.dot(&p);
+        if det == T::zero() {
+            return; // ray is parallel to the triangle
+        }
+        let r_det = T::one() / det;
+
+        // barycentric coordinates of the hit
+        let s = ray.origin - v0;
+        let u = s.dot(&p) * r_det;
+        if u < T::zero() || u > T::one() {
+            return;
+        }
+        let q = s.cross(&e1);
+        let v = ray.dir.dot(&q) * r_det;
+        if v < T::zero() || u + v > T::one() {
+            return;
+        }
+
+        let d = e2.dot(&q) * r_det;
+        if d < T::zero() || d >= ray.d {
+            return;
+        }
+        ray.d = d;
+        ray.intersection = Some(RayIntersection {
+            pos: ray.point_at(d),
+            normal: e1.cross(&e2).normalize(),
+            prim_id: id,
+            d,
+        });
+    }
+
+    /// Returns the closest point of the triangle by determining the Voronoi region of the triangle
+    /// that contains the point, which is either a corner, an edge or the face of the triangle
+    /// (see Ericson, Real-Time Collision Detection, 5.1.5).
+    fn closest_point(&self, id: usize, vbo: &VertexBuffer<T, 3>, ibo: &IndexBuffer, p: &Vector3<T>) -> Vector3<T> {
+        let (a, b, c) = Self::corners(id, vbo, ibo);
+        let (ab, ac) = (b - a, c - a);
+
+        let ap = p - a;
+        let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+        if d1 <= T::zero() && d2 <= T::zero() {
+            return *a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+        if d3 >= T::zero() && d4 <= d3 {
+            return *b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= T::zero() && d1 >= T::zero() && d3 <= T::zero() {
+            return a + ab.scale(d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+        if d6 >= T::zero() && d5 <= d6 {
+            return *c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= T::zero() && d2 >= T::zero() && d6 <= T::zero() {
+            return a + ac.scale(d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= T::zero() && d4 - d3 >= T::zero() && d5 - d6 >= T::zero() {
+            return b + (c - b).scale((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        // the point projects onto the face of the triangle
+        let denom = T::one() / (va + vb + vc);
+        a + ab.scale(vb * denom) + ac.scale(vc * denom)
+    }
+}
+
+/// Convex hull primitive, which is defined by the vertices of the hull in its local reference
+/// frame. The faces of the hull are never needed: all queries only use the support function of the
+/// hull, which is the vertex with the largest projection onto a direction. Vertices that lie
+/// within the hull do not change the results, but slow down the queries.
+///
+/// Unlike the primitives of a mesh, a convex hull is a shape on its own and is placed in the world
+/// through a `Transformer`. Intersections are tested with GJK and contacts are generated with EPA
+/// (see `helper::gjk`), such that any pair of convex shapes can be handled the same way.
+#[derive(Clone, Debug)]
+pub struct ConvexHull<T> {
+    pub vertices: Vec<Vector3<T>>,
+}
+
+impl<T: BaseFloat> ConvexHull<T> {
+    pub fn new(vertices: Vec<Vector3<T>>) -> Self {
+        ConvexHull { vertices }
+    }
+
+    /// Returns the vertices of the hull in the laboratory frame, for the transformer state `t`.
+    pub fn world_vertices(&self, t: &Transformer<T>) -> Vec<Vector3<T>> {
+        self.vertices.iter()
+            .map(|v| t.trafo_point(v))
+            .collect()
+    }
+
+    /// Returns true, if this hull with the transformer state `t` intersects the `other` hull with
+    /// the transformer state `t_other`. Touching hulls count as intersecting.
+    pub fn intersects(&self, t: &Transformer<T>, other: &ConvexHull<T>, t_other: &Transformer<T>) -> bool {
+        let (a, b) = (self.world_vertices(t), other.world_vertices(t_other));
+        gjk::intersects(
+            |d: &Vector3<T>| gjk::support_points(&a, d),
+            |d: &Vector3<T>| gjk::support_points(&b, d),
+        )
+    }
+
+    /// Generates the contact manifold between this hull with the transformer state `t` and the
+    /// `other` hull with the transformer state `t_other`, if they intersect. The normal points from
+    /// this hull to the `other` hull. The manifold contains a single contact point, halfway between
+    /// the deepest points of both hulls.
+    pub fn contact(
+        &self,
+        t: &Transformer<T>,
+        other: &ConvexHull<T>,
+        t_other: &Transformer<T>
+    ) -> Option<ContactManifold<T>> {
+        let (a, b) = (self.world_vertices(t), other.world_vertices(t_other));
+        let (normal, depth, pos) = gjk::penetration(
+            |d: &Vector3<T>| gjk::support_points(&a, d),
+            |d: &Vector3<T>| gjk::support_points(&b, d),
+        )?;
+        let mut manifold = ContactManifold::new(normal);
+        manifold.add_point(ContactPoint { pos, depth });
+        Some(manifold)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::collision::collision_primitive::{CollisionPrimitive, ConvexHull, Triangle};
+    use crate::collision::intersection::Ray;
+    use crate::collision::model::{IndexBuffer, VertexBuffer};
+    use crate::system::inertia::Transformer;
+    use crate::test_util::{at, cube_corners, unit_box};
+    use crate::volume::BVIntersector;
+
+    #[test]
+    fn intersect_ray() {
+        // two parallel triangles at z = 1 and z = 2
+        let vbo = VertexBuffer::new(vec![
+            Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 1.0),
+            Vector3::new(0.0, 0.0, 2.0), Vector3::new(1.0, 0.0, 2.0), Vector3::new(0.0, 1.0, 2.0),
+        ]);
+        let ibo = IndexBuffer::new(vec![0, 1, 2, 3, 4, 5]);
+        let mut ray = Ray {
+            d: 10.0,
+            origin: Vector3::new(0.25, 0.25, 0.0),
+            dir: Vector3::z(),
+            intersection: None,
+        };
+
+        // the farther triangle is hit first, but is replaced by the closer one
+        Triangle {}.intersect_ray(1, &vbo, &ibo, &mut ray);
+        assert_eq!(ray.d, 2.0);
+        Triangle {}.intersect_ray(0, &vbo, &ibo, &mut ray);
+        assert_eq!(ray.d, 1.0);
+        Triangle {}.intersect_ray(1, &vbo, &ibo, &mut ray);
+        assert_eq!(ray.d, 1.0);
+
+        let hit = ray.intersection.unwrap();
+        assert_eq!(hit.prim_id, 0);
+        assert_eq!(hit.pos, Vector3::new(0.25, 0.25, 1.0));
+        assert_eq!(hit.normal, Vector3::z());
+
+        // a ray that misses the triangle
+        let mut ray = Ray {
+            d: 10.0,
+            origin: Vector3::new(0.75, 0.75, 0.0),
+            dir: Vector3::z(),
+            intersection: None,
+        };
+        Triangle {}.intersect_ray(0, &vbo, &ibo, &mut ray);
+        assert!(ray.intersection.is_none());
+        assert_eq!(ray.d, 10.0);
+    }
+
+    fn unit_cube() -> ConvexHull<f64> {
+        ConvexHull::new(cube_corners(Vector3::zeros(), UnitQuaternion::identity()))
+    }
+
+    #[test]
+    fn convex_hull() {
+        let hull = unit_cube();
+        let a = Transformer::default();
+
+        // GJK agrees with the separating axis test of the equivalent boxes
+        for i in 0..50 {
+            let pos = Vector3::new(0.05 * i as f64 - 0.3, 0.9 - 0.01 * i as f64, 0.2);
+            let rot = UnitQuaternion::from_euler_angles(0.1 * i as f64, 0.7, 0.05 * i as f64);
+            let b = at(pos, rot);
+            let obb_a = unit_box(Vector3::zeros(), UnitQuaternion::identity());
+            let obb_b = unit_box(pos, rot);
+            assert_eq!(hull.intersects(&a, &hull, &b), obb_a.intersects(&obb_b), "{i}");
+        }
+
+        let b = at(Vector3::new(0.0, 0.0, 0.9), UnitQuaternion::identity());
+        let manifold = hull.contact(&a, &hull, &b).unwrap();
+        assert!((manifold.normal - Vector3::z()).norm() < 1e-9);
+        assert!((manifold.depth() - 0.1).abs() < 1e-9);
+        assert!((manifold.points()[0].pos.z - 0.45).abs() < 1e-9);
+        assert!(hull.contact(&b, &hull, &a).unwrap().normal.z < 0.0);
+    }
+}
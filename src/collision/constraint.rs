@@ -0,0 +1,165 @@
+use nalgebra::Vector3;
+use crate::collision::solver::inv_effective_mass;
+use crate::engine::PhysicsEngine;
+use crate::helper::BaseFloat;
+use crate::system::inertia::IS;
+use crate::system::object::PhyEntityID;
+
+/// A joint that keeps two anchor points on two bodies at a fixed distance, like a rigid rod. If
+/// `rope` is set, the joint only resists stretching, such that the bodies may move closer to each
+/// other freely.
+#[derive(Clone, Debug)]
+pub struct DistanceJoint<T> {
+    /// Anchor point on body A, in the reference frame of A.
+    pub anchor_a: Vector3<T>,
+    /// Anchor point on body B, in the reference frame of B.
+    pub anchor_b: Vector3<T>,
+    /// Target distance between both anchor points.
+    pub distance: T,
+    /// Baumgarte factor in `[0, 1]`, which determines the fraction of the position error that is
+    /// corrected per step.
+    pub beta: T,
+    /// If true, the joint only pulls the bodies together, but never pushes them apart.
+    pub rope: bool,
+}
+
+impl<T: BaseFloat> DistanceJoint<T> {
+    /// Creates a new rigid distance joint between the anchor points `anchor_a` and `anchor_b`,
+    /// which are specified in the reference frames of their bodies.
+    pub fn new(anchor_a: Vector3<T>, anchor_b: Vector3<T>, distance: T) -> Self {
+        DistanceJoint {
+            anchor_a,
+            anchor_b,
+            distance,
+            beta: nalgebra::convert(0.2),
+            rope: false,
+        }
+    }
+
+    /// Solves the joint between the bodies `a` and `b` for the time step `dt`.
+    ///
+    /// An impulse along the axis between both anchor points removes the relative velocity of the
+    /// anchors along that axis. To counter the drift of the positions, a Baumgarte bias velocity
+    /// of `beta / dt` times the position error is added to the target velocity. Solving the joint
+    /// several times per step improves its accuracy when multiple joints act on the same body.
+    pub fn solve(&self, a: &mut IS<T>, b: &mut IS<T>, dt: T) {
+        let pa = a.state.trafo_point(&self.anchor_a);
+        let pb = b.state.trafo_point(&self.anchor_b);
+        let axis = pb - pa;
+        let len = axis.norm();
+        if len <= T::default_epsilon() {
+            // the constraint axis is undefined
+            return;
+        }
+        let n = axis / len;
+        let error = len - self.distance;
+        if self.rope && error <= T::zero() {
+            return;
+        }
+
+        let ra = pa - a.world_center_of_mass();
+        let rb = pb - b.world_center_of_mass();
+        let k = inv_effective_mass(a, &ra, &n) + inv_effective_mass(b, &rb, &n);
+        if k <= T::zero() {
+            // both bodies are static
+            return;
+        }
+
+        let vn = (b.point_velocity_world(&pb) - a.point_velocity_world(&pa)).dot(&n);
+        let mut lambda = -(vn + self.beta / dt * error) / k;
+        if self.rope {
+            // a rope can only pull
+            lambda = T::min(lambda, T::zero());
+        }
+
+        let imp = n.scale(lambda);
+        b.apply_impulse_world(&imp, &pb);
+        a.apply_impulse_world(&-imp, &pa);
+    }
+}
+
+
+/// A distance constraint between two entities of a physics engine, like a rod between two limbs
+/// of a ragdoll. Unlike a `DistanceJoint`, which acts on two bodies directly, the constraint
+/// refers to its bodies through their ids, such that it can be stored in the engine (see
+/// `PhysicsEngine::constraints`) and is solved as part of every simulation step.
+#[derive(Clone, Debug)]
+pub struct DistanceConstraint<T> {
+    pub a: PhyEntityID,
+    pub b: PhyEntityID,
+    /// Target distance between both anchor points.
+    pub rest_length: T,
+    /// Anchor point on entity A, in the reference frame of A.
+    pub anchor_a: Vector3<T>,
+    /// Anchor point on entity B, in the reference frame of B.
+    pub anchor_b: Vector3<T>,
+}
+
+impl<T: BaseFloat> DistanceConstraint<T> {
+    pub fn new(a: PhyEntityID, b: PhyEntityID, rest_length: T, anchor_a: Vector3<T>, anchor_b: Vector3<T>) -> Self {
+        DistanceConstraint { a, b, rest_length, anchor_a, anchor_b }
+    }
+
+    /// Solves the constraint for a single step of the time `dt`. Equal and opposite impulses along
+    /// the line between both anchors drive their separation towards `rest_length`, where each
+    /// entity is moved according to its inverse mass and static entities are not moved at all. The
+    /// impulses follow those of a rigid `DistanceJoint` with the default Baumgarte factor.
+    ///
+    /// Constraints between entities that are no longer alive are skipped.
+    pub fn solve(&self, engine: &mut PhysicsEngine<T>, dt: T) {
+        if let Some((a, b)) = engine.get_pair_mut(&self.a, &self.b) {
+            // static entities are held in place like immovable bodies
+            let mut static_a = a.is_static.then(|| a.static_body());
+            let mut static_b = b.is_static.then(|| b.static_body());
+            DistanceJoint::new(self.anchor_a, self.anchor_b, self.rest_length).solve(
+                static_a.as_mut().unwrap_or(&mut a.is),
+                static_b.as_mut().unwrap_or(&mut b.is),
+                dt,
+            );
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+    use crate::collision::constraint::DistanceJoint;
+    use crate::test_util::cube_entity;
+
+    #[test]
+    fn converges_to_distance() {
+        let mut a = cube_entity(0, Vector3::zeros());
+        let mut b = cube_entity(1, Vector3::new(3.0, 1.0, 0.0));
+        let joint = DistanceJoint::new(Vector3::zeros(), Vector3::zeros(), 2.0);
+
+        let dt = 0.1;
+        for _ in 0..100 {
+            joint.solve(&mut a.is, &mut b.is, dt);
+            a.is.integrate(dt);
+            b.is.integrate(dt);
+            a.sync();
+            b.sync();
+        }
+        let separation = (b.is.state.pos - a.is.state.pos).norm();
+        assert!((separation - 2.0).abs() < 1e-3, "{separation}");
+        // the joint only acts along its axis and conserves the total momentum
+        assert!((a.is.momentum + b.is.momentum).norm() < 1e-12);
+    }
+
+    #[test]
+    fn rope_only_pulls() {
+        let mut a = cube_entity(0, Vector3::zeros());
+        let mut b = cube_entity(1, Vector3::new(1.0, 0.0, 0.0));
+        let mut joint = DistanceJoint::new(Vector3::zeros(), Vector3::zeros(), 2.0);
+        joint.rope = true;
+
+        joint.solve(&mut a.is, &mut b.is, 0.1);
+        assert_eq!(b.is.momentum, Vector3::zeros());
+
+        // a slack rope does not resist compression either
+        b.is.momentum = Vector3::new(-1.0, 0.0, 0.0);
+        joint.solve(&mut a.is, &mut b.is, 0.1);
+        assert_eq!(b.is.momentum, Vector3::new(-1.0, 0.0, 0.0));
+    }
+}
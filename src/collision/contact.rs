@@ -0,0 +1,602 @@
+use nalgebra::Vector3;
+use num::Signed;
+use crate::helper::{BaseFloat, separated_axis};
+use crate::system::object::PhyEntityID;
+use crate::volume::BoundingVolume;
+use crate::volume::capsule::Capsule;
+use crate::volume::oriented::OBB;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
+
+/// The maximal number of contact points that a single contact manifold can hold.
+pub const MAX_CONTACT_POINTS: usize = 4;
+
+/// A single point of contact between two bodies.
+#[derive(Clone, Copy, Debug)]
+pub struct ContactPoint<T> {
+    /// Position of the contact point in the laboratory frame.
+    pub pos: Vector3<T>,
+    /// Penetration depth of the bodies at the contact point, measured along the normal of the
+    /// manifold.
+    pub depth: T,
+}
+
+/// A contact manifold collects the contact points between a pair of bodies, which share a common
+/// contact normal.
+///
+/// The manifold holds at most `MAX_CONTACT_POINTS` points. If more points are added, the manifold
+/// keeps the subset that covers the largest area of the contact patch (see `reduce`).
+///
+/// # Normal Convention
+///
+/// The contact normal is a unit vector that always points from body A towards body B. Moving B
+/// along the normal (or A against it) separates the bodies. For contacts between two entities,
+/// A is the entity with the lower `PhyEntityID`. All contact generators and the solver follow
+/// this convention; a flipped normal would reverse the impulses of the contact response.
+#[derive(Clone, Debug)]
+pub struct ContactManifold<T> {
+    pub normal: Vector3<T>,
+    points: [ContactPoint<T>; MAX_CONTACT_POINTS],
+    len: usize,
+}
+
+impl<T: BaseFloat> ContactManifold<T> {
+    /// Creates a new, empty contact manifold with the specified contact normal.
+    pub fn new(normal: Vector3<T>) -> Self {
+        ContactManifold {
+            normal,
+            points: [ContactPoint { pos: Vector3::zeros(), depth: T::zero() }; MAX_CONTACT_POINTS],
+            len: 0,
+        }
+    }
+
+    /// Returns the contact points of the manifold.
+    pub fn points(&self) -> &[ContactPoint<T>] {
+        &self.points[..self.len]
+    }
+
+    /// Returns the amount of contact points in the manifold.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true, if the manifold contains no contact points.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the deepest penetration depth of all contact points in the manifold. For an empty
+    /// manifold, zero is returned.
+    pub fn depth(&self) -> T {
+        self.points().iter()
+            .fold(T::zero(), |depth, p| T::max(depth, p.depth))
+    }
+
+    /// Adds a contact point to the manifold. If the manifold is already full, the point only
+    /// replaces an existing point if that increases the area covered by the manifold.
+    pub fn add_point(&mut self, point: ContactPoint<T>) {
+        if self.len < MAX_CONTACT_POINTS {
+            self.points[self.len] = point;
+            self.len += 1;
+            return;
+        }
+
+        let mut candidates = [point; MAX_CONTACT_POINTS + 1];
+        candidates[..MAX_CONTACT_POINTS].copy_from_slice(&self.points);
+        let (selection, len) = select_points(&candidates, &self.normal, MAX_CONTACT_POINTS);
+        for i in 0..len {
+            self.points[i] = candidates[selection[i]];
+        }
+        self.len = len;
+    }
+
+    /// Reduces the manifold to at most `max_points` contact points.
+    ///
+    /// The deepest point is always kept, followed by the point farthest away from it. A third and
+    /// fourth point are chosen such that the area spanned by the kept points is maximized. Fewer
+    /// contact points make solving the contact cheaper, at the cost of a less stable contact, as
+    /// the kept points resist tipping over a smaller area.
+    pub fn reduce(&mut self, max_points: usize) {
+        if self.len <= max_points {
+            return;
+        }
+
+        let points = self.points;
+        let (selection, len) = select_points(&points[..self.len], &self.normal, max_points);
+        for i in 0..len {
+            self.points[i] = points[selection[i]];
+        }
+        self.len = len;
+    }
+}
+
+/// A contact between the two entities `a` and `b`. This is the common representation of contacts
+/// that is produced by the narrow phase and consumed by the contact response.
+///
+/// The normal of the manifold points from `a` to `b` and `a` always has the lower id of both
+/// entities (see `ContactManifold`).
+#[derive(Clone, Debug)]
+pub struct Contact<T> {
+    pub a: PhyEntityID,
+    pub b: PhyEntityID,
+    pub manifold: ContactManifold<T>,
+}
+
+impl<T: BaseFloat> Contact<T> {
+    /// Creates a new contact between the entities `a` and `b`. If `b` has the lower id, both
+    /// entities are swapped and the normal of the manifold is flipped, such that the contact
+    /// follows the normal convention.
+    pub fn new(a: PhyEntityID, b: PhyEntityID, mut manifold: ContactManifold<T>) -> Self {
+        if b < a {
+            manifold.normal = -manifold.normal;
+            Contact { a: b, b: a, manifold }
+        } else {
+            Contact { a, b, manifold }
+        }
+    }
+
+    /// Returns the contact normal, which points from `a` to `b`.
+    pub fn normal(&self) -> &Vector3<T> {
+        &self.manifold.normal
+    }
+
+    /// Returns the deepest penetration depth of all contact points.
+    pub fn depth(&self) -> T {
+        self.manifold.depth()
+    }
+
+    /// Returns the contact points in the laboratory frame, together with their penetration depths.
+    pub fn points(&self) -> &[ContactPoint<T>] {
+        self.manifold.points()
+    }
+}
+
+/// Generates the contact manifold between the boxes `a` and `b`, if they overlap. The normal of the
+/// manifold is the axis of least penetration and points from `a` to `b`.
+///
+/// If the normal is the normal of a face of one of the boxes, that face is the reference face and
+/// the face of the other box that faces it the most is the incident face. The contact points are
+/// the corners of the incident face, clipped to the sides of the reference face, that lie below
+/// the reference face. Unlike the corners of the boxes themselves, the clipped face keeps all
+/// points of a resting contact when the boxes are slightly shifted or twisted against each other,
+/// which keeps stacks of boxes stable.
+///
+/// Otherwise, like for edge-edge contacts, the contact points are the corners of each box that lie
+/// within the other box. If there are none, a single contact point halfway between the deepest
+/// points of both boxes is used.
+pub fn obb_obb<T: BaseFloat>(a: &OBB<T>, b: &OBB<T>) -> Option<ContactManifold<T>> {
+    let (ca, cb) = (a.center(), b.center());
+    let axes_a = [a.transform.right(), a.transform.up(), a.transform.forward()];
+    let axes_b = [b.transform.right(), b.transform.up(), b.transform.forward()];
+    let (sa, sb) = (a.world_half_size(), b.world_half_size());
+    let (normal, depth) = separated_axis::penetration_obb_obb(&axes_a, &axes_b, &(cb - ca), &sa, &sb)?;
+
+    // face contacts, where the reference face of A points along and that of B against the normal
+    let parallel = T::one() - T::default_epsilon().sqrt();
+    let face = |axes: &[Vector3<T>; 3]| (0..3).find(|&k| <T as Signed>::abs(&axes[k].dot(&normal)) >= parallel);
+    let mut manifold = ContactManifold::new(normal);
+    if let Some(k) = face(&axes_a) {
+        clip_incident_face((&ca, &axes_a, &sa), (&cb, &axes_b, &sb), k, &normal, &mut manifold);
+    } else if let Some(k) = face(&axes_b) {
+        clip_incident_face((&cb, &axes_b, &sb), (&ca, &axes_a, &sa), k, &-normal, &mut manifold);
+    }
+    if !manifold.is_empty() {
+        return Some(manifold);
+    }
+
+    let corners = |c: &Vector3<T>, axes: &[Vector3<T>; 3], s: &Vector3<T>| {
+        let mut corners = [*c; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            for k in 0..3 {
+                let sign = if i & (1 << k) == 0 { -T::one() } else { T::one() };
+                *corner += axes[k].scale(sign * s[k]);
+            }
+        }
+        corners
+    };
+    let contains = |c: &Vector3<T>, axes: &[Vector3<T>; 3], s: &Vector3<T>, p: &Vector3<T>| {
+        let d = p - c;
+        (0..3).all(|k| <T as Signed>::abs(&d.dot(&axes[k])) <= s[k])
+    };
+    // half extent of a box along the normal
+    let radius = |axes: &[Vector3<T>; 3], s: &Vector3<T>| {
+        (0..3).fold(T::zero(), |r, k| r + s[k] * <T as Signed>::abs(&axes[k].dot(&normal)))
+    };
+    let (ra, rb) = (radius(&axes_a, &sa), radius(&axes_b, &sb));
+
+    let corners_a = corners(&ca, &axes_a, &sa);
+    let corners_b = corners(&cb, &axes_b, &sb);
+    for p in corners_b.iter().filter(|p| contains(&ca, &axes_a, &sa, p)) {
+        manifold.add_point(ContactPoint { pos: *p, depth: ra - (p - ca).dot(&normal) });
+    }
+    for p in corners_a.iter().filter(|p| contains(&cb, &axes_b, &sb, p)) {
+        manifold.add_point(ContactPoint { pos: *p, depth: rb + (p - cb).dot(&normal) });
+    }
+
+    if manifold.is_empty() {
+        let deepest = |corners: &[Vector3<T>; 8], dir: &Vector3<T>| corners.iter()
+            .fold(corners[0], |best, p| if p.dot(dir) > best.dot(dir) { *p } else { best });
+        let pa = deepest(&corners_a, &normal);
+        let pb = deepest(&corners_b, &-normal);
+        manifold.add_point(ContactPoint { pos: (pa + pb) * T::half(), depth });
+    }
+    Some(manifold)
+}
+
+/// Adds the contact points of a face contact between two boxes to the manifold (see `obb_obb`).
+/// Each box is given by its center, its axes and its half sizes in the laboratory frame. The
+/// reference face is the face of the `reference` box along its axis `k`, whose outward normal is
+/// `n`.
+fn clip_incident_face<T: BaseFloat>(
+    reference: (&Vector3<T>, &[Vector3<T>; 3], &Vector3<T>),
+    incident: (&Vector3<T>, &[Vector3<T>; 3], &Vector3<T>),
+    k: usize,
+    n: &Vector3<T>,
+    manifold: &mut ContactManifold<T>,
+) {
+    let (cr, axes_r, sr) = reference;
+    let (ci, axes_i, si) = incident;
+
+    // the incident face is the face of the incident box that is the most antiparallel to n
+    let ki = (0..3).fold(0, |best, j| {
+        if <T as Signed>::abs(&axes_i[j].dot(n)) > <T as Signed>::abs(&axes_i[best].dot(n)) { j } else { best }
+    });
+    let sign = if axes_i[ki].dot(n) > T::zero() { -T::one() } else { T::one() };
+    let center = ci + axes_i[ki].scale(sign * si[ki]);
+    let (eu, ev) = (axes_i[(ki + 1) % 3].scale(si[(ki + 1) % 3]), axes_i[(ki + 2) % 3].scale(si[(ki + 2) % 3]));
+    let mut polygon = [center; 8];
+    polygon[..4].copy_from_slice(&[center - eu - ev, center + eu - ev, center + eu + ev, center - eu + ev]);
+
+    // clip the incident face to the side planes of the reference face
+    let mut len = 4;
+    for j in [(k + 1) % 3, (k + 2) % 3] {
+        for axis in [axes_r[j], -axes_r[j]] {
+            len = clip_polygon(&mut polygon, len, &axis, axis.dot(cr) + sr[j]);
+        }
+    }
+
+    let face = n.dot(cr) + sr[k];
+    for p in &polygon[..len] {
+        let depth = face - n.dot(p);
+        if depth >= T::zero() {
+            manifold.add_point(ContactPoint { pos: *p, depth });
+        }
+    }
+}
+
+/// Clips the convex polygon of the first `len` points of `polygon` to the half space of the points
+/// `p` with `axis · p <= offset` and returns the amount of points of the clipped polygon. Each clip
+/// adds at most one point, so a quad can be clipped four times.
+fn clip_polygon<T: BaseFloat>(polygon: &mut [Vector3<T>; 8], len: usize, axis: &Vector3<T>, offset: T) -> usize {
+    let input = *polygon;
+    let mut out = 0;
+    for i in 0..len {
+        let (p, q) = (input[i], input[(i + 1) % len]);
+        let (dp, dq) = (axis.dot(&p) - offset, axis.dot(&q) - offset);
+        if dp <= T::zero() {
+            polygon[out] = p;
+            out += 1;
+        }
+        if (dp < T::zero() && dq > T::zero()) || (dp > T::zero() && dq < T::zero()) {
+            polygon[out] = p + (q - p).scale(dp / (dp - dq));
+            out += 1;
+        }
+    }
+    out
+}
+
+/// Generates the contact manifold between the spheres `a` and `b`, if they overlap. The normal
+/// points from the center of `a` to the center of `b` and the single contact point lies halfway
+/// between the deepest points of both spheres. Spheres with coincident centers are separated along
+/// the y-axis.
+pub fn sphere_sphere<T: BaseFloat>(a: &Sphere<T, 3>, b: &Sphere<T, 3>) -> Option<ContactManifold<T>> {
+    let d = b.center - a.center;
+    let dist = d.norm();
+    let depth = a.radius + b.radius - dist;
+    if depth < T::zero() {
+        return None;
+    }
+    let normal = if dist <= T::default_epsilon() { Vector3::y() } else { d / dist };
+
+    let mut manifold = ContactManifold::new(normal);
+    let (pa, pb) = (a.center + normal.scale(a.radius), b.center - normal.scale(b.radius));
+    manifold.add_point(ContactPoint { pos: (pa + pb) * T::half(), depth });
+    Some(manifold)
+}
+
+/// Generates the contact manifold between the box `a` and the sphere `b`, if they overlap. The
+/// normal points from the closest point of the box to the center of the sphere. If the center lies
+/// within the box, the normal of the nearest face is used instead.
+pub fn obb_sphere<T: BaseFloat>(a: &OBB<T>, b: &Sphere<T, 3>) -> Option<ContactManifold<T>> {
+    let (closest, dist) = a.closest_point(&b.center);
+    if dist > b.radius {
+        return None;
+    }
+
+    let normal = if dist > T::default_epsilon() {
+        (b.center - closest) / dist
+    } else {
+        // push the sphere out through the nearest face of the box
+        let rel = a.transform.inv_trafo_point(&b.center);
+        let axes = [a.transform.right(), a.transform.up(), a.transform.forward()];
+        let face = (0..3)
+            .map(|i| (i, (a.half_size[i] - <T as Signed>::abs(&rel[i])) * <T as Signed>::abs(&a.transform.scale[i])))
+            .fold((0, T::MAX), |best, (i, d)| if d < best.1 { (i, d) } else { best })
+            .0;
+        if rel[face] < T::zero() { -axes[face] } else { axes[face] }
+    };
+
+    // the point on the surface of the box and the deepest point of the sphere
+    let surface = b.center - normal.scale(dist);
+    let deepest = b.center - normal.scale(b.radius);
+    let mut manifold = ContactManifold::new(normal);
+    manifold.add_point(ContactPoint { pos: (surface + deepest) * T::half(), depth: b.radius - dist });
+    Some(manifold)
+}
+
+/// Generates the contact manifold between the capsule `a` and the sphere `b`, if they overlap.
+pub fn capsule_sphere<T: BaseFloat>(a: &Capsule<T>, b: &Sphere<T, 3>) -> Option<ContactManifold<T>> {
+    sphere_sphere(&Sphere::new(a.closest_segment_point(&b.center), a.radius), b)
+}
+
+/// Generates the contact manifold between the capsules `a` and `b`, if they overlap. The contact
+/// is generated between the closest points of both segments, so parallel capsules only touch in a
+/// single point.
+pub fn capsule_capsule<T: BaseFloat>(a: &Capsule<T>, b: &Capsule<T>) -> Option<ContactManifold<T>> {
+    let (pa, pb) = a.closest_segment_points(b);
+    sphere_sphere(&Sphere::new(pa, a.radius), &Sphere::new(pb, b.radius))
+}
+
+/// Generates the contact manifold between the capsule `a` and the box `b`, if they overlap. The
+/// normal points from `a` to `b`.
+///
+/// The capsule is treated as spheres at both end points of its segment and at the point of the
+/// segment that lies closest to the box. This way, a capsule lying on a face of the box touches it
+/// with both ends, which keeps it from rolling over the contact. The manifold takes over the normal
+/// of the deepest sphere.
+pub fn capsule_obb<T: BaseFloat>(a: &Capsule<T>, b: &OBB<T>) -> Option<ContactManifold<T>> {
+    let (closest, _) = a.closest_segment_point_by(|p| b.closest_point(p).1);
+    let mut candidates = vec![a.a, a.b];
+    if (closest - a.a).norm() > T::default_epsilon() && (closest - a.b).norm() > T::default_epsilon() {
+        candidates.push(closest);
+    }
+    let contacts = candidates.iter()
+        .filter_map(|p| obb_sphere(b, &Sphere::new(*p, a.radius)))
+        .collect::<Vec<_>>();
+
+    let deepest = contacts.iter()
+        .fold(None, |best: Option<&ContactManifold<T>>, c| match best {
+            Some(best) if best.depth() >= c.depth() => Some(best),
+            _ => Some(c),
+        })?;
+    let mut manifold = ContactManifold::new(-deepest.normal);
+    for point in contacts.iter().flat_map(|c| c.points()) {
+        manifold.add_point(*point);
+    }
+    Some(manifold)
+}
+
+/// Generates the contact manifold between the shapes `a` and `b`, if they overlap, by dispatching
+/// to the contact generator of the respective pair of shapes. The normal points from `a` to `b`.
+pub fn shape_shape<T: BaseFloat>(a: &ShapeVolume<T>, b: &ShapeVolume<T>) -> Option<ContactManifold<T>> {
+    let flipped = |manifold: Option<ContactManifold<T>>| manifold.map(|mut m| {
+        m.normal = -m.normal;
+        m
+    });
+    match (a, b) {
+        (ShapeVolume::Box(a), ShapeVolume::Box(b)) => obb_obb(a, b),
+        (ShapeVolume::Box(a), ShapeVolume::Sphere(b)) => obb_sphere(a, b),
+        (ShapeVolume::Box(a), ShapeVolume::Capsule(b)) => flipped(capsule_obb(b, a)),
+        (ShapeVolume::Sphere(a), ShapeVolume::Box(b)) => flipped(obb_sphere(b, a)),
+        (ShapeVolume::Sphere(a), ShapeVolume::Sphere(b)) => sphere_sphere(a, b),
+        (ShapeVolume::Sphere(a), ShapeVolume::Capsule(b)) => flipped(capsule_sphere(b, a)),
+        (ShapeVolume::Capsule(a), ShapeVolume::Box(b)) => capsule_obb(a, b),
+        (ShapeVolume::Capsule(a), ShapeVolume::Sphere(b)) => capsule_sphere(a, b),
+        (ShapeVolume::Capsule(a), ShapeVolume::Capsule(b)) => capsule_capsule(a, b),
+    }
+}
+
+/// Returns the signed area of the triangle `a`, `b`, `p`, projected onto the plane with the
+/// specified normal. The value is proportional to the actual area.
+fn signed_area<T: BaseFloat>(a: &Vector3<T>, b: &Vector3<T>, p: &Vector3<T>, normal: &Vector3<T>) -> T {
+    (b - a).cross(&(p - a)).dot(normal)
+}
+
+/// Selects up to `max_points` (but never more than `MAX_CONTACT_POINTS`) contact points that cover
+/// the largest area and returns their indices, together with the number of selected points.
+fn select_points<T: BaseFloat>(
+    points: &[ContactPoint<T>],
+    normal: &Vector3<T>,
+    max_points: usize
+) -> ([usize; MAX_CONTACT_POINTS], usize) {
+    let max_points = usize::min(usize::min(max_points, MAX_CONTACT_POINTS), points.len());
+    let mut selection = [0usize; MAX_CONTACT_POINTS];
+
+    // finds the unselected point with the highest score
+    let best = |selection: &[usize], score: &dyn Fn(&ContactPoint<T>) -> T| -> usize {
+        let mut best_idx = 0usize;
+        let mut best_score = T::MIN;
+        for (i, p) in points.iter().enumerate() {
+            if selection.contains(&i) {
+                continue;
+            }
+            let s = score(p);
+            if s > best_score {
+                best_score = s;
+                best_idx = i;
+            }
+        }
+        best_idx
+    };
+
+    for n in 0..max_points {
+        selection[n] = match n {
+            // deepest point
+            0 => best(&[], &|p| p.depth),
+            // point farthest away from the deepest point
+            1 => {
+                let p0 = points[selection[0]].pos;
+                best(&selection[..1], &|p| (p.pos - p0).norm_squared())
+            },
+            // point that spans the largest triangle with the first two points
+            2 => {
+                let (p0, p1) = (points[selection[0]].pos, points[selection[1]].pos);
+                best(&selection[..2], &|p| <T as Signed>::abs(&signed_area(&p0, &p1, &p.pos, normal)))
+            },
+            // point that adds the largest area on the outside of the triangle
+            _ => {
+                let (p0, p1, p2) = (
+                    points[selection[0]].pos,
+                    points[selection[1]].pos,
+                    points[selection[2]].pos,
+                );
+                let orientation = <T as Signed>::signum(&signed_area(&p0, &p1, &p2, normal));
+                best(&selection[..3], &|p| {
+                    let a = signed_area(&p0, &p1, &p.pos, normal);
+                    let b = signed_area(&p1, &p2, &p.pos, normal);
+                    let c = signed_area(&p2, &p0, &p.pos, normal);
+                    -orientation * T::min(a, T::min(b, c))
+                })
+            },
+        };
+    }
+    (selection, max_points)
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::collision::contact::{Contact, ContactManifold, ContactPoint, obb_obb, shape_shape};
+    use crate::test_util::{cuboid, id, unit_box};
+    use crate::volume::capsule::Capsule;
+    use crate::volume::shape::ShapeVolume;
+    use crate::volume::sphere::Sphere;
+
+    fn square() -> ContactManifold<f64> {
+        let mut manifold = ContactManifold::new(Vector3::y());
+        manifold.add_point(ContactPoint { pos: Vector3::new(0.0, 0.0, 0.0), depth: 0.1 });
+        manifold.add_point(ContactPoint { pos: Vector3::new(1.0, 0.0, 0.0), depth: 0.05 });
+        manifold.add_point(ContactPoint { pos: Vector3::new(0.0, 0.0, 1.0), depth: 0.05 });
+        manifold.add_point(ContactPoint { pos: Vector3::new(1.0, 0.0, 1.0), depth: 0.02 });
+        manifold
+    }
+
+    #[test]
+    fn reduce_to_two() {
+        let mut manifold = square();
+        manifold.reduce(2);
+
+        // the deepest point and the opposite corner resist tipping about both axes
+        assert_eq!(manifold.len(), 2);
+        assert_eq!(manifold.points()[0].pos, Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(manifold.points()[1].pos, Vector3::new(1.0, 0.0, 1.0));
+        assert_eq!(manifold.depth(), 0.1);
+    }
+
+    #[test]
+    fn add_to_full_manifold() {
+        let mut manifold = square();
+        // a point in the center of the patch does not increase the covered area
+        manifold.add_point(ContactPoint { pos: Vector3::new(0.5, 0.0, 0.5), depth: 0.05 });
+        assert_eq!(manifold.len(), 4);
+        assert!(manifold.points().iter().all(|p| p.pos != Vector3::new(0.5, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn obb_obb_normal() {
+        let a = unit_box(Vector3::zeros(), UnitQuaternion::identity());
+        let b = unit_box(Vector3::new(0.8, 0.1, 0.0), UnitQuaternion::identity());
+
+        // the normal points from A to B, regardless of the order in which the boxes are passed
+        let manifold = obb_obb(&a, &b).unwrap();
+        assert!((manifold.normal - Vector3::x()).norm() < 1e-12);
+        assert!((manifold.depth() - 0.2).abs() < 1e-12);
+        assert_eq!(manifold.len(), 4);
+
+        let manifold = obb_obb(&b, &a).unwrap();
+        assert!((manifold.normal + Vector3::x()).norm() < 1e-12);
+
+        assert!(obb_obb(&a, &unit_box(Vector3::new(1.1, 0.0, 0.0), UnitQuaternion::identity())).is_none());
+    }
+
+    #[test]
+    fn obb_obb_face() {
+        // a slightly rotated box rests on a larger box with its entire bottom face
+        let a = cuboid(Vector3::zeros(), UnitQuaternion::identity(), Vector3::new(2.0, 0.5, 2.0));
+        let b = unit_box(Vector3::new(0.0, 0.99, 0.0), UnitQuaternion::from_euler_angles(1e-3, 0.3, -2e-3));
+
+        let manifold = obb_obb(&a, &b).unwrap();
+        assert!((manifold.normal - Vector3::y()).norm() < 1e-12);
+        assert_eq!(manifold.len(), 4);
+        for point in manifold.points() {
+            assert!((point.pos.y - 0.49_f64).abs() < 0.01);
+            assert!(point.depth > 0.0 && point.depth < 0.02);
+        }
+
+        // the same face contact is found, if the boxes are swapped
+        let manifold = obb_obb(&b, &a).unwrap();
+        assert!((manifold.normal + Vector3::y()).norm() < 1e-12);
+        assert_eq!(manifold.len(), 4);
+    }
+
+    #[test]
+    fn contact_order() {
+        let contact = Contact::new(id(1), id(2), square());
+        assert_eq!((contact.a.entity_id, contact.b.entity_id), (1, 2));
+        assert_eq!(*contact.normal(), Vector3::y());
+
+        // the entities are ordered by their ids and the normal is flipped accordingly
+        let contact = Contact::new(id(2), id(1), square());
+        assert_eq!((contact.a.entity_id, contact.b.entity_id), (1, 2));
+        assert_eq!(*contact.normal(), -Vector3::y());
+        assert_eq!(contact.depth(), 0.1);
+        assert_eq!(contact.points().len(), 4);
+    }
+
+    #[test]
+    fn shape_contacts() {
+        let floor = ShapeVolume::Box(unit_box(Vector3::new(0.0, -0.5, 0.0), UnitQuaternion::identity()));
+        let sphere = ShapeVolume::Sphere(Sphere::new(Vector3::new(0.2, 0.4, 0.0), 0.5));
+        let capsule = ShapeVolume::Capsule(Capsule::new(
+            Vector3::new(-0.3, 0.4, 0.0),
+            Vector3::new(0.3, 0.4, 0.0),
+            0.5
+        ));
+
+        // the sphere rests on the floor
+        let manifold = shape_shape(&floor, &sphere).unwrap();
+        assert!((manifold.normal - Vector3::y()).norm() < 1e-12);
+        assert!((manifold.depth() - 0.1).abs() < 1e-12);
+        let manifold = shape_shape(&sphere, &floor).unwrap();
+        assert!((manifold.normal + Vector3::y()).norm() < 1e-12);
+
+        // the capsule touches the floor with both ends
+        let manifold = shape_shape(&capsule, &floor).unwrap();
+        assert!((manifold.normal + Vector3::y()).norm() < 1e-12);
+        assert!((manifold.depth() - 0.1).abs() < 1e-9);
+        assert!(manifold.len() >= 2);
+        assert!(manifold.points().iter().any(|p| p.pos.x < -0.25));
+        assert!(manifold.points().iter().any(|p| p.pos.x > 0.25));
+
+        // the sphere overlaps the right end of the capsule
+        let sphere = ShapeVolume::Sphere(Sphere::new(Vector3::new(1.0, 0.4, 0.0), 0.5));
+        let manifold = shape_shape(&capsule, &sphere).unwrap();
+        assert!((manifold.normal - Vector3::x()).norm() < 1e-12);
+        assert!((manifold.depth() - 0.3).abs() < 1e-12);
+        let manifold = shape_shape(&sphere, &capsule).unwrap();
+        assert!((manifold.normal + Vector3::x()).norm() < 1e-12);
+
+        let lifted = ShapeVolume::Sphere(Sphere::new(Vector3::new(0.0, 0.6, 0.0), 0.5));
+        assert!(shape_shape(&floor, &lifted).is_none());
+        let crossing = ShapeVolume::Capsule(Capsule::new(
+            Vector3::new(0.0, 1.2, -1.0),
+            Vector3::new(0.0, 1.2, 1.0),
+            0.5
+        ));
+        let manifold = shape_shape(&capsule, &crossing).unwrap();
+        assert!((manifold.normal - Vector3::y()).norm() < 1e-12);
+        assert!((manifold.depth() - 0.2).abs() < 1e-12);
+    }
+}
@@ -1,14 +1,118 @@
 use nalgebra::SVector;
+use crate::helper::BaseFloat;
+use crate::volume::aabb::AABB;
+use crate::volume::oriented::OBB;
+use crate::volume::BVIntersector;
 
+#[derive(Clone, Debug)]
 pub struct RayIntersection<T, const DIM: usize> {
     pub pos: SVector<T, DIM>,
     pub normal: SVector<T, DIM>,
-    pub prim_id: usize
+    pub prim_id: usize,
+    /// Distance from the ray origin to the intersection, measured in multiples of the ray
+    /// direction.
+    pub d: T,
 }
 
+/// Ray for ray queries, which starts at `origin` and extends along `dir`.
 pub struct Ray<T, const DIM: usize> {
+    /// Distance along the ray up to which hits are reported, measured in multiples of `dir`. This
+    /// starts at the maximum distance of the query and is shortened to the nearest hit found so
+    /// far while the query traverses the elements, such that farther elements are skipped.
     pub d: T,
     pub origin: SVector<T, DIM>,
     pub dir: SVector<T, DIM>,
+    /// Nearest hit found so far, or `None` if nothing was hit yet.
     pub intersection: Option<RayIntersection<T, DIM>>,
 }
+
+impl<T: BaseFloat, const DIM: usize> Ray<T, DIM> {
+    /// Creates a ray that starts at `origin` and reports hits up to a distance of `max_dist` along
+    /// `dir`. The direction is normalized, such that all distances along the ray are actual
+    /// distances. Use `T::MAX` as the maximum distance for unbounded rays.
+    pub fn new(origin: SVector<T, DIM>, dir: SVector<T, DIM>, max_dist: T) -> Self {
+        Ray {
+            d: max_dist,
+            origin,
+            dir: dir.normalize(),
+            intersection: None,
+        }
+    }
+
+    /// Returns the point at the distance `t` along the ray.
+    pub fn point_at(&self, t: T) -> SVector<T, DIM> {
+        self.origin + self.dir.scale(t)
+    }
+
+    /// Returns the distance along the ray at which it enters the AABB, or `None` if the ray misses
+    /// the box. Only the section of the ray between the origin and `d` is considered; rays that
+    /// start inside of the box enter it at zero.
+    pub fn entry(&self, aabb: &AABB<T, DIM>) -> Option<T> {
+        self.clip(aabb).map(|(entry, _)| entry)
+    }
+
+    /// Returns the distances along the ray at which it enters and exits the AABB, or `None` if the
+    /// ray misses the box. Like for `entry`, the distances are limited to the section of the ray
+    /// between the origin and `d`.
+    pub fn clip(&self, aabb: &AABB<T, DIM>) -> Option<(T, T)> {
+        // slab test, limited to the section of the ray between the origin and `d`
+        let mut t_min = T::zero();
+        let mut t_max = self.d;
+        for i in 0..DIM {
+            let r_dir = T::one() / self.dir[i];
+            let t0 = (aabb.min[i] - self.origin[i]) * r_dir;
+            let t1 = (aabb.max[i] - self.origin[i]) * r_dir;
+
+            t_min = T::max(t_min, T::min(t0, t1));
+            t_max = T::min(t_max, T::max(t0, t1));
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BVIntersector<T, AABB<T, DIM>, DIM> for Ray<T, DIM> {
+    fn intersects(&self, other: &AABB<T, DIM>) -> bool {
+        self.entry(other).is_some()
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for Ray<T, 3> {
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        other.intersect_ray(self).is_some()
+    }
+}
+
+/// Elements that can be hit by rays, like meshes. This is used by ray queries through acceleration
+/// structures, like `TLAS::raycast`.
+pub trait RayCast<T, const DIM: usize> {
+    /// Intersects the ray with the element. If the element is hit closer to the ray origin than
+    /// `ray.d`, the distance of the ray is shortened to the hit, the intersection is stored in the
+    /// ray and true is returned.
+    fn raycast(&self, ray: &mut Ray<T, DIM>) -> bool;
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+    use crate::collision::intersection::Ray;
+    use crate::volume::aabb::AABB;
+
+    #[test]
+    fn new() {
+        let ray = Ray::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 3.0, 4.0), 10.0_f64);
+        assert_eq!(ray.dir, Vector3::new(0.0, 0.6, 0.8));
+        assert_eq!(ray.point_at(5.0), Vector3::new(1.0, 3.0, 4.0));
+        assert!(ray.intersection.is_none());
+
+        // boxes beyond the maximum distance are not hit
+        let aabb = AABB { min: Vector3::new(0.0, 5.4, 7.4), max: Vector3::new(2.0, 6.0, 8.0) };
+        assert!((ray.entry(&aabb).unwrap() - 9.25).abs() < 1e-12);
+        let ray = Ray::new(ray.origin, ray.dir, 9.0);
+        assert!(ray.entry(&aabb).is_none());
+    }
+}
@@ -1,13 +1,48 @@
+#[cfg(feature = "obj")]
+use std::io::BufRead;
+use std::collections::HashMap;
 use std::ops::{Index, IndexMut};
-use nalgebra::SVector;
+use nalgebra::{SVector, Vector3};
+#[cfg(feature = "obj")]
+use crate::collision::collision_primitive::Triangle;
 use crate::collision::collision_primitive::CollisionPrimitive;
+use crate::collision::intersection::{Ray, RayCast, RayIntersection};
 use crate::helper::BaseFloat;
+#[cfg(feature = "obj")]
+use crate::system::inertia::{err, Error, ErrorType};
 use crate::system::inertia::Transformer;
+use crate::volume::aabb::AABB;
+use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::bvh::{BVH, BVHElement, BVHNode, VecPool};
+use crate::volume::bvh_splitting::BinnedSAHSplit;
 
 pub struct VertexBuffer<T, const DIM: usize> {
     vertices: Vec<SVector<T, DIM>>
 }
 
+impl<T, const DIM: usize> VertexBuffer<T, DIM> {
+    pub fn new(vertices: Vec<SVector<T, DIM>>) -> Self {
+        VertexBuffer {
+            vertices
+        }
+    }
+
+    /// Returns the amount of vertices in the buffer.
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Returns true, if the buffer contains no vertices.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Returns the vertices of the buffer.
+    pub fn vertices(&self) -> &[SVector<T, DIM>] {
+        &self.vertices
+    }
+}
+
 impl<T> VertexBuffer<T, 3>
 where T: BaseFloat {
     pub fn transformed(&self, transform: &Transformer<T>) -> Self {
@@ -42,6 +77,24 @@ pub struct IndexBuffer {
     indices: Vec<usize>
 }
 
+impl IndexBuffer {
+    pub fn new(indices: Vec<usize>) -> Self {
+        IndexBuffer {
+            indices
+        }
+    }
+
+    /// Returns the amount of indices in the buffer.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns true, if the buffer contains no indices.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
 impl Index<usize> for IndexBuffer {
     type Output = usize;
 
@@ -50,13 +103,179 @@ impl Index<usize> for IndexBuffer {
     }
 }
 
-pub struct PhysicsMesh<T, Primitive: CollisionPrimitive<T, DIM>, const DIM: usize> {
+
+
+/// Element type of the BVH that is build over the primitives of a `PhysicsMesh`. Since the
+/// vertices of a primitive live in the buffers of the mesh, the element only stores the id of the
+/// primitive together with its cached bounds.
+pub struct MeshPrimitive<T, const DIM: usize> {
+    pub prim_id: usize,
+    aabb: AABB<T, DIM>,
+    centroid: SVector<T, DIM>,
+}
+
+impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for MeshPrimitive<T, DIM> {
+    fn center(&self) -> SVector<T, DIM> {
+        self.aabb.center()
+    }
+
+    fn area(&self) -> T {
+        self.aabb.area()
+    }
+
+    fn min(&self) -> SVector<T, DIM> {
+        self.aabb.min
+    }
+
+    fn max(&self) -> SVector<T, DIM> {
+        self.aabb.max
+    }
+
+    fn size(&self) -> SVector<T, DIM> {
+        self.aabb.size()
+    }
+
+    fn half_size(&self) -> SVector<T, DIM> {
+        self.aabb.half_size()
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BVHElement<T, DIM> for MeshPrimitive<T, DIM> {
+    fn centroid(&self) -> SVector<T, DIM> {
+        self.centroid
+    }
+
+    fn wrap(&self) -> AABB<T, DIM> {
+        self.aabb
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BVIntersector<T, MeshPrimitive<T, DIM>, DIM> for Ray<T, DIM> {
+    fn intersects(&self, other: &MeshPrimitive<T, DIM>) -> bool {
+        self.intersects(&other.aabb)
+    }
+}
+
+pub type MeshBVH<T, const DIM: usize> = BVH<
+    T,
+    MeshPrimitive<T, DIM>,
+    VecPool<BVHNode<T, DIM>>,
+    VecPool<MeshPrimitive<T, DIM>>,
+    DIM>;
+
+
+
+pub struct PhysicsMesh<T: BaseFloat, Primitive: CollisionPrimitive<T, DIM>, const DIM: usize> {
     vbo: VertexBuffer<T, DIM>,
     ibo: IndexBuffer,
-    prim: Primitive
+    prim: Primitive,
+    bvh: MeshBVH<T, DIM>,
+}
+
+impl<T, Primitive, const DIM: usize> PhysicsMesh<T, Primitive, DIM>
+where T: BaseFloat + From<u32>,
+      Primitive: CollisionPrimitive<T, DIM> {
+
+    /// Creates a new physics mesh from the specified buffers and builds the BVH over its
+    /// primitives. The index buffer is interpreted as a list of primitives, each of which consists
+    /// of `prim.indices().len()` consecutive indices.
+    pub fn new(vbo: VertexBuffer<T, DIM>, ibo: IndexBuffer, prim: Primitive) -> Self {
+        let bvh = Self::bvh_over(&vbo, &ibo, &prim);
+        PhysicsMesh {
+            vbo,
+            ibo,
+            prim,
+            bvh,
+        }
+    }
+
+    /// Builds a new BVH over the current primitives of the mesh. The elements of the BVH only
+    /// store the primitive ids and their bounds, which are computed by the collision primitive;
+    /// the vertices stay in the buffers of the mesh.
+    ///
+    /// The mesh already holds a BVH, which is built on construction (see `bvh`). A separate BVH
+    /// may be built, for example, to keep the BVH of a mesh whose vertices are changed afterwards.
+    pub fn build_bvh(&self) -> MeshBVH<T, DIM> {
+        Self::bvh_over(&self.vbo, &self.ibo, &self.prim)
+    }
+
+    fn bvh_over(vbo: &VertexBuffer<T, DIM>, ibo: &IndexBuffer, prim: &Primitive) -> MeshBVH<T, DIM> {
+        let num_prims = ibo.len() / prim.indices().len();
+        let mut elements = VecPool::with_capacity(num_prims);
+        for id in 0..num_prims {
+            elements.push(MeshPrimitive {
+                prim_id: id,
+                aabb: prim.wrap(id, vbo, ibo),
+                centroid: prim.centroid(id, vbo, ibo),
+            });
+        }
+
+        let mut bvh = MeshBVH::new(elements);
+        bvh.rebuild::<BinnedSAHSplit<8>>();
+        bvh
+    }
 }
 
-impl<T, Primitive: CollisionPrimitive<T, DIM>, const DIM: usize> PhysicsMesh<T, Primitive, DIM> {
+#[cfg(feature = "obj")]
+impl<T> PhysicsMesh<T, Triangle, 3>
+where T: BaseFloat + From<u32> {
+    /// Loads a triangle mesh from a Wavefront OBJ file. Only the vertex positions (`v`) and faces
+    /// (`f`) are read; polygon faces are triangulated as a fan around their first corner. Normals,
+    /// texture coordinates and all other statements are ignored. Face indices may be negative, in
+    /// which case they refer to the vertices defined before the face, as per the OBJ format.
+    ///
+    /// A parse error is returned if the input cannot be read, if a vertex position is not a number,
+    /// or if a face has fewer than three corners or references a vertex that is not defined.
+    pub fn from_obj(reader: impl BufRead) -> Result<Self, Error> {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| err!(parse format!("line {}: {}", n + 1, e)))?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let mut pos = Vector3::zeros();
+                    for i in 0..3 {
+                        let value = tokens.next()
+                            .and_then(|t| t.parse::<f64>().ok())
+                            .ok_or_else(|| err!(parse format!("line {}: invalid vertex position", n + 1)))?;
+                        pos[i] = nalgebra::convert(value);
+                    }
+                    vertices.push(pos);
+                },
+                Some("f") => {
+                    let corners = tokens
+                        .map(|t| Self::obj_index(t, vertices.len())
+                            .ok_or_else(|| err!(parse format!("line {}: invalid vertex index '{}'", n + 1, t))))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if corners.len() < 3 {
+                        return Err(err!(parse format!("line {}: face with less than three corners", n + 1)));
+                    }
+                    for i in 1..corners.len() - 1 {
+                        indices.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                    }
+                },
+                _ => (),
+            }
+        }
+        Ok(PhysicsMesh::new(VertexBuffer::new(vertices), IndexBuffer::new(indices), Triangle {}))
+    }
+
+    /// Resolves the vertex reference of a face corner, like `3`, `3/1` or `-1//2`, to an index
+    /// into the vertices that were defined so far.
+    fn obj_index(token: &str, num_vertices: usize) -> Option<usize> {
+        let idx = token.split('/').next()?.parse::<isize>().ok()?;
+        let idx = if idx < 0 {
+            num_vertices.checked_sub(idx.unsigned_abs())?
+        } else {
+            (idx as usize).checked_sub(1)?
+        };
+        (idx < num_vertices).then_some(idx)
+    }
+}
+
+impl<T, Primitive: CollisionPrimitive<T, DIM>, const DIM: usize> PhysicsMesh<T, Primitive, DIM>
+where T: BaseFloat {
     /// Returns the vertex corresponding to the specified index id. The corresponding inner call
     /// structure is
     /// ``
@@ -65,4 +284,400 @@ impl<T, Primitive: CollisionPrimitive<T, DIM>, const DIM: usize> PhysicsMesh<T,
     pub fn vertex(&self, idx: usize) -> &SVector<T, DIM> {
         &self.vbo[self.ibo[idx]]
     }
+
+    /// Returns the amount of primitives in the mesh.
+    pub fn num_prims(&self) -> usize {
+        self.ibo.len() / self.prim.indices().len()
+    }
+
+    /// Returns the BVH that is build over the primitives of the mesh.
+    pub fn bvh(&self) -> &MeshBVH<T, DIM> {
+        &self.bvh
+    }
+
+    /// Returns the vertex buffer index of the corner `k` of the primitive with id `id`.
+    fn corner_index(&self, id: usize, k: usize) -> usize {
+        self.ibo[id * self.prim.indices().len() + self.prim.indices()[k]]
+    }
+
+    /// Returns true, if the mesh is closed and consistently wound. This is the case if every edge
+    /// is shared by exactly two primitives, which traverse it in opposite directions. Edges are
+    /// identified by the indices of their vertices in the vertex buffer, so meshes whose
+    /// neighbouring primitives use separate copies of the same vertex are not closed.
+    ///
+    /// Inside tests, like `contains`, and the signed volume are only meaningful for closed meshes.
+    pub fn is_closed(&self) -> bool {
+        let mut edges = HashMap::new();
+        for id in 0..self.num_prims() {
+            for (a, b) in self.prim.edges() {
+                *edges.entry((self.corner_index(id, *a), self.corner_index(id, *b))).or_insert(0usize) += 1;
+            }
+        }
+        edges.iter().all(|((a, b), count)| *count == 1 && edges.get(&(*b, *a)) == Some(&1))
+    }
+
+    /// Returns an iterator over the edges of the primitive with id `id`, as pairs of vertices.
+    /// This may be used for edge-based contact generation.
+    pub fn prim_edges(&self, id: usize) -> impl Iterator<Item=(&SVector<T, DIM>, &SVector<T, DIM>)> {
+        self.prim.edges().iter().map(move |(a, b)| (
+            &self.vbo[self.corner_index(id, *a)],
+            &self.vbo[self.corner_index(id, *b)],
+        ))
+    }
+}
+
+impl<T, Primitive: CollisionPrimitive<T, 3>> PhysicsMesh<T, Primitive, 3>
+where T: BaseFloat + From<u32> {
+
+    /// Returns the side on which the edge from vertex `a` to vertex `b` passes the ray. The side
+    /// is the permuted inner product of the Plücker coordinates of both lines, with the line
+    /// moments taken relative to the ray origin.
+    ///
+    /// Rays that pass exactly through the edge are assigned to a side by the order of the vertex
+    /// indices. Since neighbouring faces traverse their shared edge in opposite directions, this
+    /// guarantees that such a ray is counted for exactly one of the two faces.
+    fn edge_side(&self, ray: &Ray<T, 3>, a: usize, b: usize) -> bool {
+        let pa = self.vbo[a] - ray.origin;
+        let pb = self.vbo[b] - ray.origin;
+        let side = ray.dir.dot(&pa.cross(&pb));
+        if side == T::zero() {
+            a < b
+        } else {
+            side > T::zero()
+        }
+    }
+
+    /// Intersects the ray with the primitive `id` using its edge list. The ray crosses the
+    /// primitive only if all edges pass the ray on the same side. Only crossings that lie between
+    /// the ray origin and the distance `ray.d` are reported.
+    pub fn intersect_prim_edges(&self, id: usize, ray: &Ray<T, 3>) -> Option<RayIntersection<T, 3>> {
+        let mut edges = self.prim.edges().iter();
+        let (a, b) = edges.next()?;
+        let side = self.edge_side(ray, self.corner_index(id, *a), self.corner_index(id, *b));
+        for (a, b) in edges {
+            if self.edge_side(ray, self.corner_index(id, *a), self.corner_index(id, *b)) != side {
+                return None;
+            }
+        }
+
+        // the line of the ray crosses the primitive; find the crossing on the primitive plane
+        let p0 = self.vbo[self.corner_index(id, 0)];
+        let p1 = self.vbo[self.corner_index(id, 1)];
+        let p2 = self.vbo[self.corner_index(id, 2)];
+        let normal = (p1 - p0).cross(&(p2 - p0));
+        let denom = normal.dot(&ray.dir);
+        if denom == T::zero() {
+            return None;
+        }
+        let d = normal.dot(&(p0 - ray.origin)) / denom;
+        if d < T::zero() || d > ray.d {
+            return None;
+        }
+
+        Some(RayIntersection {
+            pos: ray.point_at(d),
+            normal: normal.normalize(),
+            prim_id: id,
+            d,
+        })
+    }
+
+    /// Intersects the ray with the mesh and returns true, if any primitive is hit. The nearest hit
+    /// is retained in the ray, which is shortened accordingly.
+    ///
+    /// Only the primitives whose bounds are hit by the ray are tested; these are found by
+    /// descending the BVH of the mesh.
+    pub fn raycast(&self, ray: &mut Ray<T, 3>) -> bool {
+        let candidates: Vec<usize> = self.bvh.intersect(ray, 0).iter()
+            .map(|el| el.prim_id)
+            .collect();
+
+        let mut hit = false;
+        for id in candidates {
+            let d = ray.d;
+            self.prim.intersect_ray(id, &self.vbo, &self.ibo, ray);
+            hit |= ray.d < d;
+        }
+        hit
+    }
+
+    /// Returns all crossings of the ray with the mesh, sorted front-to-back. The primitives that
+    /// may be hit by the ray are found by descending the BVH of the mesh.
+    ///
+    /// For watertight meshes, every crossing alternates between entering and leaving the mesh;
+    /// whether a crossing enters the mesh can be determined from the sign of the dot product
+    /// between the ray direction and the face normal.
+    pub fn raycast_all(&self, ray: &Ray<T, 3>) -> Vec<RayIntersection<T, 3>> {
+        // degenerate primitives may produce distances that are not finite, which are skipped, such
+        // that the distances of the remaining hits are totally ordered
+        let mut hits: Vec<_> = self.bvh.intersect(ray, 0).iter()
+            .filter_map(|el| self.intersect_prim_edges(el.prim_id, ray))
+            .filter(|hit| hit.d.is_finite())
+            .collect();
+        hits.sort_by(|a, b| a.d.partial_cmp(&b.d).unwrap());
+        hits
+    }
+
+    /// Returns true, if the specified point lies inside of the mesh. The test counts the crossings
+    /// of a ray, starting at the point, with the mesh. An odd parity means that the point is inside.
+    /// The result is only meaningful for watertight meshes.
+    pub fn contains(&self, point: &Vector3<T>) -> bool {
+        let ray = Ray::new(*point, Vector3::x(), T::MAX);
+        self.raycast_all(&ray).len() % 2 == 1
+    }
+
+    /// Returns the signed volume that is enclosed by the mesh. The volume is positive for meshes
+    /// whose primitives are wound counter-clockwise when viewed from the outside, and negative for
+    /// meshes with inverted winding. The result is only meaningful for closed meshes (see
+    /// `is_closed`).
+    pub fn signed_volume(&self) -> T {
+        let corners = self.prim.indices().len();
+        let six: T = nalgebra::convert(6.0);
+        (0..self.num_prims())
+            .map(|id| {
+                // sum of the tetrahedra spanned by the origin and a fan over the primitive
+                let v0 = self.vbo[self.corner_index(id, 0)];
+                (1..corners - 1).fold(T::zero(), |sum, k| {
+                    let (v1, v2) = (self.vbo[self.corner_index(id, k)], self.vbo[self.corner_index(id, k + 1)]);
+                    sum + v0.dot(&v1.cross(&v2))
+                })
+            })
+            .fold(T::zero(), |sum, v| sum + v) / six
+    }
+
+    /// Returns the point of the mesh surface that lies closest to the specified point, together
+    /// with the id of the primitive it lies on and its distance to the point. If the mesh does not
+    /// contain any primitives, `None` is returned.
+    ///
+    /// The primitives are searched best-first through the BVH of the mesh, such that only the
+    /// primitives whose bounds are closer than the closest point found so far are tested.
+    pub fn closest(&self, point: &Vector3<T>) -> Option<(usize, Vector3<T>, T)> {
+        let (el, dist) = self.bvh.nearest_by(point, |el| {
+            (self.prim.closest_point(el.prim_id, &self.vbo, &self.ibo, point) - point).norm()
+        })?;
+        let closest = self.prim.closest_point(el.prim_id, &self.vbo, &self.ibo, point);
+        Some((el.prim_id, closest, dist))
+    }
+}
+
+impl<T, Primitive: CollisionPrimitive<T, 3>> RayCast<T, 3> for PhysicsMesh<T, Primitive, 3>
+where T: BaseFloat + From<u32> {
+    fn raycast(&self, ray: &mut Ray<T, 3>) -> bool {
+        PhysicsMesh::raycast(self, ray)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+    use crate::collision::collision_primitive::{CollisionPrimitive, Triangle};
+    use crate::collision::intersection::Ray;
+    use crate::collision::model::{IndexBuffer, PhysicsMesh, VertexBuffer};
+
+    /// Triangles of the unit cube `[0, 1]^3`, which face outwards and are wound counter-clockwise.
+    const CUBE_INDICES: [usize; 36] = [
+        0, 2, 1,  1, 2, 3, // z = 0
+        4, 5, 6,  5, 7, 6, // z = 1
+        0, 4, 2,  2, 4, 6, // x = 0
+        1, 3, 5,  3, 7, 5, // x = 1
+        0, 1, 4,  1, 5, 4, // y = 0
+        2, 6, 3,  3, 6, 7, // y = 1
+    ];
+
+    /// Builds a mesh over the corners of the unit cube `[0, 1]^3` with the specified triangles.
+    fn cube_with(indices: Vec<usize>) -> PhysicsMesh<f64, Triangle, 3> {
+        let vbo = VertexBuffer::new((0..8)
+            .map(|i| Vector3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64))
+            .collect());
+        PhysicsMesh::new(vbo, IndexBuffer::new(indices), Triangle {})
+    }
+
+    /// Builds the unit cube `[0, 1]^3` with outward facing, counter-clockwise wound triangles.
+    fn cube() -> PhysicsMesh<f64, Triangle, 3> {
+        cube_with(CUBE_INDICES.to_vec())
+    }
+
+    #[test]
+    fn closed_volume() {
+        let mesh = cube();
+        assert!(mesh.is_closed());
+        assert!((mesh.signed_volume() - 1.0).abs() < 1e-12);
+
+        // inverting the winding of all triangles inverts the sign of the volume
+        let inverted = cube_with(CUBE_INDICES.chunks(3).flat_map(|t| [t[0], t[2], t[1]]).collect());
+        assert!(inverted.is_closed());
+        assert!((inverted.signed_volume() + 1.0).abs() < 1e-12);
+
+        // a single flipped triangle breaks the consistent winding, a missing one opens the mesh
+        let mut flipped = CUBE_INDICES.to_vec();
+        flipped.swap(1, 2);
+        assert!(!cube_with(flipped).is_closed());
+        assert!(!cube_with(CUBE_INDICES[3..].to_vec()).is_closed());
+    }
+
+    #[test]
+    fn raycast_closed_mesh() {
+        let mesh = cube();
+        // the ray passes exactly through the diagonals of the x = 0 and x = 1 faces
+        let ray = Ray {
+            d: f64::MAX,
+            origin: Vector3::new(-1.0, 0.5, 0.5),
+            dir: Vector3::x(),
+            intersection: None,
+        };
+        let hits = mesh.raycast_all(&ray);
+        assert_eq!(hits.len(), 2);
+
+        // front face
+        assert!((hits[0].pos - Vector3::new(0.0, 0.5, 0.5)).norm() < 1e-12);
+        assert!(hits[0].normal.dot(&ray.dir) < 0.0);
+        // back face
+        assert!((hits[1].pos - Vector3::new(1.0, 0.5, 0.5)).norm() < 1e-12);
+        assert!(hits[1].normal.dot(&ray.dir) > 0.0);
+
+        // the normal of a huge triangle overflows, such that its distance is NaN and it is skipped
+        let vbo = VertexBuffer::new(vec![
+            Vector3::new(0.0, 0.0, 0.0), Vector3::new(1e300, 0.0, 0.0), Vector3::new(0.0, 1e300, 0.0),
+            Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 1.0),
+        ]);
+        let mesh = PhysicsMesh::new(vbo, IndexBuffer::new(vec![0, 1, 2, 3, 4, 5]), Triangle {});
+        let ray = Ray {
+            d: f64::MAX,
+            origin: Vector3::new(0.25, 0.25, -1.0),
+            dir: Vector3::z(),
+            intersection: None,
+        };
+        assert!(mesh.intersect_prim_edges(0, &ray).unwrap().d.is_nan());
+        let hits = mesh.raycast_all(&ray);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].prim_id, 1);
+        assert_eq!(hits[0].d, 2.0);
+    }
+
+    #[test]
+    fn contains() {
+        let mesh = cube();
+        assert!(mesh.contains(&Vector3::new(0.5, 0.5, 0.5)));
+        assert!(mesh.contains(&Vector3::new(0.1, 0.9, 0.3)));
+        assert!(!mesh.contains(&Vector3::new(-0.5, 0.5, 0.5)));
+        assert!(!mesh.contains(&Vector3::new(2.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn closest() {
+        let mesh = cube();
+        let points = [
+            (Vector3::new(0.5, 0.5, 2.0), Vector3::new(0.5, 0.5, 1.0)),
+            (Vector3::new(2.0, 2.0, 2.0), Vector3::new(1.0, 1.0, 1.0)),
+            (Vector3::new(-1.0, 0.3, 2.0), Vector3::new(0.0, 0.3, 1.0)),
+            // points inside of the mesh are projected onto the nearest face
+            (Vector3::new(0.4, 0.5, 0.2), Vector3::new(0.4, 0.5, 0.0)),
+        ];
+        for (p, expected) in points {
+            let (id, closest, dist) = mesh.closest(&p).unwrap();
+            assert!((closest - expected).norm() < 1e-12);
+            assert!((dist - (p - expected).norm()).abs() < 1e-12);
+
+            // brute force reference over all primitives
+            let min = (0..mesh.num_prims())
+                .map(|id| (mesh.prim.closest_point(id, &mesh.vbo, &mesh.ibo, &p) - p).norm())
+                .fold(f64::MAX, f64::min);
+            assert!((dist - min).abs() < 1e-12);
+            assert!((mesh.prim.closest_point(id, &mesh.vbo, &mesh.ibo, &p) - closest).norm() < 1e-12);
+        }
+
+        let empty = PhysicsMesh::<f64, Triangle, 3>::new(VertexBuffer::new(vec![]), IndexBuffer::new(vec![]), Triangle {});
+        assert!(empty.closest(&Vector3::zeros()).is_none());
+    }
+
+    #[test]
+    fn raycast_nearest() {
+        let mesh = cube();
+        let mut ray = Ray {
+            d: f64::MAX,
+            origin: Vector3::new(0.25, 0.5, 2.0),
+            dir: -Vector3::z(),
+            intersection: None,
+        };
+        assert!(mesh.raycast(&mut ray));
+        assert_eq!(ray.d, 1.0);
+
+        // the first triangle of the z = 1 face is hit from the outside
+        let hit = ray.intersection.unwrap();
+        assert_eq!(hit.prim_id, 2);
+        assert_eq!(hit.pos, Vector3::new(0.25, 0.5, 1.0));
+        assert_eq!(hit.normal, Vector3::z());
+
+        // a ray that passes by the cube
+        let mut ray = Ray {
+            d: f64::MAX,
+            origin: Vector3::new(2.0, 0.5, 2.0),
+            dir: -Vector3::z(),
+            intersection: None,
+        };
+        assert!(!mesh.raycast(&mut ray));
+        assert!(ray.intersection.is_none());
+    }
+
+    #[test]
+    fn build_bvh() {
+        // a quad in the z = 0 plane, subdivided into 8 x 8 cells of two triangles each
+        let n = 8;
+        let vbo = VertexBuffer::new((0..(n + 1) * (n + 1))
+            .map(|i| Vector3::new((i % (n + 1)) as f64, (i / (n + 1)) as f64, 0.0))
+            .collect());
+        let ibo = IndexBuffer::new((0..n * n)
+            .flat_map(|cell| {
+                let corner = cell / n * (n + 1) + cell % n;
+                [corner, corner + 1, corner + n + 1, corner + 1, corner + n + 2, corner + n + 1]
+            })
+            .collect());
+        let mesh = PhysicsMesh::new(vbo, ibo, Triangle {});
+        let bvh = mesh.build_bvh();
+        assert_eq!(bvh.validate(), Ok(()));
+
+        // the second triangle of the cell (5, 2) covers the half of the cell above its diagonal
+        let mut ray = Ray {
+            d: f64::MAX,
+            origin: Vector3::new(5.75, 2.5, 1.0),
+            dir: -Vector3::z(),
+            intersection: None,
+        };
+        let candidates: Vec<usize> = bvh.intersect(&ray, 0).iter().map(|el| el.prim_id).collect();
+        assert!(candidates.len() < 2 * n * n);
+        let expected = 2 * (2 * n + 5) + 1;
+        assert!(candidates.contains(&expected));
+
+        assert!(mesh.raycast(&mut ray));
+        assert_eq!(ray.intersection.unwrap().prim_id, expected);
+        assert_eq!(ray.d, 1.0);
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn from_obj() {
+        let obj = "\
+            # unit cube with quad faces\n\
+            o cube\n\
+            v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+            v 0 0 1\nv 1 0 1\nv 0 1 1\nv 1 1 1\n\
+            vn 0 0 -1\n\
+            f 1//1 3//1 4//1 2//1\n\
+            f 5 6 8 7\n\
+            f 1 5 7 3\n\
+            f 2 4 8 6\n\
+            f 1 2 6 5\n\
+            f -6 -2 -1 -5\n";
+        let mesh = PhysicsMesh::<f64, Triangle, 3>::from_obj(obj.as_bytes()).ok().unwrap();
+        assert_eq!(mesh.vbo.len(), 8);
+        assert_eq!(mesh.num_prims(), 12);
+        assert!(mesh.contains(&Vector3::new(0.5, 0.5, 0.5)));
+        assert!(!mesh.contains(&Vector3::new(1.5, 0.5, 0.5)));
+
+        assert!(PhysicsMesh::<f64, Triangle, 3>::from_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes()).is_err());
+        assert!(PhysicsMesh::<f64, Triangle, 3>::from_obj("v 0 0 x\n".as_bytes()).is_err());
+        assert!(PhysicsMesh::<f64, Triangle, 3>::from_obj("v 0 0 0\nv 1 0 0\nf 1 2\n".as_bytes()).is_err());
+    }
 }
@@ -0,0 +1,315 @@
+use nalgebra::Vector3;
+use crate::collision::contact::{ContactManifold, MAX_CONTACT_POINTS};
+use crate::helper::BaseFloat;
+use crate::system::inertia::{IS, relative_point_velocity};
+
+/// Returns the inverse effective mass of the inertial system at the offset `r` from its center of
+/// mass along the direction `dir`, both in the laboratory frame. This is the change of the velocity
+/// of the point along `dir` per unit impulse along `dir`.
+pub(crate) fn inv_effective_mass<T: BaseFloat>(is: &IS<T>, r: &Vector3<T>, dir: &Vector3<T>) -> T {
+    *is.mass.inverse_mass() + dir.dot(&(is.world_inv_inertia() * r.cross(dir)).cross(r))
+}
+
+/// Resolves the contact between the inertial systems `a` and `b` by applying an impulse along the
+/// contact normal at every contact point of the manifold.
+///
+/// The normal of the manifold has to point from `a` to `b` (see `ContactManifold`). The impulses
+/// push `b` along and `a` against the normal, such that the bodies are separated. Contact points
+/// at which the bodies already move apart are skipped. The `restitution` determines how much of
+/// the approaching velocity is reflected, where zero makes the contact perfectly inelastic.
+///
+/// Static bodies have a zero inverse mass and inertia and are not moved by the impulses, such that
+/// the dynamic body takes the entire response. Contacts between two static bodies are ignored.
+pub fn resolve_contact<T: BaseFloat>(
+    a: &mut IS<T>,
+    b: &mut IS<T>,
+    manifold: &ContactManifold<T>,
+    restitution: T
+) {
+    let n = manifold.normal;
+    if a.mass.is_immovable() && b.mass.is_immovable() {
+        return;
+    }
+
+    for point in manifold.points() {
+        // relative velocity of B with respect to A along the normal; a negative value means that
+        // the bodies approach each other
+        let vn = relative_point_velocity(a, b, &point.pos).dot(&n);
+        if vn >= T::zero() {
+            continue;
+        }
+
+        let ra = point.pos - a.world_center_of_mass();
+        let rb = point.pos - b.world_center_of_mass();
+        let k = inv_effective_mass(a, &ra, &n) + inv_effective_mass(b, &rb, &n);
+        let imp = n.scale(-(T::one() + restitution) * vn / k);
+
+        b.apply_impulse_world(&imp, &point.pos);
+        a.apply_impulse_world(&-imp, &point.pos);
+    }
+}
+
+
+/// A single contact point, prepared for the iterations of the `ContactSolver`.
+struct SolverPoint<T> {
+    /// Indices of the bodies A and B in the bodies of the solver.
+    a: usize,
+    b: usize,
+    normal: Vector3<T>,
+    /// Position of the contact point in the laboratory frame.
+    pos: Vector3<T>,
+    normal_mass: T,
+    tangents: [Vector3<T>; 2],
+    tangent_mass: [T; 2],
+    /// Target separating velocity along the normal, from restitution and position correction.
+    bias: T,
+    normal_imp: T,
+    tangent_imp: [T; 2],
+}
+
+/// Iterative contact solver, which resolves many contacts at once using sequential impulses.
+///
+/// Resolving one contact after the other, like `resolve_contact` does, disturbs the contacts that
+/// were already resolved. The solver therefore iterates over all contacts several times and
+/// accumulates the impulse of every contact point. Instead of clamping each impulse on its own,
+/// only the accumulated impulses are clamped, such that later iterations may take back parts of
+/// earlier impulses. This makes stacks of bodies converge to a resting state.
+///
+/// Each iteration applies a normal impulse, followed by a Coulomb friction impulse, which is
+/// limited to `friction` times the accumulated normal impulse. Penetration is corrected through
+/// a Baumgarte bias velocity, which pushes the bodies apart by a fraction of the penetration depth
+/// that exceeds `slop` per step.
+#[derive(Clone, Debug)]
+pub struct ContactSolver<T> {
+    /// Number of passes over all contacts per step.
+    pub iterations: usize,
+    /// Fraction of the approaching velocity that is reflected.
+    pub restitution: T,
+    /// Coulomb friction coefficient.
+    pub friction: T,
+    /// Fraction of the penetration depth that is corrected per step.
+    pub baumgarte: T,
+    /// Penetration depth that is tolerated without correction, which prevents jitter of resting
+    /// contacts.
+    pub slop: T,
+}
+
+impl<T: BaseFloat> ContactSolver<T> {
+    pub fn new() -> Self {
+        ContactSolver {
+            iterations: 10,
+            restitution: T::zero(),
+            friction: nalgebra::convert(0.5),
+            baumgarte: nalgebra::convert(0.2),
+            slop: nalgebra::convert(0.005),
+        }
+    }
+
+    /// Resolves the `contacts` between the `bodies` for the time step `dt`. Each contact consists
+    /// of the indices of the bodies A and B in `bodies` and their contact manifold, whose normal
+    /// points from A to B (see `ContactManifold`).
+    ///
+    /// The impulses are applied to the momentum and angular momentum of the bodies through
+    /// `IS::apply_impulse_world`; the positions are only corrected through the velocities once the
+    /// bodies are integrated. Static bodies are not affected.
+    pub fn solve(&self, bodies: &mut [&mut IS<T>], contacts: &[(usize, usize, ContactManifold<T>)], dt: T) {
+        // prepare the contact points
+        let mut points = Vec::with_capacity(contacts.len() * MAX_CONTACT_POINTS);
+        for (a, b, manifold) in contacts {
+            let (body_a, body_b) = (&*bodies[*a], &*bodies[*b]);
+            let n = manifold.normal;
+            let tangents = tangents(&n);
+            for point in manifold.points() {
+                let ra = point.pos - body_a.world_center_of_mass();
+                let rb = point.pos - body_b.world_center_of_mass();
+                let k = inv_effective_mass(body_a, &ra, &n) + inv_effective_mass(body_b, &rb, &n);
+                if k <= T::zero() {
+                    // both bodies are static
+                    continue;
+                }
+
+                let vn = relative_point_velocity(body_a, body_b, &point.pos).dot(&n);
+                let restitution_bias = if vn < T::zero() { -self.restitution * vn } else { T::zero() };
+                let position_bias = self.baumgarte / dt * T::max(point.depth - self.slop, T::zero());
+                let tangent_mass = tangents.map(|t| {
+                    T::one() / (inv_effective_mass(body_a, &ra, &t) + inv_effective_mass(body_b, &rb, &t))
+                });
+                points.push(SolverPoint {
+                    a: *a,
+                    b: *b,
+                    normal: n,
+                    pos: point.pos,
+                    normal_mass: T::one() / k,
+                    tangents,
+                    tangent_mass,
+                    bias: T::max(restitution_bias, position_bias),
+                    normal_imp: T::zero(),
+                    tangent_imp: [T::zero(); 2],
+                });
+            }
+        }
+
+        for _ in 0..self.iterations {
+            for point in points.iter_mut() {
+                let (body_a, body_b) = pair_mut(bodies, point.a, point.b);
+                let n = point.normal;
+
+                // normal impulse; the accumulated impulse may only push the bodies apart
+                let vn = relative_point_velocity(body_a, body_b, &point.pos).dot(&n);
+                let imp = point.normal_mass * (point.bias - vn);
+                let total = T::max(point.normal_imp + imp, T::zero());
+                let imp = n.scale(total - point.normal_imp);
+                point.normal_imp = total;
+                body_b.apply_impulse_world(&imp, &point.pos);
+                body_a.apply_impulse_world(&-imp, &point.pos);
+
+                // friction impulse, limited by the friction cone
+                let max_friction = self.friction * point.normal_imp;
+                for k in 0..2 {
+                    let t = point.tangents[k];
+                    let vt = relative_point_velocity(body_a, body_b, &point.pos).dot(&t);
+                    let imp = -point.tangent_mass[k] * vt;
+                    let total = T::min(T::max(point.tangent_imp[k] + imp, -max_friction), max_friction);
+                    let imp = t.scale(total - point.tangent_imp[k]);
+                    point.tangent_imp[k] = total;
+                    body_b.apply_impulse_world(&imp, &point.pos);
+                    body_a.apply_impulse_world(&-imp, &point.pos);
+                }
+            }
+        }
+    }
+}
+
+impl<T: BaseFloat> Default for ContactSolver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns two orthonormal vectors that are perpendicular to the unit vector `n`.
+fn tangents<T: BaseFloat>(n: &Vector3<T>) -> [Vector3<T>; 2] {
+    // cross with the axis that is the least aligned with the normal
+    let axis = if <T as num::Signed>::abs(&n.x) < nalgebra::convert(0.57) {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let t1 = n.cross(&axis).normalize();
+    [t1, n.cross(&t1)]
+}
+
+/// Returns mutable references to the two distinct entries `a` and `b` of `slice`.
+fn pair_mut<E>(slice: &mut [E], a: usize, b: usize) -> (&mut E, &mut E) {
+    assert_ne!(a, b, "a body cannot be in contact with itself");
+    if a < b {
+        let (left, right) = slice.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+    use crate::collision::contact::{ContactManifold, ContactPoint};
+    use crate::collision::solver::{ContactSolver, resolve_contact};
+    use crate::system::inertia::{IS, MassDistribution, relative_point_velocity, Transformer};
+    use crate::system::object::PhyEntity;
+    use crate::test_util::{at, cube_entity};
+
+    fn cube(entity_id: usize, pos: Vector3<f64>, vel: Vector3<f64>) -> PhyEntity<f64> {
+        let mut entity = cube_entity(entity_id, pos);
+        entity.is.momentum = vel;
+        entity
+    }
+
+    #[test]
+    fn pushes_apart() {
+        // B is passed first, but has the higher id
+        let mut b = cube(1, Vector3::new(0.8, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        let mut a = cube(0, Vector3::zeros(), Vector3::new(1.0, 0.0, 0.0));
+        let contact = b.contact(&a).unwrap();
+        assert_eq!(contact.a, a.id);
+        assert!((contact.normal() - Vector3::x()).norm() < 1e-12);
+
+        // the impulses conserve the momentum and stop the first contact point; the other points
+        // are disturbed by the impulses that follow, but the approach slows down
+        let (mut resolved_a, mut resolved_b) = (a.is.clone(), b.is.clone());
+        resolve_contact(&mut resolved_a, &mut resolved_b, &contact.manifold, 0.0);
+        assert!((resolved_a.momentum + resolved_b.momentum).norm() < 1e-12);
+        assert!(resolved_b.momentum.x - resolved_a.momentum.x > -0.1);
+
+        // the solver resolves all contact points together
+        ContactSolver::new().solve(&mut [&mut a.is, &mut b.is], &[(0, 1, contact.manifold.clone())], 0.1);
+        for point in contact.points() {
+            assert!(relative_point_velocity(&a.is, &b.is, &point.pos).x >= -1e-6);
+        }
+
+        // moving on separates the bodies
+        for _ in 0..10 {
+            a.is.integrate(0.1);
+            b.is.integrate(0.1);
+        }
+        assert!(b.is.state.pos.x - a.is.state.pos.x >= 0.8);
+    }
+
+    #[test]
+    fn static_body() {
+        let mut floor = cube(0, Vector3::zeros(), Vector3::zeros());
+        floor.is.mass = MassDistribution::immovable();
+        let mut b = cube(1, Vector3::new(0.1, 0.9, 0.0), Vector3::new(0.0, -1.0, 0.0));
+
+        // impulses applied directly to a static body have no effect
+        floor.is.apply_impulse(&Vector3::new(1.0, 2.0, 3.0), &Vector3::new(0.5, 0.5, 0.5));
+        assert_eq!(floor.is.momentum, Vector3::zeros());
+        assert_eq!(floor.is.angular_mom, Vector3::zeros());
+
+        let contact = floor.contact(&b).unwrap();
+        assert!((contact.normal() - Vector3::y()).norm() < 1e-12);
+        resolve_contact(&mut floor.is, &mut b.is, &contact.manifold, 0.5);
+        assert_eq!(floor.is.momentum, Vector3::zeros());
+        assert_eq!(floor.is.angular_mom, Vector3::zeros());
+        // the dynamic body takes the entire response
+        assert!(b.is.momentum.y > 0.0);
+
+        // a static body does not move, even with momentum
+        floor.is.momentum = Vector3::new(1.0, 0.0, 0.0);
+        floor.is.integrate(1.0);
+        assert_eq!(floor.is.state.pos, Vector3::zeros());
+    }
+
+    #[test]
+    fn rotated_body() {
+        // B is rotated about two axes and has a different moment of inertia about each axis, such
+        // that its angular momentum differs between the laboratory frame and its own frame
+        let floor = || IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), MassDistribution::immovable());
+        let inertia = Matrix3::from_diagonal(&Vector3::new(0.5, 1.0, 2.0));
+        let mass = MassDistribution::new(1.0, Vector3::zeros(), inertia).ok().unwrap();
+        let rot = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.5);
+        let state = at(Vector3::new(0.0, 1.0, 0.0), rot);
+        let body = || IS::new(Vector3::new(0.0, -1.0, 0.0), Vector3::zeros(), state.clone(), mass.clone());
+
+        // off-center contact point below B
+        let mut manifold = ContactManifold::new(Vector3::y());
+        let pos = Vector3::new(0.3, 0.5, 0.2);
+        manifold.add_point(ContactPoint { pos, depth: 0.01 });
+
+        // a perfectly inelastic contact stops the contact point along the normal
+        let (mut a, mut b) = (floor(), body());
+        resolve_contact(&mut a, &mut b, &manifold, 0.0);
+        assert!(relative_point_velocity(&a, &b, &pos).y.abs() < 1e-12);
+        assert!(b.angular_mom.norm() > 0.1);
+
+        // without friction and position correction, the solver finds the same impulse
+        let solver = ContactSolver { friction: 0.0, baumgarte: 0.0, ..ContactSolver::new() };
+        let (mut solved_a, mut solved_b) = (floor(), body());
+        solver.solve(&mut [&mut solved_a, &mut solved_b], &[(0, 1, manifold)], 1.0 / 60.0);
+        assert!(relative_point_velocity(&solved_a, &solved_b, &pos).y.abs() < 1e-12);
+        assert!((solved_b.momentum - b.momentum).norm() < 1e-12);
+        assert!((solved_b.angular_mom - b.angular_mom).norm() < 1e-12);
+    }
+}
@@ -1,11 +1,19 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::mem;
 use std::ops::{Index, IndexMut};
 use std::sync::Arc;
+use nalgebra::Vector3;
 use crate::collision::Collider;
+use crate::collision::constraint::DistanceConstraint;
+use crate::collision::contact::shape_shape;
+use crate::collision::solver::ContactSolver;
 use crate::helper::BaseFloat;
 use crate::system::object::{PhyEntity, PhyEntityID};
 use crate::volume::bvh::VecPool;
-use crate::volume::tlas::{TLAS, TLASElement, TLASNode};
+use crate::volume::aabb::AABB;
+use crate::volume::oriented::OBB;
+use crate::volume::tlas::{TLAS, TLASElement, TLASNode, TLASPool};
 use parking_lot::{RawRwLock, RwLock};
 use parking_lot::lock_api::{RwLockReadGuard, RwLockWriteGuard};
 
@@ -50,24 +58,528 @@ impl<T: BaseFloat> Default for PERef<T> {
 pub static mut PHYSICS_ENGINE : PERef<f64> = PERef { arc: None };
 
 
+/// TLAS over the entities of a single world of the engine (see `PhysicsEngine::worlds`).
+pub type PhyWorld<T> = TLAS<T, PhyEntity<T>, VecPool<TLASNode<T, 3>>, VecPool<PhyEntity<T>>, 3>;
+
+/// Slot of an entity id in the engine. The generation of the slot is increased every time the
+/// entity in the slot is removed, which invalidates all ids that still refer to the old entity.
+struct Slot {
+    generation: u32,
+    /// World of the entity in the slot.
+    world_id: u8,
+    /// Index of the entity in the BLAS of its world, or `None` if the id is free.
+    blas_index: Option<usize>,
+}
+
+/// Change of the overlap state of a pair of entities between two simulation steps, see
+/// `PhysicsEngine::contact_events`. The first id of each pair is always the lower one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContactEvent {
+    /// The bounding volumes of both entities started to overlap.
+    Begin(PhyEntityID, PhyEntityID),
+    /// The bounding volumes of both entities stopped to overlap, or one of the entities was
+    /// removed.
+    End(PhyEntityID, PhyEntityID),
+}
+
 pub struct PhysicsEngine<T: BaseFloat> {
     collider: HashMap<usize, Box<dyn Collider<T, 3>>>,
-    pub world: TLAS<T, PhyEntity<T>, VecPool<TLASNode<T, 3>>, VecPool<PhyEntity<T>>, 3>
+    /// Global gravitational acceleration that acts on all bodies in the world.
+    pub gravity: Vector3<T>,
+    /// The TLAS of each world, keyed by the `world_id` of the entities. Entities of different
+    /// worlds live in separate acceleration structures, such that they never collide with each
+    /// other or show up in each other's queries. A world is created when the first entity is
+    /// spawned into it. Entities should be added through `spawn` and removed through
+    /// `remove_entity`, as these keep the entity ids in sync with the BLAS.
+    pub worlds: HashMap<u8, PhyWorld<T>>,
+    /// Solver for the contacts between the entities, which is run once per simulation step.
+    pub solver: ContactSolver<T>,
+    /// Constraints between the entities, which are solved after the contacts in every simulation
+    /// step.
+    pub constraints: Vec<DistanceConstraint<T>>,
+    /// Number of times all constraints are solved per simulation step. More iterations improve the
+    /// accuracy of chains of constraints, like ropes and ragdolls, which act on the same bodies.
+    pub constraint_iterations: usize,
+    /// Duration of a single simulation step, see `step`.
+    pub fixed_dt: T,
+    /// Simulation time that has not been simulated yet, as it is shorter than `fixed_dt`.
+    accumulator: T,
+    /// Maps each entity id to the index of the entity in the BLAS and the current generation of
+    /// the id.
+    slots: Vec<Slot>,
+    /// Entity ids of removed entities, which are reused by the next spawned entities.
+    free_ids: Vec<usize>,
+    /// Sorted pairs of overlapping entities of the last simulation step, which are compared to
+    /// the pairs of the next step to generate the contact events.
+    contact_pairs: Vec<(PhyEntityID, PhyEntityID)>,
+    /// Contact events of the last call to `step`.
+    contact_events: Vec<ContactEvent>,
 }
 
 impl<T: BaseFloat> PhysicsEngine<T> {
     pub fn new() -> Self {
         PhysicsEngine {
             collider: HashMap::new(),
-            world: TLAS::new(64),
+            gravity: Vector3::new(T::zero(), nalgebra::convert(-9.81), T::zero()),
+            worlds: HashMap::new(),
+            solver: ContactSolver::new(),
+            constraints: Vec::new(),
+            constraint_iterations: 4,
+            fixed_dt: nalgebra::convert(1.0 / 60.0),
+            accumulator: T::zero(),
+            slots: Vec::new(),
+            free_ids: Vec::new(),
+            contact_pairs: Vec::new(),
+            contact_events: Vec::new(),
+        }
+    }
+
+    /// Adds the entity to the world `world_id` of its id and returns its id. The `entity_id` and
+    /// `generation` of the entity are assigned by the engine, reusing the ids of removed entities
+    /// first with a new generation; the world and chunk ids of the entity are kept. Entity ids are
+    /// unique across all worlds.
+    ///
+    /// The TLAS is not rebuilt by this method, but is flagged as dirty, so that it is rebuilt
+    /// once at the start of the next simulation step. This way, many entities can be spawned
+    /// without rebuilding the tree for each of them. To include the entity in intersection queries
+    /// before the next step, call `build`, or spawn the entities through `spawn_batch`.
+    pub fn spawn(&mut self, mut entity: PhyEntity<T>) -> PhyEntityID {
+        let entity_id = match self.free_ids.pop() {
+            Some(entity_id) => entity_id,
+            None => {
+                self.slots.push(Slot { generation: 0, world_id: 0, blas_index: None });
+                self.slots.len() - 1
+            }
+        };
+        let world_id = entity.id.world_id;
+        let world = self.worlds.entry(world_id).or_insert_with(|| TLAS::new(64));
+        let slot = &mut self.slots[entity_id];
+        slot.world_id = world_id;
+        slot.blas_index = Some(world.blas().size());
+        entity.id.entity_id = entity_id;
+        entity.id.generation = slot.generation;
+
+        let id = entity.id.clone();
+        world.blas_mut().push(entity);
+        // the new element is not part of the tree yet, which flags the TLAS as dirty
+        world.update_leaf(world.blas().size() - 1);
+        id
+    }
+
+    /// Adds all entities to the world like `spawn` and returns their ids in the same order. The
+    /// TLAS is rebuilt once after all entities were added, such that they are immediately included
+    /// in intersection queries.
+    pub fn spawn_batch(&mut self, entities: impl IntoIterator<Item = PhyEntity<T>>) -> Vec<PhyEntityID> {
+        let ids = entities.into_iter()
+            .map(|entity| self.spawn(entity))
+            .collect();
+        self.build();
+        ids
+    }
+
+    /// Removes the entity with the specified id from the world and returns it. The ids of all
+    /// other entities stay valid, while the id of the removed entity is freed and may be reused
+    /// with a new generation by entities that are spawned later on. Returns `None`, if there is no
+    /// entity with the id, or if the id is stale.
+    ///
+    /// The TLAS stays intact, but is rebuilt at the start of the next simulation step (see
+    /// `TLAS::remove_blas`).
+    pub fn remove_entity(&mut self, id: PhyEntityID) -> Option<PhyEntity<T>> {
+        let blas_index = self.try_blas_index(&id)?;
+        let slot = &mut self.slots[id.entity_id];
+        slot.blas_index = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        let world = self.worlds.get_mut(&id.world_id)?;
+        let entity = world.remove_blas(blas_index);
+
+        // the last entity in the BLAS moved into the gap
+        if blas_index < world.blas().size() {
+            let moved = world.blas()[blas_index].id.entity_id;
+            self.slots[moved].blas_index = Some(blas_index);
         }
+        self.free_ids.push(id.entity_id);
+        Some(entity)
     }
 
+    /// Removes the entity with the specified id from the world, like `remove_entity`, and drops
+    /// it. Returns true, if the entity was alive.
+    pub fn despawn(&mut self, id: PhyEntityID) -> bool {
+        self.remove_entity(id).is_some()
+    }
+
+    /// Returns the amount of entities in all worlds.
+    pub fn len(&self) -> usize {
+        self.worlds.values().map(|world| world.blas().size()).sum()
+    }
+
+    /// Returns true, if there are no entities in any world.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entities and constraints from all worlds, like for a level transition. The
+    /// cached contact pairs are dropped as well, without reporting the ends of their contacts. The
+    /// allocations of the TLAS and BLAS of each world and of the id slots are kept, such that the
+    /// worlds can be filled again without reallocating. The settings of the engine, like the
+    /// gravity and the solver, are kept as well.
+    ///
+    /// The ids of all removed entities become stale, like with `remove_entity`, so ids from
+    /// before the reset never refer to entities that are spawned afterwards.
+    pub fn clear(&mut self) {
+        for world in self.worlds.values_mut() {
+            world.clear();
+        }
+        self.constraints.clear();
+        self.accumulator = T::zero();
+        self.contact_pairs.clear();
+        self.contact_events.clear();
+        self.free_ids.clear();
+        for (entity_id, slot) in self.slots.iter_mut().enumerate() {
+            if slot.blas_index.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+            self.free_ids.push(entity_id);
+        }
+    }
+
+    /// Returns true, if the id refers to an entity that is alive. Ids of removed entities are
+    /// stale, even if their entity id was reused by a newer entity.
+    pub fn contains(&self, id: &PhyEntityID) -> bool {
+        self.try_blas_index(id).is_some()
+    }
+
+    /// Returns the entity with the specified id, or `None` if the id is stale or unknown.
+    pub fn get(&self, id: &PhyEntityID) -> Option<&PhyEntity<T>> {
+        self.try_blas_index(id).map(|blas_index| &self.worlds[&id.world_id].blas()[blas_index])
+    }
+
+    /// Returns the entity with the specified id mutably, or `None` if the id is stale or unknown.
+    pub fn get_mut(&mut self, id: &PhyEntityID) -> Option<&mut PhyEntity<T>> {
+        let blas_index = self.try_blas_index(id)?;
+        Some(&mut self.worlds.get_mut(&id.world_id)?.blas_mut()[blas_index])
+    }
+
+    /// Returns the two distinct entities with the specified ids mutably, or `None` if either id is
+    /// stale or unknown, or if both ids refer to the same entity.
+    pub fn get_pair_mut(
+        &mut self,
+        a: &PhyEntityID,
+        b: &PhyEntityID
+    ) -> Option<(&mut PhyEntity<T>, &mut PhyEntity<T>)> {
+        let (ia, ib) = (self.try_blas_index(a)?, self.try_blas_index(b)?);
+        if a.world_id != b.world_id {
+            let [wa, wb] = self.worlds.get_disjoint_mut([&a.world_id, &b.world_id]);
+            return Some((&mut wa?.blas_mut()[ia], &mut wb?.blas_mut()[ib]));
+        }
+        let vec = &mut self.worlds.get_mut(&a.world_id)?.blas_mut().vec;
+        if ia < ib {
+            let (low, high) = vec.split_at_mut(ib);
+            Some((&mut low[ia], &mut high[0]))
+        } else if ib < ia {
+            let (low, high) = vec.split_at_mut(ia);
+            Some((&mut high[0], &mut low[ib]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the index of the entity with the specified id in the BLAS, if the entity is alive.
+    fn try_blas_index(&self, id: &PhyEntityID) -> Option<usize> {
+        self.slots.get(id.entity_id)
+            .filter(|slot| slot.generation == id.generation && slot.world_id == id.world_id)
+            .and_then(|slot| slot.blas_index)
+    }
+
+    /// Returns the index of the entity with the specified id in the BLAS of its world.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if there is no entity with the id or if the id is stale.
+    fn blas_index(&self, id: &PhyEntityID) -> usize {
+        match self.try_blas_index(id) {
+            Some(blas_index) => blas_index,
+            None => match self.slots.get(id.entity_id) {
+                Some(slot) if slot.generation != id.generation => panic!(
+                    "Stale entity id {} (generation {}, current generation {}) in the physics engine",
+                    id.entity_id, id.generation, slot.generation
+                ),
+                _ => panic!("No entity with id {} in the physics engine", id.entity_id),
+            },
+        }
+    }
+
+    /// Advances the simulation by the time `dt` and returns the interpolation factor for the time
+    /// that is left over.
+    ///
+    /// The time is accumulated internally and the simulation only ever advances in sub-steps of
+    /// exactly `fixed_dt`; zero or more sub-steps are run per call. Time that is left over is
+    /// carried over to the next call. The returned factor in `[0, 1)` is the fraction of a
+    /// sub-step that is left over, which can be used to interpolate between the last two states
+    /// for rendering.
+    ///
+    /// Since every sub-step uses the same time step, the state of the simulation only depends on
+    /// the number of sub-steps that were run, not on how the time was split between the calls.
+    /// Splitting the same total time differently yields bit-identical results, as long as the
+    /// accumulated time covers the same number of sub-steps. Note that the accumulated time itself
+    /// is subject to rounding, so time steps that are exact fractions of `fixed_dt` in binary
+    /// (e.g. powers of two) should be preferred when exact reproducibility is required.
+    pub fn step(&mut self, dt: T) -> T {
+        self.advance(dt, self.fixed_dt)
+    }
+
+    /// Advances the simulation by the frame time `frame_dt` in sub-steps of `fixed_dt`, like
+    /// `step`, and returns the interpolation factor for the time that is left over. The fixed time
+    /// step only applies to this call and the `fixed_dt` of the engine is left unchanged. The
+    /// left over time is carried over to the next call regardless of its time step, so the fixed
+    /// time step should be the same for all calls.
+    ///
+    /// A fixed time step that is not positive and finite cannot make progress, so the simulation
+    /// is not advanced and zero is returned.
+    pub fn advance(&mut self, frame_dt: T, fixed_dt: T) -> T {
+        self.contact_events.clear();
+        if !(fixed_dt > T::zero() && fixed_dt.is_finite()) {
+            return T::zero();
+        }
+        self.accumulator += frame_dt;
+        while self.accumulator >= fixed_dt {
+            self.substep(fixed_dt);
+            self.accumulator -= fixed_dt;
+        }
+        self.accumulator / fixed_dt
+    }
+
+    /// Runs a single simulation step over the time `dt`. The TLAS of each world is rebuilt first,
+    /// if entities were spawned into or removed from it since the last build, and the contact
+    /// events of the step are generated from the overlapping pairs. Gravity is applied to all
+    /// entities and the contacts and constraints between them are resolved. The dynamic entities
+    /// are then integrated, before the TLAS is refit to their new bounds.
+    fn substep(&mut self, dt: T) {
+        for world in self.worlds.values_mut().filter(|world| world.is_dirty()) {
+            world.build();
+        }
+        self.update_contact_pairs();
+        self.apply_gravity(dt);
+        self.solve_contacts(dt);
+        self.solve_constraints(dt);
+        for world in self.worlds.values_mut() {
+            let blas = world.blas_mut();
+            for i in 0..blas.size() {
+                let entity = &mut blas[i];
+                if entity.is_static {
+                    continue;
+                }
+                entity.is.integrate(dt);
+                entity.sync();
+            }
+            world.refit();
+        }
+    }
+
+    /// Rebuilds the TLAS of every world, such that entities that were spawned since the last build
+    /// are included in intersection queries (see `TLAS::build`).
+    pub fn build(&mut self) {
+        for world in self.worlds.values_mut() {
+            world.build();
+        }
+    }
+
+    /// Refits the TLAS of every world to the current bounds of its entities, after entities were
+    /// moved outside of a simulation step (see `TLAS::refit`).
+    pub fn refit(&mut self) {
+        for world in self.worlds.values_mut() {
+            world.refit();
+        }
+    }
+
+    /// Generates the contacts between all overlapping entities and resolves them with the contact
+    /// solver of the engine for the time step `dt`. The contacts of each world are solved
+    /// separately. The TLAS of every world has to be up to date.
+    pub fn solve_contacts(&mut self, dt: T) {
+        for world in self.worlds.values_mut() {
+            let blas = world.blas();
+            let contacts = world.overlapping_pairs().into_iter()
+                .filter_map(|(i, j)| {
+                    let contact = blas[i].contact(&blas[j])?;
+                    // body A of the contact is the entity with the lower id
+                    let (a, b) = if blas[i].id == contact.a { (i, j) } else { (j, i) };
+                    Some((a, b, contact.manifold))
+                })
+                .collect::<Vec<_>>();
+            if contacts.is_empty() {
+                continue;
+            }
+
+            // static entities take part as immovable bodies at rest, such that the impulses of the
+            // solver do not pile up in their momentum
+            let entities = &mut world.blas_mut().vec;
+            let mut static_bodies = entities.iter()
+                .filter(|entity| entity.is_static)
+                .map(PhyEntity::static_body)
+                .collect::<Vec<_>>();
+            let mut static_bodies = static_bodies.iter_mut();
+            let mut bodies = entities.iter_mut()
+                .map(|entity| match entity.is_static {
+                    true => static_bodies.next().unwrap(),
+                    false => &mut entity.is,
+                })
+                .collect::<Vec<_>>();
+            self.solver.solve(&mut bodies, &contacts, dt);
+        }
+    }
+
+    /// Solves all constraints of the engine `constraint_iterations` times for a single step of the
+    /// time `dt`.
+    pub fn solve_constraints(&mut self, dt: T) {
+        let constraints = mem::take(&mut self.constraints);
+        for _ in 0..self.constraint_iterations {
+            for constraint in &constraints {
+                constraint.solve(self, dt);
+            }
+        }
+        self.constraints = constraints;
+    }
+
+    /// Applies the global gravity over the time step `dt` to the momentum of all bodies in the
+    /// world. The gravity is scaled by the `gravity_scale` of each body. Static bodies are not
+    /// affected.
+    pub fn apply_gravity(&mut self, dt: T) {
+        for world in self.worlds.values_mut() {
+            let blas = world.blas_mut();
+            for i in 0..blas.size() {
+                let entity = &mut blas[i];
+                if entity.is_static || entity.is.mass.is_immovable() {
+                    continue;
+                }
+                let scale = *entity.is.mass.mass() * entity.gravity_scale * dt;
+                entity.is.momentum += self.gravity.scale(scale);
+            }
+        }
+    }
+
+    /// Returns the ids of all pairs of entities whose bounding boxes overlap. The TLAS is only
+    /// traversed once, every pair is reported exactly once and entities are never paired with
+    /// themselves. The first id of each pair is always the lower one, which is the body A of the
+    /// contact normal convention (see `ContactManifold`). Entities of different worlds are never
+    /// paired.
+    ///
+    /// The TLAS of every world has to be up to date for the pairs to be accurate.
+    pub fn collision_pairs(&self) -> Vec<(PhyEntityID, PhyEntityID)> {
+        self.worlds.values()
+            .flat_map(|world| {
+                let blas = world.blas();
+                world.overlapping_pairs().into_iter().map(move |(a, b)| {
+                    let (a, b) = (blas[a].id.clone(), blas[b].id.clone());
+                    if a < b { (a, b) } else { (b, a) }
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the contact events of the last call to `step`, in the order of the sub-steps they
+    /// occurred in. Pairs of entities whose bounding volumes start to overlap report a
+    /// `ContactEvent::Begin`, and pairs that stop to overlap report a `ContactEvent::End`. This
+    /// allows reacting to entities entering and leaving each other, instead of polling the
+    /// overlapping pairs every step.
+    ///
+    /// The events are based on the bounding volumes of the entities, like `collision_pairs`.
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        &self.contact_events
+    }
+
+    /// Compares the overlapping pairs of the current state of the world to the cached pairs of the
+    /// last sub-step and records the differences as contact events. Both pair lists are sorted,
+    /// such that they are compared in a single pass over the pairs.
+    fn update_contact_pairs(&mut self) {
+        let mut pairs = self.collision_pairs();
+        pairs.sort_unstable();
+
+        let prev = &self.contact_pairs;
+        let (mut i, mut j) = (0, 0);
+        while i < prev.len() || j < pairs.len() {
+            let ordering = match (prev.get(i), pairs.get(j)) {
+                (Some(p), Some(n)) => p.cmp(n),
+                (Some(_), None) => Ordering::Less,
+                (None, _) => Ordering::Greater,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let (a, b) = prev[i].clone();
+                    self.contact_events.push(ContactEvent::End(a, b));
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    let (a, b) = pairs[j].clone();
+                    self.contact_events.push(ContactEvent::Begin(a, b));
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        self.contact_pairs = pairs;
+    }
+
+    /// Returns the bounds of all entities in the world `world_id`, or the empty box of
+    /// `AABB::new()` if there are no entities in the world (see `TLAS::scene_bounds`).
+    pub fn world_bounds(&self, world_id: u8) -> AABB<T, 3> {
+        self.worlds.get(&world_id).map_or_else(AABB::new, |world| world.scene_bounds())
+    }
+
+    /// Returns the entities whose bounding volumes overlap the bounding volume of the entity `id`.
+    /// Only the world of the entity is searched. The entity itself is part of the results.
+    ///
+    /// The results borrow the engine, such that they cannot outlive a lock on a shared engine (see
+    /// `PERef`). Use `query_collider_ids` to keep the results after the lock is released.
     pub fn query_colliders(&self, id: PhyEntityID) -> Vec<&PhyEntity<T>> {
-        let header = &self.world.blas()[id.entity_id];
-        let colliders = self.world
-            .intersect(header.bounding_volume(), 0);
-        colliders
+        let header = &self[id.clone()];
+        self.worlds[&id.world_id].intersect(header.bounding_volume(), 0)
+    }
+
+    /// Returns the ids of the entities whose bounding volumes overlap the bounding volume of the
+    /// entity `id`, like `query_colliders`.
+    ///
+    /// Unlike the entity references of `query_colliders`, the ids do not borrow the engine. This
+    /// allows to release a read lock on a shared engine right after the query and to look up the
+    /// entities again later, for example under a short write lock. Entities may be removed in the
+    /// meantime, so the ids have to be checked through `contains` or `get` before they are used.
+    pub fn query_collider_ids(&self, id: PhyEntityID) -> Vec<PhyEntityID> {
+        self.query_colliders(id).into_iter()
+            .map(|entity| entity.id.clone())
+            .collect()
+    }
+
+    /// Returns the entities of the world `world_id` whose collision shapes overlap the specified
+    /// box. Unlike `query_colliders`, the box does not have to belong to an entity, which allows
+    /// to check a placement before spawning an entity there, or to find the entities inside of a
+    /// trigger area.
+    pub fn query_obb(&self, world_id: u8, obb: &OBB<T>) -> Vec<&PhyEntity<T>> {
+        self.worlds.get(&world_id).map_or_else(Vec::new, |world| world.intersect(obb, 0))
+    }
+
+    /// Returns the entities whose collision shapes overlap the shape of the entity `id`, each paired
+    /// with the minimum translation vector that separates it from the entity `id`. The vector is
+    /// the translation of the other entity along the axis of least penetration; moving the entity
+    /// `id` by the negated vector separates both entities just as well. Touching entities are
+    /// reported with a zero vector. The entity itself is not part of the results.
+    pub fn query_overlaps(&self, id: PhyEntityID) -> Vec<(PhyEntityID, Vector3<T>)> {
+        let entity = &self[id.clone()];
+        self.worlds[&id.world_id].intersect(entity.bounding_volume(), 0).into_iter()
+            .filter(|other| other.id != entity.id)
+            .filter_map(|other| {
+                let manifold = shape_shape(entity.bounding_volume(), other.bounding_volume())?;
+                Some((other.id.clone(), manifold.normal.scale(manifold.depth())))
+            })
+            .collect()
+    }
+
+    /// Pushes the entities whose bounding volumes overlap the bounding volume of the entity `id`
+    /// into `out`, like `query_colliders`. The entity itself is part of the results. Reusing `out`
+    /// for many queries avoids allocating a result vector for each of them (see `QueryBuffer`).
+    pub fn query_colliders_into<'a>(&'a self, id: PhyEntityID, out: &mut Vec<&'a PhyEntity<T>>) {
+        let header = &self[id.clone()];
+        self.worlds[&id.world_id].intersect_into(header.bounding_volume(), 0, out);
     }
 }
 
@@ -75,13 +587,15 @@ impl<T: BaseFloat> Index<PhyEntityID> for PhysicsEngine<T> {
     type Output = PhyEntity<T>;
 
     fn index(&self, index: PhyEntityID) -> &Self::Output {
-        &self.world.blas()[index.entity_id]
+        &self.worlds[&index.world_id].blas()[self.blas_index(&index)]
     }
 }
 
 impl<T: BaseFloat> IndexMut<PhyEntityID> for PhysicsEngine<T> {
     fn index_mut(&mut self, index: PhyEntityID) -> &mut Self::Output {
-        &mut self.world.blas_mut()[index.entity_id]
+        let blas_index = self.blas_index(&index);
+        let world = self.worlds.get_mut(&index.world_id).expect("the world of a live entity exists");
+        &mut world.blas_mut()[blas_index]
     }
 }
 
@@ -102,3 +616,516 @@ impl PhysicsEngine<f64> {
         }
     }
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::collision::constraint::DistanceConstraint;
+    use crate::engine::{ContactEvent, PERef, PhysicsEngine};
+    use crate::system::inertia::MassDistribution;
+    use crate::system::object::{PhyEntity, PhyEntityBuilder, PhyEntityID};
+    use crate::test_util::{cube_entity, cuboid, id};
+    use crate::volume::aabb::AABB;
+    use crate::volume::oriented::OBB;
+    use crate::volume::QueryBuffer;
+    use crate::volume::tlas::TLASPool;
+
+    #[test]
+    fn gravity_scale() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let falling = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        let mut entity = PhyEntity::cube(id(0), Vector3::repeat(1.0));
+        entity.gravity_scale = 0.0;
+        let floating = engine.spawn(entity);
+        let mut entity = PhyEntity::cube(id(0), Vector3::repeat(1.0));
+        entity.gravity_scale = -0.5;
+        let rising = engine.spawn(entity);
+        // static entities are not accelerated, even without an immovable mass distribution
+        let mut entity = PhyEntity::cube(id(0), Vector3::repeat(1.0));
+        entity.is_static = true;
+        let fixed = engine.spawn(entity);
+
+        for _ in 0..10 {
+            engine.apply_gravity(0.1);
+            for id in [&falling, &floating, &rising] {
+                engine[id.clone()].is.integrate(0.1);
+            }
+        }
+
+        assert!(engine[falling].is.state.pos.y < 0.0);
+        assert_eq!(engine[floating].is.state.pos, Vector3::zeros());
+        assert!(engine[rising].is.state.pos.y > 0.0);
+        assert_eq!(engine[fixed].is.momentum, Vector3::zeros());
+    }
+
+    #[test]
+    fn remove_entity() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let ids: Vec<_> = (0..3)
+            .map(|i| engine.spawn(cube_entity(0, Vector3::new(i as f64 * 2.0, 0.0, 0.0))))
+            .collect();
+        engine.build();
+
+        let removed = engine.remove_entity(ids[1].clone()).unwrap();
+        assert_eq!(removed.id, ids[1]);
+        assert!(engine.remove_entity(ids[1].clone()).is_none());
+
+        // the remaining entities are still addressable by their ids
+        assert_eq!(engine[ids[0].clone()].id, ids[0]);
+        assert_eq!(engine[ids[0].clone()].is.state.pos.x, 0.0);
+        assert_eq!(engine[ids[2].clone()].id, ids[2]);
+        assert_eq!(engine[ids[2].clone()].is.state.pos.x, 4.0);
+        assert_eq!(engine.query_colliders(ids[2].clone()).len(), 1);
+
+        // the freed id is reused with a new generation, which keeps the old id stale
+        let respawned = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        assert_eq!(respawned.entity_id, ids[1].entity_id);
+        assert_eq!(respawned.generation, ids[1].generation + 1);
+        assert_eq!(engine[ids[2].clone()].id, ids[2]);
+        assert!(engine.contains(&respawned));
+        assert!(!engine.contains(&ids[1]));
+        assert!(engine.get(&ids[1]).is_none());
+        assert!(!engine.despawn(ids[1].clone()));
+        assert_eq!(engine.get(&respawned).unwrap().id, respawned);
+
+        assert!(engine.despawn(respawned.clone()));
+        assert!(engine.get_mut(&respawned).is_none());
+        assert_eq!(engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0))).generation, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stale entity id")]
+    fn stale_id() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let stale = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        engine.despawn(stale.clone());
+        engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        let _ = &engine[stale];
+    }
+
+    #[test]
+    fn collision_pairs() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        // a cluster of four overlapping cubes and a separate one
+        let positions = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.9, 0.0, 0.0),
+            Vector3::new(0.0, 0.9, 0.0),
+            Vector3::new(0.9, 0.9, 0.0),
+            Vector3::new(5.0, 0.0, 0.0),
+        ];
+        let ids: Vec<_> = positions.iter()
+            .map(|pos| engine.spawn(cube_entity(0, *pos)))
+            .collect();
+        engine.build();
+
+        let mut pairs = engine.collision_pairs();
+        pairs.sort();
+        let mut expected = Vec::new();
+        for a in 0..4 {
+            for b in (a + 1)..4 {
+                expected.push((ids[a].clone(), ids[b].clone()));
+            }
+        }
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn contact_events() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let cube = |x: f64| PhyEntityBuilder::new(PhyEntity::cube(id(0), Vector3::repeat(1.0)))
+            .position(Vector3::new(x, 0.0, 0.0))
+            .mass(MassDistribution::immovable())
+            .build();
+        let a = engine.spawn(cube(0.0));
+        let b = engine.spawn(cube(0.9));
+        let c = engine.spawn(cube(5.0));
+        let dt = engine.fixed_dt;
+
+        engine.step(dt);
+        assert_eq!(engine.contact_events(), [ContactEvent::Begin(a.clone(), b.clone())]);
+        // pairs that keep overlapping are only reported once
+        engine.step(dt);
+        assert!(engine.contact_events().is_empty());
+
+        let mut teleport = |id: &PhyEntityID, x: f64| {
+            let entity = engine.get_mut(id).unwrap();
+            entity.is.state.pos = Vector3::new(x, 0.0, 0.0);
+            entity.sync();
+        };
+        teleport(&a, -5.0);
+        teleport(&c, 1.8);
+        engine.refit();
+        engine.step(dt);
+        assert_eq!(engine.contact_events(), [
+            ContactEvent::End(a.clone(), b.clone()),
+            ContactEvent::Begin(b.clone(), c.clone()),
+        ]);
+
+        // removing an entity ends its contacts
+        engine.despawn(b.clone());
+        engine.step(dt);
+        assert_eq!(engine.contact_events(), [ContactEvent::End(b, c)]);
+    }
+
+    #[test]
+    fn query_buffer() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let ids: Vec<_> = (0..8)
+            .map(|i| engine.spawn(cube_entity(0, Vector3::new(i as f64 * 0.75, 0.0, 0.0))))
+            .collect();
+        engine.build();
+
+        let mut buffer = QueryBuffer::new();
+        let mut capacity = 0;
+        for frame in 0..3 {
+            for id in &ids {
+                buffer.with(|out| {
+                    engine.query_colliders_into(id.clone(), out);
+                    let expected = engine.query_colliders(id.clone());
+                    assert_eq!(out.len(), expected.len());
+                    assert!(out.iter().all(|e| expected.iter().any(|x| x.id == e.id)));
+                });
+            }
+            // the allocation of the first frame is reused afterwards
+            if frame > 0 {
+                assert_eq!(buffer.capacity(), capacity);
+            }
+            capacity = buffer.capacity();
+        }
+        assert!(capacity >= 3);
+    }
+
+    #[test]
+    fn clear() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let spawn_cubes = |engine: &mut PhysicsEngine<f64>| -> Vec<PhyEntityID> {
+            let entities = (0..100).map(|i| cube_entity(0, Vector3::new(i as f64 * 2.0, 0.0, 0.0)));
+            engine.spawn_batch(entities)
+        };
+        let ids = spawn_cubes(&mut engine);
+        engine.step(1.0 / 60.0);
+        assert_eq!(engine.len(), 100);
+        let capacity = (engine.worlds[&0].blas().capacity(), engine.worlds[&0].nodes().capacity());
+
+        engine.clear();
+        assert!(engine.is_empty());
+        assert_eq!((engine.worlds[&0].blas().capacity(), engine.worlds[&0].nodes().capacity()), capacity);
+        assert!(engine.world_bounds(0).min.x > engine.world_bounds(0).max.x);
+        assert!(ids.iter().all(|id| !engine.contains(id)));
+        engine.step(1.0 / 60.0);
+
+        // the world can be filled again without reallocating, and the old ids stay stale
+        let new_ids = spawn_cubes(&mut engine);
+        assert_eq!(engine.len(), 100);
+        assert_eq!((engine.worlds[&0].blas().capacity(), engine.worlds[&0].nodes().capacity()), capacity);
+        assert!(ids.iter().all(|id| !engine.contains(id)));
+        assert!(new_ids.iter().all(|id| engine.contains(id)));
+        assert_eq!(engine.query_obb(0, &OBB::from(AABB { min: Vector3::repeat(-0.25), max: Vector3::repeat(0.25) })).len(), 1);
+    }
+
+    #[test]
+    fn world_bounds() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let empty = engine.world_bounds(0);
+        assert!(empty.min.x > empty.max.x);
+
+        for x in [-3.0, 5.0] {
+            engine.spawn(cube_entity(0, Vector3::new(x, 1.0, 0.0)));
+        }
+        let bounds = engine.world_bounds(0);
+        assert!((bounds.min - Vector3::new(-3.5, 0.5, -0.5)).norm() < 1e-12);
+        assert!((bounds.max - Vector3::new(5.5, 1.5, 0.5)).norm() < 1e-12);
+        engine.build();
+        assert_eq!(engine.world_bounds(0).max, bounds.max);
+    }
+
+    #[test]
+    fn query_collider_ids() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let ids: Vec<_> = (0..4)
+            .map(|i| engine.spawn(cube_entity(0, Vector3::new(i as f64 * 0.75, 0.0, 0.0))))
+            .collect();
+        engine.build();
+        let shared = PERef::new(engine);
+
+        // the ids outlive the read lock of the query
+        let colliders = shared.lock().query_collider_ids(ids[1].clone());
+        {
+            let engine = shared.lock();
+            let mut expected: Vec<_> = engine.query_colliders(ids[1].clone()).iter()
+                .map(|entity| entity.id.clone())
+                .collect();
+            expected.sort();
+            let mut actual = colliders.clone();
+            actual.sort();
+            assert_eq!(actual, expected);
+            assert_eq!(actual, ids[..3]);
+        }
+
+        let mut engine = shared.lock_mut();
+        for id in &colliders {
+            engine[id.clone()].is.state.pos.y += 1.0;
+        }
+        assert_eq!(engine[ids[0].clone()].is.state.pos.y, 1.0);
+        assert_eq!(engine[ids[3].clone()].is.state.pos.y, 0.0);
+    }
+
+    #[test]
+    fn query_overlaps() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let positions = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.8, 0.1, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(5.0, 0.0, 0.0),
+        ];
+        let ids: Vec<_> = positions.iter()
+            .map(|pos| engine.spawn(cube_entity(0, *pos)))
+            .collect();
+        engine.build();
+
+        let mut overlaps = engine.query_overlaps(ids[0].clone());
+        overlaps.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(overlaps.len(), 2);
+        assert_eq!(overlaps[0].0, ids[1]);
+        assert!((overlaps[0].1 - Vector3::new(0.2, 0.0, 0.0)).norm() < 1e-12);
+        // the cube on top only touches the first one
+        assert_eq!(overlaps[1].0, ids[2]);
+        assert!(overlaps[1].1.norm() < 1e-12);
+
+        // pushing the second cube out along the vector separates it from the first one
+        engine[ids[1].clone()].is.state.pos += overlaps[0].1 + Vector3::new(1e-9, 0.0, 0.0);
+        engine[ids[1].clone()].sync();
+        engine.build();
+        assert!(engine.query_overlaps(ids[0].clone()).iter().all(|(id, _)| *id != ids[1]));
+        assert!(engine.query_overlaps(ids[3].clone()).is_empty());
+    }
+
+    #[test]
+    fn query_obb() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let cube = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        let mut sphere = PhyEntity::sphere(id(0), 0.5);
+        sphere.is.state.pos = Vector3::new(3.0, 0.0, 0.0);
+        sphere.sync();
+        let sphere = engine.spawn(sphere);
+        engine.build();
+
+        let placement = |pos: Vector3<f64>, angle: f64| {
+            cuboid(pos, UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle), Vector3::new(1.0, 0.25, 0.25))
+        };
+        let ids = |obb: &OBB<f64>| -> Vec<_> {
+            engine.query_obb(0, obb).iter().map(|e| e.id.clone()).collect()
+        };
+
+        // free space between both entities
+        assert!(ids(&placement(Vector3::new(1.5, 0.0, 0.0), std::f64::consts::FRAC_PI_2)).is_empty());
+        assert_eq!(ids(&placement(Vector3::new(1.5, 0.0, 0.0), 0.0)).len(), 2);
+        assert_eq!(ids(&placement(Vector3::new(1.4, 0.6, 0.0), 0.0)), vec![cube]);
+        // the box overlaps the bounding box of the sphere, but not the sphere itself
+        assert!(ids(&placement(Vector3::new(3.65, 0.0, 0.65), std::f64::consts::FRAC_PI_2)).is_empty());
+        assert_eq!(ids(&placement(Vector3::new(3.0, 0.6, 0.0), std::f64::consts::FRAC_PI_2)), vec![sphere]);
+    }
+
+    #[test]
+    fn spawn_batch() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let floor = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        let ids = engine.spawn_batch((0..4).map(|i| cube_entity(0, Vector3::new(i as f64 * 0.75, 0.0, 0.0))));
+
+        // the ids are assigned in order and all entities are part of the tree
+        let expected: Vec<_> = (1..5).map(id).collect();
+        assert_eq!(ids, expected);
+        assert!(!engine.worlds[&0].is_dirty());
+        assert_eq!(engine.query_colliders(ids[1].clone()).len(), 4);
+        assert_eq!(engine.query_colliders(floor.clone()).len(), 3);
+
+        // single spawns are included by the next step
+        let late = engine.spawn(PhyEntity::cube(id(0), Vector3::repeat(1.0)));
+        assert!(engine.worlds[&0].is_dirty());
+        assert!(engine.query_colliders(floor.clone()).len() < 4);
+        engine.gravity = Vector3::zeros();
+        engine.step(engine.fixed_dt);
+        assert!(!engine.worlds[&0].is_dirty());
+        assert!(engine.query_colliders(late).len() >= 3);
+    }
+
+    #[test]
+    fn pendulum() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let mut pivot = PhyEntity::cube(id(0), Vector3::repeat(0.5));
+        pivot.is.mass = MassDistribution::immovable();
+        let pivot = engine.spawn(pivot);
+        let mut bob = PhyEntity::cube(id(0), Vector3::repeat(0.5));
+        bob.is.state.pos = Vector3::new(3.0, 0.0, 0.0);
+        bob.sync();
+        let bob = engine.spawn(bob);
+        engine.constraints.push(DistanceConstraint::new(
+            pivot.clone(), bob.clone(), 2.0, Vector3::zeros(), Vector3::zeros()
+        ));
+
+        // the bob is pulled onto the circle around the pivot and swings through the bottom
+        let mut lowest = 0.0f64;
+        for _ in 0..120 {
+            engine.step(engine.fixed_dt);
+            let pos = engine[bob.clone()].is.state.pos;
+            assert!(pos.norm() < 3.0 + 1e-9);
+            lowest = lowest.min(pos.y);
+        }
+        let pos = engine[bob.clone()].is.state.pos;
+        assert!((pos.norm() - 2.0).abs() < 0.05, "{pos}");
+        assert!(lowest < -1.9, "{lowest}");
+        assert_eq!(engine[pivot.clone()].is.state.pos, Vector3::zeros());
+
+        // constraints of removed entities are skipped
+        engine.despawn(bob);
+        engine.step(engine.fixed_dt);
+        assert_eq!(engine.constraints.len(), 1);
+    }
+
+    fn falling_cubes() -> (PhysicsEngine<f64>, Vec<PhyEntityID>) {
+        let mut engine = PhysicsEngine::<f64>::new();
+        engine.fixed_dt = 0.125;
+        let ids = (0..3)
+            .map(|i| {
+                let mut entity = PhyEntity::cube(id(0), Vector3::repeat(1.0));
+                entity.is.state.pos = Vector3::new(i as f64 * 2.0, 10.0, 0.0);
+                entity.is.momentum = Vector3::new(0.5, 0.0, -0.25 * i as f64);
+                entity.is.angular_mom = Vector3::new(0.1, 0.2 * i as f64, 0.0);
+                entity.sync();
+                engine.spawn(entity)
+            })
+            .collect();
+        engine.build();
+        (engine, ids)
+    }
+
+    #[test]
+    fn fixed_step() {
+        let (mut large, ids) = falling_cubes();
+        let (mut small, _) = falling_cubes();
+
+        assert_eq!(large.step(1.0), 0.0);
+        for _ in 0..8 {
+            assert_eq!(small.step(0.125), 0.0);
+        }
+        for id in &ids {
+            assert_eq!(large[id.clone()].is.state.pos, small[id.clone()].is.state.pos);
+            assert_eq!(large[id.clone()].is.state.rot, small[id.clone()].is.state.rot);
+            assert_eq!(large[id.clone()].is.momentum, small[id.clone()].is.momentum);
+        }
+        assert!(large[ids[0].clone()].is.state.pos.y < 10.0);
+
+        // left over time is carried over to the next step
+        let pos = large[ids[0].clone()].is.state.pos;
+        assert_eq!(large.step(0.0625), 0.5);
+        assert_eq!(large[ids[0].clone()].is.state.pos, pos);
+        assert_eq!(large.step(0.0625), 0.0);
+        assert_ne!(large[ids[0].clone()].is.state.pos, pos);
+    }
+
+    #[test]
+    fn advance() {
+        let (mut stepped, ids) = falling_cubes();
+        let (mut advanced, _) = falling_cubes();
+        advanced.fixed_dt = 1.0;
+
+        // frame times of varying length run the same fixed steps
+        stepped.step(1.0);
+        for frame_dt in [0.3125, 0.5, 0.0625, 0.125] {
+            advanced.advance(frame_dt, 0.125);
+        }
+        // the fixed time step of the call does not replace the one of the engine
+        assert_eq!(advanced.fixed_dt, 1.0);
+        assert_eq!(advanced.advance(0.0625, 0.125), 0.5);
+        for id in &ids {
+            assert_eq!(stepped[id.clone()].is.state.pos, advanced[id.clone()].is.state.pos);
+            assert_eq!(stepped[id.clone()].is.momentum, advanced[id.clone()].is.momentum);
+        }
+
+        // invalid fixed time steps neither hang nor advance the simulation
+        let pos = advanced[ids[0].clone()].is.state.pos;
+        for fixed_dt in [0.0, -0.125, f64::NAN, f64::INFINITY] {
+            assert_eq!(advanced.advance(1.0, fixed_dt), 0.0);
+            advanced.fixed_dt = fixed_dt;
+            assert_eq!(advanced.step(1.0), 0.0);
+        }
+        assert_eq!(advanced[ids[0].clone()].is.state.pos, pos);
+    }
+
+    #[test]
+    fn resting_contact() {
+        // the floor is held in place by an immovable mass distribution, or is flagged static
+        // without the builder, such that it keeps the finite mass of the cube constructor
+        for is_static in [false, true] {
+            let mut engine = PhysicsEngine::<f64>::new();
+            let mut floor = PhyEntity::cube(id(0), Vector3::new(20.0, 1.0, 20.0));
+            if is_static {
+                floor.is_static = true;
+            } else {
+                floor.is.mass = MassDistribution::immovable();
+            }
+            floor.sync();
+            let floor = engine.spawn(floor);
+
+            let cube = engine.spawn(cube_entity(0, Vector3::new(0.3, 2.0, -0.2)));
+            engine.build();
+
+            // drop the cube onto the floor and let it settle
+            let mut lowest = f64::MAX;
+            for _ in 0..240 {
+                engine.step(1.0 / 60.0);
+                lowest = f64::min(lowest, engine[cube.clone()].is.state.pos.y);
+            }
+
+            // the cube rests on top of the floor, at y = 1, without sinking in or bouncing off
+            let is = &engine[cube].is;
+            assert!(lowest > 0.9, "{lowest}");
+            assert!((is.state.pos.y - 1.0).abs() < 0.02, "{}", is.state.pos.y);
+            assert!(is.momentum.norm() < 1e-2, "{}", is.momentum);
+            assert!(is.angular_mom.norm() < 1e-2, "{}", is.angular_mom);
+            assert!((is.state.pos.xz() - Vector3::new(0.3, 0.0, -0.2).xz()).norm() < 1e-3, "{}", is.state.pos);
+            // the contact impulses do not pile up in the momentum of the floor
+            assert_eq!(engine[floor.clone()].is.state.pos, Vector3::zeros());
+            assert_eq!(engine[floor].is.momentum, Vector3::zeros());
+        }
+    }
+
+    #[test]
+    fn worlds() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        let cube = |world_id: u8| PhyEntityBuilder::new(PhyEntity::cube(
+            PhyEntityID { world_id, ..id(0) },
+            Vector3::repeat(1.0),
+        )).build();
+        let a = engine.spawn(cube(0));
+        let b = engine.spawn(cube(1));
+        assert_ne!(a.entity_id, b.entity_id);
+        assert_eq!(engine.len(), 2);
+        assert_eq!(engine.worlds[&0].blas().size(), 1);
+        assert_eq!(engine.worlds[&1].blas().size(), 1);
+
+        // the cubes overlap, but live in different worlds
+        engine.build();
+        assert!(engine.collision_pairs().is_empty());
+        let colliders = engine.query_colliders(a.clone());
+        assert_eq!(colliders.len(), 1);
+        assert_eq!(colliders[0].id, a);
+        assert_eq!(engine.query_obb(1, &OBB::from(AABB { min: Vector3::repeat(-0.25), max: Vector3::repeat(0.25) })).len(), 1);
+        assert!(engine.query_obb(2, &OBB::from(AABB { min: Vector3::repeat(-0.25), max: Vector3::repeat(0.25) })).is_empty());
+
+        // no contacts are solved between the worlds, so both cubes fall alike
+        engine.step(1.0 / 60.0);
+        assert!(engine.contact_events().is_empty());
+        assert_eq!(engine[a.clone()].is.state.pos, engine[b.clone()].is.state.pos);
+
+        // an id is only valid in the world of its entity
+        assert!(!engine.contains(&PhyEntityID { world_id: 1, ..a.clone() }));
+        assert!(engine.remove_entity(a).is_some());
+        assert_eq!(engine.worlds[&0].blas().size(), 0);
+        assert!(engine.contains(&b));
+    }
+}
@@ -6,6 +6,9 @@ use nalgebra::SimdRealField;
 use num::{One, Zero};
 use crate::helper::mat::{Half, Two};
 
+#[cfg(feature = "f16")]
+pub mod f16;
+pub mod gjk;
 pub mod mat;
 pub mod separated_axis;
 
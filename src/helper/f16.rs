@@ -0,0 +1,502 @@
+//! Half precision floats as `BaseFloat`, which halve the memory of acceleration structures over
+//! huge scenes at the cost of precision.
+//!
+//! Neither nalgebra nor simba implement their scalar traits for `half::f16`, and the orphan rule
+//! prevents implementing foreign traits (`RealField`, `ComplexField`, `SimdValue`, `SubsetOf`, the
+//! `approx` traits and the `num` traits) for the foreign type. `F16` therefore wraps the half
+//! float in a newtype. Arithmetic is done by `half`, while all transcendental functions are
+//! evaluated in `f32` and rounded back to half precision.
+//!
+//! Half floats have an epsilon of about 1e-3 and cannot represent values beyond 65504, so they are
+//! only suited for data that is stored at low precision, like the bounding boxes of a BVH.
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign};
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+use half::f16;
+use nalgebra::{ComplexField, Field, RealField, SimdValue};
+use num::{FromPrimitive, Num, One, Signed, Zero};
+use simba::scalar::SubsetOf;
+use crate::helper::BaseFloat;
+use crate::helper::mat::Half;
+
+/// Half precision float, which implements `BaseFloat`.
+#[derive(Clone, Copy, Default, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct F16(pub f16);
+
+impl F16 {
+    pub fn from_f32(value: f32) -> Self {
+        F16(f16::from_f32(value))
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0.to_f32()
+    }
+
+    fn map(self, f: impl FnOnce(f32) -> f32) -> Self {
+        F16::from_f32(f(self.to_f32()))
+    }
+}
+
+impl From<u32> for F16 {
+    /// Converts the integer to the nearest half float; values beyond 65504 become infinite.
+    fn from(value: u32) -> Self {
+        F16::from_f32(value as f32)
+    }
+}
+
+impl Debug for F16 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for F16 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+macro_rules! impl_op {
+    ($($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident;)*) => {$(
+        impl $Op for F16 {
+            type Output = Self;
+
+            fn $op(self, rhs: Self) -> Self {
+                F16(self.0.$op(rhs.0))
+            }
+        }
+
+        impl $OpAssign for F16 {
+            fn $op_assign(&mut self, rhs: Self) {
+                self.0.$op_assign(rhs.0)
+            }
+        }
+    )*};
+}
+
+impl_op!(
+    Add, add, AddAssign, add_assign;
+    Sub, sub, SubAssign, sub_assign;
+    Mul, mul, MulAssign, mul_assign;
+    Div, div, DivAssign, div_assign;
+    Rem, rem, RemAssign, rem_assign;
+);
+
+impl Neg for F16 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        F16(-self.0)
+    }
+}
+
+impl Zero for F16 {
+    fn zero() -> Self {
+        F16(f16::ZERO)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == f16::ZERO
+    }
+}
+
+impl One for F16 {
+    fn one() -> Self {
+        F16(f16::ONE)
+    }
+}
+
+impl Half for F16 {
+    fn half() -> Self {
+        F16(f16::from_f32(0.5))
+    }
+}
+
+impl Num for F16 {
+    type FromStrRadixErr = <f32 as Num>::FromStrRadixErr;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f32::from_str_radix(str, radix).map(F16::from_f32)
+    }
+}
+
+impl Signed for F16 {
+    fn abs(&self) -> Self {
+        self.map(f32::abs)
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        if *self <= *other { F16::zero() } else { *self - *other }
+    }
+
+    fn signum(&self) -> Self {
+        self.map(f32::signum)
+    }
+
+    fn is_positive(&self) -> bool {
+        self.0.is_sign_positive()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0.is_sign_negative()
+    }
+}
+
+impl FromPrimitive for F16 {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(F16::from_f32(n as f32))
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(F16::from_f32(n as f32))
+    }
+
+    fn from_f32(n: f32) -> Option<Self> {
+        Some(F16::from_f32(n))
+    }
+
+    fn from_f64(n: f64) -> Option<Self> {
+        Some(F16(f16::from_f64(n)))
+    }
+}
+
+impl AbsDiffEq for F16 {
+    type Epsilon = Self;
+
+    fn default_epsilon() -> Self {
+        F16(f16::EPSILON)
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self) -> bool {
+        self.to_f32().abs_diff_eq(&other.to_f32(), epsilon.to_f32())
+    }
+}
+
+impl RelativeEq for F16 {
+    fn default_max_relative() -> Self {
+        F16(f16::EPSILON)
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: Self, max_relative: Self) -> bool {
+        self.to_f32().relative_eq(&other.to_f32(), epsilon.to_f32(), max_relative.to_f32())
+    }
+}
+
+impl UlpsEq for F16 {
+    fn default_max_ulps() -> u32 {
+        4
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: Self, max_ulps: u32) -> bool {
+        if self.abs_diff_eq(other, epsilon) {
+            return true;
+        }
+        if self.0.is_sign_positive() != other.0.is_sign_positive() {
+            return false;
+        }
+        // floats of the same sign are ordered like the magnitude of their bits
+        let (a, b) = (self.0.to_bits(), other.0.to_bits());
+        u32::from(a.abs_diff(b)) <= max_ulps
+    }
+}
+
+impl SimdValue for F16 {
+    type Element = Self;
+    type SimdBool = bool;
+
+    fn lanes() -> usize {
+        1
+    }
+
+    fn splat(val: Self) -> Self {
+        val
+    }
+
+    fn extract(&self, _: usize) -> Self {
+        *self
+    }
+
+    unsafe fn extract_unchecked(&self, _: usize) -> Self {
+        *self
+    }
+
+    fn replace(&mut self, _: usize, val: Self) {
+        *self = val
+    }
+
+    unsafe fn replace_unchecked(&mut self, _: usize, val: Self) {
+        *self = val
+    }
+
+    fn select(self, cond: bool, other: Self) -> Self {
+        if cond { self } else { other }
+    }
+}
+
+impl Field for F16 {}
+
+impl SubsetOf<F16> for F16 {
+    fn to_superset(&self) -> F16 {
+        *self
+    }
+
+    fn from_superset_unchecked(element: &F16) -> Self {
+        *element
+    }
+
+    fn is_in_subset(_: &F16) -> bool {
+        true
+    }
+}
+
+impl SubsetOf<F16> for f64 {
+    fn to_superset(&self) -> F16 {
+        F16(f16::from_f64(*self))
+    }
+
+    fn from_superset_unchecked(element: &F16) -> Self {
+        element.0.to_f64()
+    }
+
+    fn is_in_subset(_: &F16) -> bool {
+        true
+    }
+}
+
+/// Implements methods of `ComplexField` and `RealField`, which take and return `Self` only, by
+/// evaluating the same method of `f32`.
+macro_rules! via_f32 {
+    ($($name:ident),* $(,)?) => {$(
+        fn $name(self) -> Self {
+            self.map(f32::$name)
+        }
+    )*};
+}
+
+impl ComplexField for F16 {
+    type RealField = Self;
+
+    fn from_real(re: Self) -> Self {
+        re
+    }
+
+    fn real(self) -> Self {
+        self
+    }
+
+    fn imaginary(self) -> Self {
+        F16::zero()
+    }
+
+    fn modulus(self) -> Self {
+        self.map(f32::abs)
+    }
+
+    fn modulus_squared(self) -> Self {
+        self * self
+    }
+
+    fn argument(self) -> Self {
+        if self >= F16::zero() { F16::zero() } else { F16::pi() }
+    }
+
+    fn norm1(self) -> Self {
+        self.map(f32::abs)
+    }
+
+    fn scale(self, factor: Self) -> Self {
+        self * factor
+    }
+
+    fn unscale(self, factor: Self) -> Self {
+        self / factor
+    }
+
+    via_f32!(floor, ceil, round, trunc, fract, abs, signum, recip);
+    via_f32!(sin, cos, tan, asin, acos, atan, sinh, cosh, tanh, asinh, acosh, atanh);
+    via_f32!(log2, log10, ln, ln_1p, sqrt, exp, exp2, exp_m1, cbrt);
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self.map(|x| x.mul_add(a.to_f32(), b.to_f32()))
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        self.map(|x| x.hypot(other.to_f32()))
+    }
+
+    fn conjugate(self) -> Self {
+        self
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        let (sin, cos) = self.to_f32().sin_cos();
+        (F16::from_f32(sin), F16::from_f32(cos))
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.map(|x| x.log(base.to_f32()))
+    }
+
+    fn powi(self, n: i32) -> Self {
+        self.map(|x| x.powi(n))
+    }
+
+    fn powf(self, n: Self) -> Self {
+        self.map(|x| x.powf(n.to_f32()))
+    }
+
+    fn powc(self, n: Self) -> Self {
+        self.powf(n)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.0.is_finite()
+    }
+
+    fn try_sqrt(self) -> Option<Self> {
+        (self >= F16::zero()).then(|| self.map(f32::sqrt))
+    }
+}
+
+impl RealField for F16 {
+    fn is_sign_positive(&self) -> bool {
+        self.0.is_sign_positive()
+    }
+
+    fn is_sign_negative(&self) -> bool {
+        self.0.is_sign_negative()
+    }
+
+    fn copysign(self, sign: Self) -> Self {
+        F16(self.0.copysign(sign.0))
+    }
+
+    fn max(self, other: Self) -> Self {
+        F16(self.0.max(other.0))
+    }
+
+    fn min(self, other: Self) -> Self {
+        F16(self.0.min(other.0))
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self {
+        F16(self.0.clamp(min.0, max.0))
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        self.map(|x| x.atan2(other.to_f32()))
+    }
+
+    fn min_value() -> Option<Self> {
+        Some(F16(f16::MIN))
+    }
+
+    fn max_value() -> Option<Self> {
+        Some(F16(f16::MAX))
+    }
+
+    fn pi() -> Self {
+        F16(f16::PI)
+    }
+
+    fn two_pi() -> Self {
+        F16::from_f32(std::f32::consts::TAU)
+    }
+
+    fn frac_pi_2() -> Self {
+        F16(f16::FRAC_PI_2)
+    }
+
+    fn frac_pi_3() -> Self {
+        F16(f16::FRAC_PI_3)
+    }
+
+    fn frac_pi_4() -> Self {
+        F16(f16::FRAC_PI_4)
+    }
+
+    fn frac_pi_6() -> Self {
+        F16(f16::FRAC_PI_6)
+    }
+
+    fn frac_pi_8() -> Self {
+        F16(f16::FRAC_PI_8)
+    }
+
+    fn frac_1_pi() -> Self {
+        F16(f16::FRAC_1_PI)
+    }
+
+    fn frac_2_pi() -> Self {
+        F16(f16::FRAC_2_PI)
+    }
+
+    fn frac_2_sqrt_pi() -> Self {
+        F16(f16::FRAC_2_SQRT_PI)
+    }
+
+    fn e() -> Self {
+        F16(f16::E)
+    }
+
+    fn log2_e() -> Self {
+        F16(f16::LOG2_E)
+    }
+
+    fn log10_e() -> Self {
+        F16(f16::LOG10_E)
+    }
+
+    fn ln_2() -> Self {
+        F16(f16::LN_2)
+    }
+
+    fn ln_10() -> Self {
+        F16(f16::LN_10)
+    }
+}
+
+impl BaseFloat for F16 {
+    const MIN: Self = F16(f16::MIN);
+    const MAX: Self = F16(f16::MAX);
+
+    fn floor_to_u32(self) -> u32 {
+        self.to_f32() as u32
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+    use crate::helper::f16::F16;
+    use crate::volume::aabb::AABB;
+    use crate::volume::BoundingVolume;
+
+    #[test]
+    fn aabb_wraps_points() {
+        let points = [
+            Vector3::new(1.1, -2.3, 0.7),
+            Vector3::new(-3.9, 4.2, 0.1),
+            Vector3::new(0.3, 1.7, -6.6),
+            Vector3::new(2.5, 0.0, 3.3),
+        ];
+        let aabb = AABB::from_points(&points.map(|p| p.map(F16::from_f32)));
+
+        // the points are rounded on conversion, so the box wraps them within half precision
+        let (min, max) = (aabb.min.map(F16::to_f32), aabb.max.map(F16::to_f32));
+        for p in &points {
+            for i in 0..3 {
+                let tol = p[i].abs() * f32::from(half::f16::EPSILON);
+                assert!(min[i] <= p[i] + tol && p[i] - tol <= max[i], "{p} is not wrapped");
+            }
+        }
+        assert!((min - Vector3::new(-3.9, -2.3, -6.6)).amax() < 1e-2);
+        assert!((max - Vector3::new(2.5, 4.2, 3.3)).amax() < 1e-2);
+
+        let center = aabb.center().map(F16::to_f32);
+        assert!((center - Vector3::new(-0.7, 0.95, -1.65)).amax() < 1e-2);
+    }
+}
@@ -0,0 +1,360 @@
+use nalgebra::Vector3;
+use num::Signed;
+use crate::helper::BaseFloat;
+
+/// Maximal number of iterations of GJK and EPA, which guards against cycling due to rounding.
+const MAX_ITERATIONS: usize = 64;
+
+/// A vertex of the Minkowski difference `A - B`, together with the support points of both shapes
+/// that it was generated from.
+#[derive(Clone, Copy, Debug)]
+struct SupportPoint<T> {
+    p: Vector3<T>,
+    a: Vector3<T>,
+    b: Vector3<T>,
+}
+
+/// Returns the support point of the Minkowski difference `A - B` in the direction `dir`.
+fn support<T, SA, SB>(support_a: &SA, support_b: &SB, dir: &Vector3<T>) -> SupportPoint<T>
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    let a = support_a(dir);
+    let b = support_b(&-dir);
+    SupportPoint { p: a - b, a, b }
+}
+
+fn same_direction<T: BaseFloat>(a: &Vector3<T>, b: &Vector3<T>) -> bool {
+    a.dot(b) > T::zero()
+}
+
+/// Returns the support point of the convex set of `points` in the direction `dir`, which is the
+/// point with the largest projection onto `dir`.
+pub fn support_points<T: BaseFloat>(points: &[Vector3<T>], dir: &Vector3<T>) -> Vector3<T> {
+    points.iter()
+        .fold((T::MIN, Vector3::zeros()), |(best, best_p), p| {
+            let d = p.dot(dir);
+            if d > best { (d, *p) } else { (best, best_p) }
+        }).1
+}
+
+/// Tests, if the convex shapes A and B intersect using the Gilbert-Johnson-Keerthi algorithm. Both
+/// shapes are only defined through their support functions, which return the point of the shape
+/// that lies farthest in the specified direction. Touching shapes count as intersecting.
+pub fn intersects<T, SA, SB>(support_a: SA, support_b: SB) -> bool
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    gjk(&support_a, &support_b).is_some()
+}
+
+/// Returns the penetration of the intersecting convex shapes A and B, using the expanding polytope
+/// algorithm on the simplex found by GJK. The result consists of the unit normal, which points
+/// from A to B, the penetration depth along the normal and a contact point halfway between the
+/// deepest points of both shapes. Moving B by the depth along the normal separates the shapes.
+///
+/// Returns `None` if the shapes do not intersect, or if the penetration cannot be determined
+/// because the Minkowski difference of the shapes is flat.
+pub fn penetration<T, SA, SB>(support_a: SA, support_b: SB) -> Option<(Vector3<T>, T, Vector3<T>)>
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    let simplex = gjk(&support_a, &support_b)?;
+    let polytope = expand_simplex(&support_a, &support_b, simplex)?;
+    epa(&support_a, &support_b, polytope)
+}
+
+/// Runs GJK and returns the final simplex, if the Minkowski difference contains the origin. The
+/// simplex may contain fewer than four points if the origin lies on one of its features.
+fn gjk<T, SA, SB>(support_a: &SA, support_b: &SB) -> Option<Vec<SupportPoint<T>>>
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    let first = support(support_a, support_b, &Vector3::x());
+    let mut dir = -first.p;
+    // the newest point is always the first point of the simplex
+    let mut simplex = vec![first];
+
+    for _ in 0..MAX_ITERATIONS {
+        if dir.norm_squared() <= T::default_epsilon() {
+            // the origin lies on the current simplex
+            return Some(simplex);
+        }
+        let p = support(support_a, support_b, &dir);
+        if p.p.dot(&dir) < T::zero() {
+            // the origin lies beyond the farthest point in the direction of the origin
+            return None;
+        }
+        simplex.insert(0, p);
+        if next_simplex(&mut simplex, &mut dir) {
+            return Some(simplex);
+        }
+    }
+    None
+}
+
+/// Reduces the simplex to the feature closest to the origin and updates the search direction
+/// towards the origin. Returns true, if the simplex is a tetrahedron that contains the origin.
+fn next_simplex<T: BaseFloat>(simplex: &mut Vec<SupportPoint<T>>, dir: &mut Vector3<T>) -> bool {
+    match simplex.len() {
+        2 => line(simplex, dir),
+        3 => triangle(simplex, dir),
+        _ => tetrahedron(simplex, dir),
+    }
+}
+
+fn line<T: BaseFloat>(simplex: &mut Vec<SupportPoint<T>>, dir: &mut Vector3<T>) -> bool {
+    let (a, b) = (simplex[0], simplex[1]);
+    let ab = b.p - a.p;
+    let ao = -a.p;
+    if same_direction(&ab, &ao) {
+        *dir = ab.cross(&ao).cross(&ab);
+    } else {
+        *simplex = vec![a];
+        *dir = ao;
+    }
+    false
+}
+
+fn triangle<T: BaseFloat>(simplex: &mut Vec<SupportPoint<T>>, dir: &mut Vector3<T>) -> bool {
+    let (a, b, c) = (simplex[0], simplex[1], simplex[2]);
+    let ab = b.p - a.p;
+    let ac = c.p - a.p;
+    let ao = -a.p;
+    let abc = ab.cross(&ac);
+
+    if same_direction(&abc.cross(&ac), &ao) {
+        if same_direction(&ac, &ao) {
+            *simplex = vec![a, c];
+            *dir = ac.cross(&ao).cross(&ac);
+            false
+        } else {
+            *simplex = vec![a, b];
+            line(simplex, dir)
+        }
+    } else if same_direction(&ab.cross(&abc), &ao) {
+        *simplex = vec![a, b];
+        line(simplex, dir)
+    } else if same_direction(&abc, &ao) {
+        *dir = abc;
+        false
+    } else {
+        *simplex = vec![a, c, b];
+        *dir = -abc;
+        false
+    }
+}
+
+fn tetrahedron<T: BaseFloat>(simplex: &mut Vec<SupportPoint<T>>, dir: &mut Vector3<T>) -> bool {
+    let (a, b, c, d) = (simplex[0], simplex[1], simplex[2], simplex[3]);
+    let ab = b.p - a.p;
+    let ac = c.p - a.p;
+    let ad = d.p - a.p;
+    let ao = -a.p;
+
+    if same_direction(&ab.cross(&ac), &ao) {
+        *simplex = vec![a, b, c];
+        triangle(simplex, dir)
+    } else if same_direction(&ac.cross(&ad), &ao) {
+        *simplex = vec![a, c, d];
+        triangle(simplex, dir)
+    } else if same_direction(&ad.cross(&ab), &ao) {
+        *simplex = vec![a, d, b];
+        triangle(simplex, dir)
+    } else {
+        true
+    }
+}
+
+/// Expands a simplex that contains the origin to a tetrahedron with a non-zero volume, which is
+/// needed as the initial polytope of EPA. Returns `None` if no such tetrahedron exists.
+fn expand_simplex<T, SA, SB>(
+    support_a: &SA,
+    support_b: &SB,
+    mut simplex: Vec<SupportPoint<T>>
+) -> Option<Vec<SupportPoint<T>>>
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    let eps = T::default_epsilon().sqrt();
+    let axes = [Vector3::x(), Vector3::y(), Vector3::z()];
+
+    while simplex.len() < 4 {
+        // search directions in which a new point extends the simplex
+        let dirs = match simplex.len() {
+            1 => axes.iter().flat_map(|a| [*a, -a]).collect::<Vec<_>>(),
+            2 => {
+                let line = simplex[1].p - simplex[0].p;
+                axes.iter()
+                    .map(|a| line.cross(a))
+                    .filter(|d| d.norm_squared() > eps)
+                    .flat_map(|d| [d, -d])
+                    .collect()
+            },
+            _ => {
+                let n = (simplex[1].p - simplex[0].p).cross(&(simplex[2].p - simplex[0].p));
+                vec![n, -n]
+            },
+        };
+
+        let extends = |p: &SupportPoint<T>| match simplex.len() {
+            1 => (p.p - simplex[0].p).norm() > eps,
+            2 => (p.p - simplex[0].p).cross(&(simplex[1].p - simplex[0].p)).norm() > eps,
+            _ => {
+                let n = (simplex[1].p - simplex[0].p).cross(&(simplex[2].p - simplex[0].p));
+                <T as Signed>::abs(&n.dot(&(p.p - simplex[0].p))) > eps
+            },
+        };
+        let p = dirs.iter()
+            .map(|d| support(support_a, support_b, d))
+            .find(extends)?;
+        simplex.push(p);
+    }
+    Some(simplex)
+}
+
+/// A triangular face of the polytope, together with its outward normal and its distance to the
+/// origin.
+struct Face<T> {
+    idx: [usize; 3],
+    normal: Vector3<T>,
+    distance: T,
+}
+
+impl<T: BaseFloat> Face<T> {
+    /// Creates the face with the corners `idx`. The normal is oriented away from the point
+    /// `interior`, which lies inside of the polytope. The origin cannot be used for this, as it
+    /// may lie on a face of the polytope.
+    fn new(polytope: &[SupportPoint<T>], idx: [usize; 3], interior: &Vector3<T>) -> Self {
+        let (a, b, c) = (polytope[idx[0]].p, polytope[idx[1]].p, polytope[idx[2]].p);
+        let mut normal = (b - a).cross(&(c - a)).normalize();
+        let mut idx = idx;
+        if normal.dot(&(a - interior)) < T::zero() {
+            // keep the winding consistent with the outward normal
+            normal = -normal;
+            idx.swap(1, 2);
+        }
+        Face { idx, normal, distance: normal.dot(&a) }
+    }
+}
+
+/// Runs EPA on the initial tetrahedron `polytope`, which has to contain the origin.
+fn epa<T, SA, SB>(
+    support_a: &SA,
+    support_b: &SB,
+    mut polytope: Vec<SupportPoint<T>>
+) -> Option<(Vector3<T>, T, Vector3<T>)>
+where T: BaseFloat,
+      SA: Fn(&Vector3<T>) -> Vector3<T>,
+      SB: Fn(&Vector3<T>) -> Vector3<T> {
+    let tolerance = T::default_epsilon().sqrt();
+    let interior = polytope.iter().fold(Vector3::zeros(), |sum, p| sum + p.p) * T::half() * T::half();
+    let mut faces = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]].iter()
+        .map(|idx| Face::new(&polytope, *idx, &interior))
+        .collect::<Vec<_>>();
+
+    for _ in 0..MAX_ITERATIONS {
+        let closest = faces.iter()
+            .enumerate()
+            .fold(0, |best, (i, f)| if f.distance < faces[best].distance { i } else { best });
+        let (normal, distance) = (faces[closest].normal, faces[closest].distance);
+
+        let p = support(support_a, support_b, &normal);
+        if p.p.dot(&normal) - distance <= tolerance {
+            // the closest face lies on the boundary of the Minkowski difference
+            return Some((normal, distance, contact_point(&polytope, &faces[closest])));
+        }
+
+        // remove all faces that can see the new point and collect the edges of the hole
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        faces.retain(|f| {
+            if f.normal.dot(&(p.p - polytope[f.idx[0]].p)) <= T::zero() {
+                return true;
+            }
+            for k in 0..3 {
+                let edge = (f.idx[k], f.idx[(k + 1) % 3]);
+                if let Some(i) = edges.iter().position(|e| *e == (edge.1, edge.0)) {
+                    // the edge is shared by two removed faces
+                    edges.swap_remove(i);
+                } else {
+                    edges.push(edge);
+                }
+            }
+            false
+        });
+
+        let new = polytope.len();
+        polytope.push(p);
+        for (i, j) in edges {
+            faces.push(Face::new(&polytope, [i, j, new], &interior));
+        }
+    }
+    None
+}
+
+/// Returns the point halfway between the deepest points of both shapes. These are found by
+/// projecting the origin onto the face and applying its barycentric coordinates to the support
+/// points of both shapes.
+fn contact_point<T: BaseFloat>(polytope: &[SupportPoint<T>], face: &Face<T>) -> Vector3<T> {
+    let (a, b, c) = (polytope[face.idx[0]], polytope[face.idx[1]], polytope[face.idx[2]]);
+    let p = face.normal.scale(face.distance);
+
+    let (v0, v1, v2) = (b.p - a.p, c.p - a.p, p - a.p);
+    let (d00, d01, d11) = (v0.dot(&v0), v0.dot(&v1), v1.dot(&v1));
+    let (d20, d21) = (v2.dot(&v0), v2.dot(&v1));
+    let denom = d00 * d11 - d01 * d01;
+    let (v, w) = if <T as Signed>::abs(&denom) <= T::default_epsilon() {
+        (T::zero(), T::zero())
+    } else {
+        ((d11 * d20 - d01 * d21) / denom, (d00 * d21 - d01 * d20) / denom)
+    };
+    let u = T::one() - v - w;
+
+    let on_a = a.a.scale(u) + b.a.scale(v) + c.a.scale(w);
+    let on_b = a.b.scale(u) + b.b.scale(v) + c.b.scale(w);
+    (on_a + on_b) * T::half()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::helper::gjk::{intersects, penetration, support_points};
+    use crate::test_util::cube_corners;
+
+    #[test]
+    fn cubes() {
+        let a = cube_corners(Vector3::zeros(), UnitQuaternion::identity());
+        let b = cube_corners(Vector3::new(0.8, 0.1, 0.0), UnitQuaternion::identity());
+        let sa = |d: &Vector3<f64>| support_points(&a, d);
+        let sb = |d: &Vector3<f64>| support_points(&b, d);
+        assert!(intersects(sa, sb));
+
+        let (normal, depth, point) = penetration(sa, sb).unwrap();
+        assert!((normal - Vector3::x()).norm() < 1e-9, "{normal}");
+        assert!((depth - 0.2).abs() < 1e-9, "{depth}");
+        assert!((point.x - 0.4).abs() < 1e-9, "{point}");
+
+        // separated along a diagonal, but overlapping along all world axes
+        let rot = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_4);
+        let c = cube_corners(Vector3::new(1.05, 1.05, 0.0), rot);
+        let sc = |d: &Vector3<f64>| support_points(&c, d);
+        assert!(!intersects(sa, sc));
+        assert!(penetration(sa, sc).is_none());
+        assert!(!intersects(sa, |d: &Vector3<f64>| support_points(&cube_corners(Vector3::new(0.0, 1.25, 0.0), rot), d)));
+    }
+
+    #[test]
+    fn concentric() {
+        // the origin lies in the center of the Minkowski difference
+        let a = cube_corners(Vector3::zeros(), UnitQuaternion::identity());
+        let b = cube_corners(Vector3::zeros(), UnitQuaternion::from_euler_angles(0.3, 0.2, 0.1));
+        let (normal, depth, _) = penetration(
+            |d: &Vector3<f64>| support_points(&a, d),
+            |d: &Vector3<f64>| support_points(&b, d),
+        ).unwrap();
+        assert!((normal.norm() - 1.0).abs() < 1e-9);
+        assert!(depth > 1.0 && depth < 2.0, "{depth}");
+    }
+}
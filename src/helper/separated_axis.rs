@@ -17,13 +17,26 @@ macro_rules! add {
 }
 
 macro_rules! intersect_projection {
+    ($T:ty, $axis:expr, $($fac:ident * |$p:ident|),+ ; eps = $eps:expr) => (
+        abs!($T, $axis) > add!($($fac * (abs!($T, $p) + $eps)),+)
+    );
     ($T:ty, $axis:expr, $($fac:ident * |$p:ident|),+ $(+ $add:expr)*) => (
         abs!($T, $axis) > add!($($add, )* $($fac * abs!($T, $p)),+)
     );
 }
 
+/// Slack that is added to the absolute direction cosines of the edge-edge axes. For nearly
+/// parallel edges, both the projected distance and the projected radii along the unnormalized
+/// cross product vanish, such that rounding errors alone may report a separation. The slack
+/// keeps the radii above the rounding noise (see Gottschalk et al., OBBTree, 1996).
+const EDGE_AXIS_EPSILON: f64 = 1e-6;
+
 
 /// OBB-OBB non-intersection test using the separating axis theorem in three spacial dimensions.
+///
+/// The edge-edge axes are not normalized. Instead, a small slack is added to the projected radii
+/// along these axes, such that nearly parallel boxes are not reported as separated due to rounding
+/// errors. The test may therefore report boxes as intersecting that are separated by a tiny gap.
 pub fn intersects_obb_obb<T: BaseFloat>(
     a0: &Vector3<T>, a1: &Vector3<T>, a2: &Vector3<T>,
     b0: &Vector3<T>, b1: &Vector3<T>, b2: &Vector3<T>,
@@ -64,22 +77,85 @@ pub fn intersects_obb_obb<T: BaseFloat>(
     }
 
 
+    let eps: T = nalgebra::convert(EDGE_AXIS_EPSILON);
+
     // -- axis B0, B1, B2
            !intersect_projection!(T, b0.dot(rel), sa0 * |c00|, sa1 * |c10|, sa2 * |c20| + sb0)
         && !intersect_projection!(T, b1.dot(rel), sa0 * |c01|, sa1 * |c11|, sa2 * |c21| + sb1)
         && !intersect_projection!(T, b2.dot(rel), sa0 * |c02|, sa1 * |c12|, sa2 * |c22| + sb2)
     // -- axis A0 x B0, A0 x B1, A0 x B2
-        && !intersect_projection!(T, c10 * a2d - c20 * a1d, sa1 * |c20|, sa2 * |c10|, sb1 * |c02|, sb2 * |c01|)
-        && !intersect_projection!(T, c11 * a2d - c21 * a1d, sa1 * |c21|, sa2 * |c11|, sb0 * |c02|, sb2 * |c00|)
-        && !intersect_projection!(T, c12 * a2d - c22 * a1d, sa1 * |c22|, sa2 * |c12|, sb0 * |c01|, sb1 * |c00|)
+        && !intersect_projection!(T, c10 * a2d - c20 * a1d, sa1 * |c20|, sa2 * |c10|, sb1 * |c02|, sb2 * |c01|; eps = eps)
+        && !intersect_projection!(T, c11 * a2d - c21 * a1d, sa1 * |c21|, sa2 * |c11|, sb0 * |c02|, sb2 * |c00|; eps = eps)
+        && !intersect_projection!(T, c12 * a2d - c22 * a1d, sa1 * |c22|, sa2 * |c12|, sb0 * |c01|, sb1 * |c00|; eps = eps)
     // -- axis A1 x B0, A1 x B1, A1 x B2
-        && !intersect_projection!(T, c20 * a0d - c00 * a2d, sa0 * |c20|, sa2 * |c00|, sb1 * |c12|, sb2 * |c11|)
-        && !intersect_projection!(T, c21 * a0d - c01 * a2d, sa0 * |c21|, sa2 * |c01|, sb0 * |c12|, sb2 * |c10|)
-        && !intersect_projection!(T, c22 * a0d - c02 * a2d, sa0 * |c22|, sb2 * |c02|, sb0 * |c11|, sb1 * |c10|)
+        && !intersect_projection!(T, c20 * a0d - c00 * a2d, sa0 * |c20|, sa2 * |c00|, sb1 * |c12|, sb2 * |c11|; eps = eps)
+        && !intersect_projection!(T, c21 * a0d - c01 * a2d, sa0 * |c21|, sa2 * |c01|, sb0 * |c12|, sb2 * |c10|; eps = eps)
+        && !intersect_projection!(T, c22 * a0d - c02 * a2d, sa0 * |c22|, sa2 * |c02|, sb0 * |c11|, sb1 * |c10|; eps = eps)
     // -- axis A2 x B0, A2 x B1, A2 x B2
-        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|, sb1 * |c22|, sb2 * |c21|)
-        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c01|, sb0 * |c22|, sb2 * |c20|)
-        && !intersect_projection!(T, c02 * a1d - c12 * a0d, sa0 * |c12|, sa1 * |c02|, sb0 * |c21|, sb1 * |c20|)
+        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|, sb1 * |c22|, sb2 * |c21|; eps = eps)
+        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c01|, sb0 * |c22|, sb2 * |c20|; eps = eps)
+        && !intersect_projection!(T, c02 * a1d - c12 * a0d, sa0 * |c12|, sa1 * |c02|, sb0 * |c21|, sb1 * |c20|; eps = eps)
+}
+
+/// OBB-OBB penetration test using the separating axis theorem in three spacial dimensions.
+///
+/// The boxes are specified by their unit axes `a` and `b`, their half sizes `sa` and `sb` along
+/// these axes and the vector `rel` from the center of box A to the center of box B. If the boxes
+/// overlap or touch, the axis of least penetration is returned together with the penetration
+/// depth along that axis. The returned axis is normalized and always points from A towards B.
+///
+/// The face axes of both boxes are tested before the edge-edge axes, such that face contacts are
+/// preferred in case of equal penetration depths. An edge-edge axis only replaces a face axis if
+/// its penetration depth is smaller by more than 5%. The edges of nearly parallel boxes span axes,
+/// which are numerically unreliable and would otherwise turn the resting contact of a stacked box
+/// into an edge contact.
+pub fn penetration_obb_obb<T: BaseFloat>(
+    a: &[Vector3<T>; 3],
+    b: &[Vector3<T>; 3],
+    rel: &Vector3<T>,
+    sa: &Vector3<T>,
+    sb: &Vector3<T>,
+) -> Option<(Vector3<T>, T)> {
+    let eps: T = nalgebra::convert(1e-6);
+    let edge_tolerance: T = nalgebra::convert(0.95);
+    let mut best: Option<(Vector3<T>, T)> = None;
+
+    let mut test_axis = |axis: Vector3<T>, tolerance: T| -> bool {
+        let ra = sa[0] * abs!(T, axis.dot(&a[0]))
+            + sa[1] * abs!(T, axis.dot(&a[1]))
+            + sa[2] * abs!(T, axis.dot(&a[2]));
+        let rb = sb[0] * abs!(T, axis.dot(&b[0]))
+            + sb[1] * abs!(T, axis.dot(&b[1]))
+            + sb[2] * abs!(T, axis.dot(&b[2]));
+        let dist = axis.dot(rel);
+        let depth = ra + rb - abs!(T, dist);
+        if depth < T::zero() {
+            return false;
+        }
+        if best.as_ref().is_none_or(|(_, d)| depth < *d * tolerance) {
+            let axis = if dist < T::zero() { -axis } else { axis };
+            best = Some((axis, depth));
+        }
+        true
+    };
+
+    // -- face axes of A and B
+    for axis in a.iter().chain(b.iter()) {
+        if !test_axis(*axis, T::one()) {
+            return None;
+        }
+    }
+    // -- edge-edge axes; parallel edges do not span an axis and are skipped
+    for ea in a {
+        for eb in b {
+            let axis = ea.cross(eb);
+            let len = axis.norm();
+            if len > eps && !test_axis(axis / len, edge_tolerance) {
+                return None;
+            }
+        }
+    }
+    best
 }
 
 /// OBB-OBB non-intersection test using the separation axis theorem in two spacial dimensions.
@@ -109,12 +185,9 @@ pub fn intersects_obb_obb_2d<T: BaseFloat>(
         return false;
     }
 
-    // -- axis B0, B1, B2
+    // -- axis B0, B1; in two dimensions, there are no edge-edge axes
            !intersect_projection!(T, b0.dot(rel), sa0 * |c00|, sa1 * |c10| + sb0)
         && !intersect_projection!(T, b1.dot(rel), sa0 * |c01|, sa1 * |c11| + sb1)
-    // -- axis A2 x B0, A2 x B1
-        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|)
-        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c10|)
 }
 
 
@@ -157,22 +230,24 @@ pub fn intersects_obb_aabb<T: BaseFloat>(
     }
 
 
+    let eps: T = nalgebra::convert(EDGE_AXIS_EPSILON);
+
     // -- axis B0, B1, B2
            !intersect_projection!(T, rel.x, sa0 * |c00|, sa1 * |c10|, sa2 * |c20| + sb0)
         && !intersect_projection!(T, rel.y, sa0 * |c01|, sa1 * |c11|, sa2 * |c21| + sb1)
         && !intersect_projection!(T, rel.z, sa0 * |c02|, sa1 * |c12|, sa2 * |c22| + sb2)
     // -- axis A0 x B0, A0 x B1, A0 x B2
-        && !intersect_projection!(T, c10 * a2d - c20 * a1d, sa1 * |c20|, sa2 * |c10|, sb1 * |c02|, sb2 * |c01|)
-        && !intersect_projection!(T, c11 * a2d - c21 * a1d, sa1 * |c21|, sa2 * |c11|, sb0 * |c02|, sb2 * |c00|)
-        && !intersect_projection!(T, c12 * a2d - c22 * a1d, sa1 * |c22|, sa2 * |c12|, sb0 * |c01|, sb1 * |c00|)
+        && !intersect_projection!(T, c10 * a2d - c20 * a1d, sa1 * |c20|, sa2 * |c10|, sb1 * |c02|, sb2 * |c01|; eps = eps)
+        && !intersect_projection!(T, c11 * a2d - c21 * a1d, sa1 * |c21|, sa2 * |c11|, sb0 * |c02|, sb2 * |c00|; eps = eps)
+        && !intersect_projection!(T, c12 * a2d - c22 * a1d, sa1 * |c22|, sa2 * |c12|, sb0 * |c01|, sb1 * |c00|; eps = eps)
     // -- axis A1 x B0, A1 x B1, A1 x B2
-        && !intersect_projection!(T, c20 * a0d - c00 * a2d, sa0 * |c20|, sa2 * |c00|, sb1 * |c12|, sb2 * |c11|)
-        && !intersect_projection!(T, c21 * a0d - c01 * a2d, sa0 * |c21|, sa2 * |c01|, sb0 * |c12|, sb2 * |c10|)
-        && !intersect_projection!(T, c22 * a0d - c02 * a2d, sa0 * |c22|, sb2 * |c02|, sb0 * |c11|, sb1 * |c10|)
+        && !intersect_projection!(T, c20 * a0d - c00 * a2d, sa0 * |c20|, sa2 * |c00|, sb1 * |c12|, sb2 * |c11|; eps = eps)
+        && !intersect_projection!(T, c21 * a0d - c01 * a2d, sa0 * |c21|, sa2 * |c01|, sb0 * |c12|, sb2 * |c10|; eps = eps)
+        && !intersect_projection!(T, c22 * a0d - c02 * a2d, sa0 * |c22|, sa2 * |c02|, sb0 * |c11|, sb1 * |c10|; eps = eps)
     // -- axis A2 x B0, A2 x B1, A2 x B2
-        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|, sb1 * |c22|, sb2 * |c21|)
-        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c01|, sb0 * |c22|, sb2 * |c20|)
-        && !intersect_projection!(T, c02 * a1d - c12 * a0d, sa0 * |c12|, sa1 * |c02|, sb0 * |c21|, sb1 * |c20|)
+        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|, sb1 * |c22|, sb2 * |c21|; eps = eps)
+        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c01|, sb0 * |c22|, sb2 * |c20|; eps = eps)
+        && !intersect_projection!(T, c02 * a1d - c12 * a0d, sa0 * |c12|, sa1 * |c02|, sb0 * |c21|, sb1 * |c20|; eps = eps)
 }
 
 /// OBB-AABB non-intersection test using the separation axis theorem in two spacial dimensions.
@@ -201,12 +276,9 @@ pub fn intersects_obb_aabb_2d<T: BaseFloat>(
         return false;
     }
 
-    // -- axis B0, B1, B2
+    // -- axis B0, B1; in two dimensions, there are no edge-edge axes
     !intersect_projection!(T, rel.x, sa0 * |c00|, sa1 * |c10| + sb0)
         && !intersect_projection!(T, rel.y, sa0 * |c01|, sa1 * |c11| + sb1)
-
-        && !intersect_projection!(T, c00 * a1d - c10 * a0d, sa0 * |c10|, sa1 * |c00|)
-        && !intersect_projection!(T, c01 * a1d - c11 * a0d, sa0 * |c11|, sa1 * |c10|)
 }
 
 /// AABB-AABB non-intersection test using the separation axis theorem in arbitrary spacial
@@ -226,3 +298,92 @@ pub fn intersects_aabb_aabb<T: BaseFloat, const DIM: usize>(
     //     && max1.y >= min0.y && min1.y <= max0.y
     //     && max1.z >= min0.z && min1.z <= max0.z
 }
+
+/// Returns the extents of the overlap of two AABBs along each axis, or `None` if the boxes do not
+/// intersect. Touching boxes overlap with an extent of zero along the touching axis.
+///
+/// The axis with the smallest extent is the axis of minimum penetration; moving one of the boxes
+/// by that extent along that axis separates both boxes (minimum translation vector).
+pub fn overlap_aabb_aabb<T: BaseFloat, const DIM: usize>(
+    min0: &SVector<T, DIM>, max0: &SVector<T, DIM>,
+    min1: &SVector<T, DIM>, max1: &SVector<T, DIM>,
+) -> Option<SVector<T, DIM>> {
+    let mut overlap = SVector::zeros();
+    for i in 0..DIM {
+        overlap[i] = T::min(max0[i], max1[i]) - T::max(min0[i], min1[i]);
+        if overlap[i] < T::zero() {
+            return None;
+        }
+    }
+    Some(overlap)
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Quaternion, UnitQuaternion, Vector2, Vector3};
+    use crate::helper::separated_axis::{intersects_aabb_aabb, intersects_obb_obb, overlap_aabb_aabb, penetration_obb_obb};
+
+    fn axes(q: &UnitQuaternion<f64>) -> [Vector3<f64>; 3] {
+        let m = q.to_rotation_matrix();
+        [m.matrix().column(0).into(), m.matrix().column(1).into(), m.matrix().column(2).into()]
+    }
+
+    fn intersects(a: &[Vector3<f64>; 3], b: &[Vector3<f64>; 3], rel: &Vector3<f64>, sa: &Vector3<f64>, sb: &Vector3<f64>) -> bool {
+        intersects_obb_obb(&a[0], &a[1], &a[2], &b[0], &b[1], &b[2], rel, sa.x, sa.y, sa.z, sb.x, sb.y, sb.z)
+    }
+
+    #[test]
+    fn overlap_aabb() {
+        let (min0, max0) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0));
+        let (min1, max1) = (Vector3::new(1.5, -1.0, 0.5), Vector3::new(4.0, 1.0, 1.0));
+        assert_eq!(overlap_aabb_aabb(&min0, &max0, &min1, &max1), Some(Vector3::new(0.5, 1.0, 0.5)));
+        assert_eq!(overlap_aabb_aabb(&min1, &max1, &min0, &max0), Some(Vector3::new(0.5, 1.0, 0.5)));
+
+        // touching boxes overlap without extent; the result agrees with the intersection test
+        let (min2, max2) = (Vector2::new(1.0, 0.0), Vector2::new(2.0, 1.0));
+        let (min3, max3) = (Vector2::new(2.0, 0.5), Vector2::new(3.0, 3.0));
+        assert_eq!(overlap_aabb_aabb(&min2, &max2, &min3, &max3), Some(Vector2::new(0.0, 0.5)));
+        let (min3, max3) = (Vector2::new(2.1, 0.5), Vector2::new(3.0, 3.0));
+        assert_eq!(overlap_aabb_aabb(&min2, &max2, &min3, &max3), None);
+        assert!(!intersects_aabb_aabb(&min2, &max2, &min3, &max3));
+    }
+
+    #[test]
+    fn obb_obb_nearly_parallel() {
+        // the orientations of both boxes only differ by rounding errors, such that all edge-edge
+        // axes nearly vanish
+        let qa = UnitQuaternion::new_unchecked(Quaternion::new(
+            0.587514589980861, 0.3957428082685242, -0.5578422623566333, 0.4324653125884159));
+        let qb = UnitQuaternion::new_unchecked(Quaternion::new(
+            0.587514589980861, 0.39574280826852426, -0.5578422623566333, 0.432465312588416));
+        let (a, b) = (axes(&qa), axes(&qb));
+        let sa = Vector3::new(2.5, 0.45, 0.4);
+        let sb = Vector3::new(5.0, 0.6, 0.3);
+        let rel = Vector3::new(0.85, 0.1, -0.9);
+
+        assert!(penetration_obb_obb(&a, &b, &rel, &sa, &sb).is_some());
+        assert!(intersects(&a, &b, &rel, &sa, &sb));
+    }
+
+    #[test]
+    fn obb_obb_agrees_with_penetration() {
+        // the normalized axes of the penetration test serve as the reference
+        let sa = Vector3::new(2.0, 0.5, 0.25);
+        let sb = Vector3::new(0.3, 0.6, 1.5);
+        for i in 0..6 {
+            for j in 0..6 {
+                let a = axes(&UnitQuaternion::from_euler_angles(0.4 * i as f64, 0.0, 0.7 * j as f64));
+                let b = axes(&UnitQuaternion::from_euler_angles(0.3 * j as f64, 0.9 * i as f64, 0.2));
+                for k in 0..40 {
+                    let rel = Vector3::new(0.3 * k as f64 - 6.0, 0.05 * k as f64 - 1.0, 1.3 - 0.07 * k as f64);
+                    let expected = penetration_obb_obb(&a, &b, &rel, &sa, &sb);
+                    if expected.is_none_or(|(_, depth)| depth > 1e-6) {
+                        assert_eq!(intersects(&a, &b, &rel, &sa, &sb), expected.is_some());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -3,3 +3,7 @@ pub mod helper;
 pub mod volume;
 pub mod engine;
 pub mod collision;
+#[cfg(test)]
+mod test_util;
+
+pub use system::inertia::{Error, ErrorType};
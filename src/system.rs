@@ -1,2 +1,3 @@
 pub mod inertia;
-pub mod object;
\ No newline at end of file
+pub mod object;
+pub mod hierarchy;
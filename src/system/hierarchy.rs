@@ -0,0 +1,162 @@
+use std::sync::OnceLock;
+use crate::helper::BaseFloat;
+use crate::system::inertia::Transformer;
+
+/// Node of a `TransformHierarchy`.
+struct Node<T> {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Transformer state of the node in the reference frame of its parent.
+    local: Transformer<T>,
+    /// Cached transformer state of the node in the laboratory frame. If a node is outdated, so are
+    /// all of its descendants.
+    world: OnceLock<Transformer<T>>,
+}
+
+/// Tree of transformer states, where the state of each node is specified in the reference frame of
+/// its parent. This is used for articulated objects, like the limbs of a ragdoll or the wheels of
+/// a car, which move along with the object they are attached to.
+///
+/// The states of the nodes in the laboratory frame are computed by composing the states from the
+/// root down to the node. They are cached until the local state of the node or one of its
+/// ancestors changes.
+pub struct TransformHierarchy<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: BaseFloat> Default for TransformHierarchy<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: BaseFloat> TransformHierarchy<T> {
+    pub fn new() -> Self {
+        TransformHierarchy {
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Returns the amount of nodes in the hierarchy.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true, if the hierarchy contains no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a node with the state `local` in the reference frame of the node `parent` and returns
+    /// the index of the new node. Nodes without a parent are roots, whose local state is specified
+    /// in the laboratory frame.
+    ///
+    /// Since parents have to exist before their children, the hierarchy cannot contain cycles.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the `parent` node does not exist.
+    pub fn add(&mut self, parent: Option<usize>, local: Transformer<T>) -> usize {
+        let idx = self.nodes.len();
+        if let Some(parent) = parent {
+            assert!(parent < idx, "the parent node {} does not exist", parent);
+            self.nodes[parent].children.push(idx);
+        }
+        self.nodes.push(Node {
+            parent,
+            children: Vec::new(),
+            local,
+            world: OnceLock::new(),
+        });
+        idx
+    }
+
+    /// Returns the parent of the node `node`, or `None` if the node is a root.
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes[node].parent
+    }
+
+    /// Returns the children of the node `node`.
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.nodes[node].children
+    }
+
+    /// Returns the state of the node `node` in the reference frame of its parent.
+    pub fn local(&self, node: usize) -> &Transformer<T> {
+        &self.nodes[node].local
+    }
+
+    /// Sets the state of the node `node` in the reference frame of its parent. The cached states of
+    /// the node and all of its descendants are computed again on their next access.
+    pub fn set_local(&mut self, node: usize, local: Transformer<T>) {
+        self.nodes[node].local = local;
+        self.invalidate(node);
+    }
+
+    /// Returns a mutable reference to the state of the node `node` in the reference frame of its
+    /// parent, and invalidates the cached states of the node and its descendants like `set_local`.
+    /// Changes to the public fields of the state have to be followed by
+    /// `Transformer::update_transformation`, while the setters of the state take care of this.
+    pub fn local_mut(&mut self, node: usize) -> &mut Transformer<T> {
+        self.invalidate(node);
+        &mut self.nodes[node].local
+    }
+
+    /// Returns the state of the node `node` in the laboratory frame. The state is composed from
+    /// the states of the ancestors of the node, which are cached as well.
+    pub fn world_transform(&self, node: usize) -> &Transformer<T> {
+        let n = &self.nodes[node];
+        n.world.get_or_init(|| match n.parent {
+            Some(parent) => self.world_transform(parent).trafo(&n.local),
+            None => n.local.clone(),
+        })
+    }
+
+    /// Drops the cached states of the node `node` and all of its descendants.
+    fn invalidate(&mut self, node: usize) {
+        let mut stack = vec![node];
+        while let Some(idx) = stack.pop() {
+            // the descendants of outdated nodes are outdated as well, as they depend on the node
+            if self.nodes[idx].world.take().is_some() {
+                stack.extend_from_slice(&self.nodes[idx].children);
+            }
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::system::hierarchy::TransformHierarchy;
+    use crate::test_util::at;
+
+    #[test]
+    fn chain() {
+        let quarter = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        let mut hierarchy = TransformHierarchy::new();
+        let root = hierarchy.add(None, at(Vector3::new(1.0, 0.0, 0.0), quarter));
+        let child = hierarchy.add(Some(root), at(Vector3::new(2.0, 0.0, 0.0), UnitQuaternion::identity()));
+        let grandchild = hierarchy.add(Some(child), at(Vector3::new(0.0, 0.0, 3.0), quarter));
+
+        // the child is rotated onto the y-axis by the root
+        let world = hierarchy.world_transform(grandchild);
+        assert!((world.pos - Vector3::new(1.0, 2.0, 3.0)).norm() < 1e-12);
+        assert!(world.rot.angle_to(&(quarter * quarter)) < 1e-12);
+        assert!((world.trafo_point(&Vector3::x()) - Vector3::new(0.0, 2.0, 3.0)).norm() < 1e-12);
+
+        // moving the root moves all of its descendants
+        hierarchy.local_mut(root).set_pos(Vector3::new(-1.0, 0.0, 1.0));
+        let world = hierarchy.world_transform(grandchild);
+        assert!((world.pos - Vector3::new(-1.0, 2.0, 4.0)).norm() < 1e-12);
+        assert!((hierarchy.world_transform(child).pos - Vector3::new(-1.0, 2.0, 1.0)).norm() < 1e-12);
+
+        // changing the child does not affect the root
+        hierarchy.set_local(child, at(Vector3::zeros(), UnitQuaternion::identity()));
+        assert!((hierarchy.world_transform(grandchild).pos - Vector3::new(-1.0, 0.0, 4.0)).norm() < 1e-12);
+        assert_eq!(hierarchy.world_transform(root).pos, Vector3::new(-1.0, 0.0, 1.0));
+        assert_eq!(hierarchy.children(root), [child]);
+        assert_eq!(hierarchy.parent(grandchild), Some(child));
+    }
+}
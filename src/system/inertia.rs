@@ -1,11 +1,15 @@
-use std::mem;
+use std::fmt;
+use std::sync::OnceLock;
 use std::ops::{AddAssign, Neg, SubAssign};
-use nalgebra::{ClosedAdd, ClosedMul, ComplexField, Const, DefaultAllocator, Dim, Matrix, Matrix3, Matrix4, OMatrix, RealField, Scalar, Storage, UnitQuaternion, Vector3};
+use nalgebra::{ClosedAdd, ClosedMul, ComplexField, Const, DefaultAllocator, Dim, Isometry3, Matrix, Matrix3, Matrix4, OMatrix, Quaternion, RealField, Scalar, Similarity3, Storage, Translation3, UnitQuaternion, Vector3};
 use nalgebra::allocator::Allocator;
-use num::{One, Zero};
+use num::{One, Signed, Zero};
 use crate::helper::{BaseFloat, mat};
+#[cfg(feature="bevy_support")]
+use bevy::prelude::{Quat, Transform, Vec3};
 
 /// The base error type for the error classes used by the physics engine core.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorType {
     /// The math error enum type is used for all algebraic errors, like, for example, when
     /// dividing by a zero-value or trying to invert a non-invertible matrix.
@@ -14,10 +18,14 @@ pub enum ErrorType {
     /// physical model of the world. For example, the inertia tensor for a 3d-object should always
     /// be a 3x3 invertible matrix.
     PhysicsError,
+    /// The parse error enum type is used for malformed input data, like a mesh file that
+    /// references vertices that do not exist.
+    ParseError,
 }
 
 /// Base error structure. An error consists of an error base type and an optional error message.
 /// To generate an error, the build-in `err!` macro should be used.
+#[derive(Debug)]
 pub struct Error {
     msg: Option<String>,
     ty: ErrorType,
@@ -31,8 +39,39 @@ impl Error {
             ty
         }
     }
+
+    /// Returns the error class of this error.
+    pub fn error_type(&self) -> ErrorType {
+        self.ty
+    }
+
+    /// Returns the message of this error, if there is one.
+    pub fn message(&self) -> Option<&str> {
+        self.msg.as_deref()
+    }
 }
 
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorType::MathError => write!(f, "math error"),
+            ErrorType::PhysicsError => write!(f, "physics error"),
+            ErrorType::ParseError => write!(f, "parse error"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.msg {
+            Some(msg) => write!(f, "{}: {}", self.ty, msg),
+            None => write!(f, "{}", self.ty),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 macro_rules! err {
     (math) => (
         Error::new(ErrorType::MathError, None)
@@ -46,6 +85,12 @@ macro_rules! err {
     (physics $msg:expr) => (
         Error::new(ErrorType::PhysicsError, Some(String::from($msg)))
     );
+    (parse) => (
+        Error::new(ErrorType::ParseError, None)
+    );
+    (parse $msg:expr) => (
+        Error::new(ErrorType::ParseError, Some(String::from($msg)))
+    );
 }
 pub(crate) use err;
 
@@ -59,34 +104,107 @@ pub(crate) use err;
 /// This structure may be packaged into component data structures together with children objects,
 /// mesh-data, and other components.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: BaseFloat + serde::Serialize",
+    deserialize = "T: BaseFloat + serde::Deserialize<'de>"
+)))]
 pub struct IS<T> {
+    /// Momentum of the center of mass in the laboratory frame.
     pub momentum: Vector3<T>,
+    /// Angular momentum about the center of mass in the reference frame of the system, where the
+    /// inertia tensor of the mass distribution is defined.
     pub angular_mom: Vector3<T>,
     pub state: Transformer<T>,
     pub mass: MassDistribution<T>,
 }
 
 /// Data structure for the mass distributions of an inertial system.
+///
+/// Static bodies, which cannot be moved by any impulse, are modeled with an infinite mass and
+/// inertia tensor (see `immovable`). Their inverse mass and inverse inertia tensor are zero.
+///
+/// With the `serde` feature, the inverse mass and inertia tensor are not serialized, but computed
+/// again on deserialization.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "T: BaseFloat + serde::Serialize")))]
 pub struct MassDistribution<T> {
     mass: T,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    inv_mass: T,
     center_of_mass: Vector3<T>,
     inertia: Matrix3<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     inv_inertia: Matrix3<T>,
 }
 
 /// Data structure for a transformer state.
+///
+/// With the `serde` feature, the transformation matrices are not serialized, but generated again
+/// on deserialization.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "T: BaseFloat + serde::Serialize")))]
 pub struct Transformer<T> {
     pub pos: Vector3<T>,
     pub offset: Vector3<T>,
     pub scale: Vector3<T>,
     pub rot: UnitQuaternion<T>,
 
-    /// Transformation matrix for transforming points and vectors into the laboratory frame
-    mat: Matrix4<T>,
-    /// Transformation matrix for transforming points and vectors into the inertial reference frame
-    inv_mat: Matrix4<T>,
+    /// Transformation matrices for transforming points and vectors into the laboratory frame and
+    /// into the inertial reference frame. After a change through one of the setters, the matrices
+    /// are generated again on first access.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    matrices: OnceLock<(Matrix4<T>, Matrix4<T>)>,
+}
+
+/// Serialized fields of a `Transformer`, from which the transformation matrices are generated.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(deserialize = "T: BaseFloat + serde::Deserialize<'de>"))]
+struct TransformerState<T> {
+    pos: Vector3<T>,
+    offset: Vector3<T>,
+    scale: Vector3<T>,
+    rot: UnitQuaternion<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Transformer<T>
+where T: BaseFloat + serde::Deserialize<'de> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = TransformerState::<T>::deserialize(deserializer)?;
+        Ok(Transformer::new(state.pos, state.rot, state.scale, state.offset))
+    }
+}
+
+/// Serialized fields of a `MassDistribution`, from which the inverse mass and inertia tensor are
+/// computed.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(deserialize = "T: BaseFloat + serde::Deserialize<'de>"))]
+struct MassState<T> {
+    mass: T,
+    center_of_mass: Vector3<T>,
+    inertia: Matrix3<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for MassDistribution<T>
+where T: BaseFloat + serde::Deserialize<'de> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = MassState::<T>::deserialize(deserializer)?;
+        if !state.mass.is_finite() {
+            // static bodies have an infinite inertia tensor, which cannot be inverted
+            return Ok(MassDistribution {
+                center_of_mass: state.center_of_mass,
+                ..MassDistribution::immovable()
+            });
+        }
+        MassDistribution::new(state.mass, state.center_of_mass, state.inertia)
+            .map_err(|_| serde::de::Error::custom("failed to invert inertia tensor"))
+    }
 }
 
 
@@ -163,6 +281,11 @@ impl<T> IS<T> {
     }
 }
 
+/// Rotation angle per integration step, below which `IS::integrate` uses the first-order
+/// quaternion update. The update deviates from the exact rotation by about a twelfth of the cubed
+/// angle, which is far below the rounding error of the orientation at this threshold.
+const SMALL_ANGLE: f64 = 1e-4;
+
 impl<T> IS<T>
 where T: BaseFloat {
 
@@ -170,18 +293,28 @@ where T: BaseFloat {
     /// position and the velocity are specified as within the reference frame of this inertial
     /// system.
     ///
-    /// To get the point velocity from outside of the inertial system, all values have to be
-    /// transformed. This could look something like this:
-    ///
-    /// ``
-    /// is.trafo_outof(
-    ///     &is.get_point_vel(
-    ///         &is.trafo_into(&point)
-    ///     )
-    /// )
-    /// ``
+    /// The velocity consists of the linear velocity of the system and the rotation about its
+    /// center of mass, like `point_velocity_world`. Points in the laboratory frame should use
+    /// `point_velocity_world` directly, instead of transforming the point into and the velocity out
+    /// of the reference frame of the system.
     pub fn get_point_vel(&self, point: &Vector3<T>) -> Vector3<T> {
-        self.get_angular_vel().cross(point)
+        self.trafo_vec_into(&self.point_velocity_world(&self.trafo_point_outof(point)))
+    }
+
+    /// Returns the velocity of the point `world_point` that moves along with the inertial system.
+    /// Both the point and the velocity are specified in the laboratory frame.
+    ///
+    /// The velocity is `v + ω × r`, with the linear velocity `v` of the center of mass, the angular
+    /// velocity `ω` and the offset `r` of the point from the center of mass. Static systems do not
+    /// move, such that all of their points are at rest.
+    pub fn point_velocity_world(&self, world_point: &Vector3<T>) -> Vector3<T> {
+        let r = world_point - self.world_center_of_mass();
+        self.momentum.scale(self.mass.inv_mass) + self.world_angular_vel().cross(&r)
+    }
+
+    /// Returns the center of mass of the inertial system in the laboratory frame.
+    pub fn world_center_of_mass(&self) -> Vector3<T> {
+        self.state.trafo_point(self.mass.center_of_mass())
     }
 
     /// Returns the angular velocity of the inertial system within the reference frame of the
@@ -190,17 +323,113 @@ where T: BaseFloat {
         self.mass.inv_inertia * self.angular_mom
     }
 
+    /// Returns the angular velocity of the inertial system in the laboratory frame, which is the
+    /// angular velocity of `get_angular_vel` rotated by the orientation of the system.
+    pub fn world_angular_vel(&self) -> Vector3<T> {
+        self.state.rot * self.get_angular_vel()
+    }
+
+    /// Sets the angular momentum of the inertial system such that it spins with the angular
+    /// velocity `omega`, specified in the reference frame of the inertial system like
+    /// `get_angular_vel`. Static systems keep their angular momentum.
+    pub fn set_angular_velocity(&mut self, omega: Vector3<T>) {
+        if self.mass.is_immovable() {
+            return;
+        }
+        self.angular_mom = self.mass.inertia * omega;
+    }
+
+    /// Sets the momentum of the inertial system such that its center of mass moves with the
+    /// velocity `vel`. Static systems keep their momentum.
+    pub fn set_linear_velocity(&mut self, vel: Vector3<T>) {
+        if self.mass.is_immovable() {
+            return;
+        }
+        self.momentum = vel.scale(self.mass.mass);
+    }
+
+    /// Returns the kinetic energy of the inertial system, which is the sum of the translational
+    /// energy `p² / 2m` and the rotational energy `L · ω / 2`. Static systems have no kinetic
+    /// energy.
+    pub fn kinetic_energy(&self) -> T {
+        let translational = self.momentum.norm_squared() * self.mass.inv_mass;
+        let rotational = self.angular_mom.dot(&self.get_angular_vel());
+        (translational + rotational) * T::half()
+    }
+
+    /// Returns the inverse inertia tensor of the inertial system in the laboratory frame.
+    ///
+    /// The inertia tensor of the mass distribution is defined in the reference frame of the
+    /// inertial system, where it stays constant. Quantities in the laboratory frame, like impulses
+    /// from collision responses, need the tensor rotated into the laboratory frame. For the
+    /// orientation `R` of the inertial system, this is `R * I^-1 * R^T`.
+    ///
+    /// Since the tensor only depends on the orientation of the system, the scale and offset of the
+    /// transformer state are not taken into account.
+    pub fn world_inv_inertia(&self) -> Matrix3<T> {
+        let rot = self.state.rot.to_rotation_matrix();
+        rot.matrix() * self.mass.inv_inertia * rot.matrix().transpose()
+    }
+
     /// Applies an impulse to a specified point of the inertial system. All values are to be
-    /// provided from the reference frame of the inertial system.
+    /// provided from the reference frame of the inertial system. Impulses have no effect on static
+    /// systems.
+    ///
+    /// The impulse changes the momentum, which is kept in the laboratory frame, and its torque
+    /// about the center of mass changes the angular momentum, which is kept in the reference frame
+    /// of the system. Impulses through the center of mass therefore do not spin the system.
+    ///
+    /// Impulses from world space, like contact impulses, should be applied through
+    /// `apply_impulse_world` instead of being transformed into the reference frame first.
     pub fn apply_impulse(&mut self, imp: &Vector3<T>, point: &Vector3<T>) {
+        if self.mass.is_immovable() {
+            return;
+        }
+        let imp = self.trafo_vec_outof(imp);
+        let r = self.trafo_point_outof(point) - self.world_center_of_mass();
         self.momentum += imp;
-        self.angular_mom += point.cross(imp);
+        self.angular_mom += self.state.rot.inverse_transform_vector(&r.cross(&imp));
     }
 
+    /// Applies the impulse `imp` at the point `world_point` of the inertial system, where both
+    /// values are specified in the laboratory frame. Impulses have no effect on static systems.
+    ///
+    /// The impulse and the point are transformed into the reference frame of the system and
+    /// applied through `apply_impulse`.
+    pub fn apply_impulse_world(&mut self, imp: &Vector3<T>, world_point: &Vector3<T>) {
+        let (imp, point) = (self.trafo_vec_into(imp), self.trafo_point_into(world_point));
+        self.apply_impulse(&imp, &point);
+    }
+
+    /// Applies the force `force` at the point `world_point` of the inertial system for the time
+    /// `t`, where the force and the point are specified in the laboratory frame. This is the same
+    /// as applying the impulse `force * t` through `apply_impulse_world`, such that the force ends
+    /// up in the reference frame of the system like every other impulse.
+    pub fn apply_force_world(&mut self, force: &Vector3<T>, world_point: &Vector3<T>, t: T) {
+        self.apply_impulse_world(&force.scale(t), world_point);
+    }
+
+    /// Integrates the state of the inertial system over the time `t`. Static systems do not move.
+    ///
+    /// The orientation is advanced by the exponential map of the rotation vector `ω t`, with the
+    /// angular velocity `ω` in the laboratory frame. Below `SMALL_ANGLE`, the first-order update
+    /// `q + ½ (0, ω t) q` is used instead and renormalized, since the axis of tiny rotation vectors
+    /// cannot be determined precisely.
+    ///
+    /// Without torque, the angular momentum is conserved in the laboratory frame. Since it is
+    /// stored in the reference frame of the system, it is rotated back by the rotation of the step.
     pub fn integrate(&mut self, t: T) {
-        self.state.pos += self.momentum.scale(t / self.mass.mass);
-        let rot = UnitQuaternion::new(self.get_angular_vel().scale(t));
-        self.state.rot = rot * self.state.rot;
+        self.state.pos += self.momentum.scale(t * self.mass.inv_mass);
+        let angle = self.world_angular_vel().scale(t);
+        let world_mom = self.state.rot * self.angular_mom;
+        if angle.norm() < nalgebra::convert(SMALL_ANGLE) {
+            let q = self.state.rot.into_inner();
+            let dq = Quaternion::from_imag(angle * T::half()) * q;
+            self.state.rot = UnitQuaternion::new_normalize(q + dq);
+        } else {
+            self.state.rot = UnitQuaternion::new(angle) * self.state.rot;
+        }
+        self.angular_mom = self.state.rot.inverse_transform_vector(&world_mom);
     }
 
     pub fn sync(&mut self) {
@@ -213,7 +442,7 @@ where T: BaseFloat {
     where C: Dim,
           ST: Storage<T, Const<4>, C>,
           DefaultAllocator: Allocator<T, Const<4>, C> {
-        self.state.inv_mat * vec
+        self.state.inv_tsro() * vec
     }
 
     /// Transforms a matrix value from the reference frame of the inertial system into the
@@ -222,7 +451,7 @@ where T: BaseFloat {
     where C: Dim,
           ST: Storage<T, Const<4>, C>,
           DefaultAllocator: Allocator<T, Const<4>, C> {
-        self.state.mat * vec
+        self.state.tsro() * vec
     }
 
     /// Transforms a 3d-vector value from the laboratory frame into the reference frame of the
@@ -286,6 +515,20 @@ where T: BaseFloat {
     }
 }
 
+/// Returns the velocity of body `b` relative to body `a` at the shared point `world_point`, like
+/// the contact point of both bodies. The point and the velocity are specified in the laboratory
+/// frame; a negative component along the contact normal from A to B means that the bodies approach
+/// each other at the point.
+///
+/// This is the velocity of the point on `b` minus the velocity of the point on `a`, each
+/// consisting of the linear velocity of the body and its rotation about its center of mass (see
+/// `IS::point_velocity_world`). This is the same as transforming the point into the reference
+/// frame of each body, taking `IS::get_point_vel` there and transforming the velocities back, which
+/// already includes the linear velocities of the bodies.
+pub fn relative_point_velocity<T: BaseFloat>(a: &IS<T>, b: &IS<T>, world_point: &Vector3<T>) -> Vector3<T> {
+    b.point_velocity_world(world_point) - a.point_velocity_world(world_point)
+}
+
 
 
 
@@ -295,6 +538,7 @@ where T: Scalar + Zero + One {
     fn default() -> Self {
         MassDistribution {
             mass: T::one(),
+            inv_mass: T::one(),
             center_of_mass: Vector3::zeros(),
             inertia: Matrix3::identity(),
             inv_inertia: Matrix3::identity(),
@@ -316,6 +560,7 @@ where T: Scalar + ComplexField {
     pub fn new(mass: T, com: Vector3<T>, inertia: Matrix3<T>) -> Result<Self, Error> {
         // try to invert inertia and build mass distribution from there
         Ok(MassDistribution {
+            inv_mass: T::one() / mass.clone(),
             mass,
             center_of_mass: com,
             inv_inertia: inertia.clone().try_inverse()
@@ -325,12 +570,129 @@ where T: Scalar + ComplexField {
     }
 }
 
+impl<T> MassDistribution<T>
+where T: BaseFloat {
+    /// Creates the mass distribution of a static body, which has an infinite mass and inertia
+    /// tensor. Such a body is not moved by impulses or integration, but other bodies still collide
+    /// with it, which makes it suitable for floors, walls and other level geometry.
+    pub fn immovable() -> Self {
+        let infinity = T::one() / T::zero();
+        MassDistribution {
+            mass: infinity,
+            inv_mass: T::zero(),
+            center_of_mass: Vector3::zeros(),
+            inertia: Matrix3::from_diagonal_element(infinity),
+            inv_inertia: Matrix3::zeros(),
+        }
+    }
+
+    /// Returns true, if the mass distribution belongs to a static body, i.e. if its inverse mass is
+    /// zero.
+    pub fn is_immovable(&self) -> bool {
+        self.inv_mass == T::zero()
+    }
+
+    /// Creates the mass distribution of a solid box with the specified mass and edge lengths, whose
+    /// mass is distributed uniformly over its volume. The edges are aligned with the axes of the
+    /// reference frame and the center of mass lies at its origin.
+    ///
+    /// Returns an error, if any of the edge lengths is zero.
+    pub fn solid_box(mass: T, size: Vector3<T>) -> Result<Self, Error> {
+        let sq = size.component_mul(&size);
+        let inertia = Vector3::new(sq.y + sq.z, sq.x + sq.z, sq.x + sq.y)
+            .scale(mass / nalgebra::convert::<f64, T>(12.0));
+        Self::new(mass, Vector3::zeros(), Matrix3::from_diagonal(&inertia))
+    }
+
+    /// Creates the mass distribution of a solid sphere with the specified mass and radius, whose
+    /// mass is distributed uniformly over its volume. The center of mass lies at the origin of the
+    /// reference frame.
+    ///
+    /// Returns an error, if the radius is zero.
+    pub fn solid_sphere(mass: T, radius: T) -> Result<Self, Error> {
+        let inertia = nalgebra::convert::<f64, T>(0.4) * mass * radius * radius;
+        Self::new(mass, Vector3::zeros(), Matrix3::from_diagonal_element(inertia))
+    }
+
+    /// Creates the mass distribution of a solid capsule with the specified mass and radius, whose
+    /// mass is distributed uniformly over its volume. The axis of the capsule is the y-axis of the
+    /// reference frame and `height` is the length of its cylindrical part, i.e. the distance
+    /// between the centers of both hemispherical caps. The center of mass lies at the origin.
+    ///
+    /// Returns an error, if the radius is zero.
+    pub fn solid_capsule(mass: T, radius: T, height: T) -> Result<Self, Error> {
+        // split the mass between the cylinder and the two hemispherical caps by their volumes
+        let (r2, h2) = (radius * radius, height * height);
+        let four_thirds = nalgebra::convert::<f64, T>(4.0 / 3.0);
+        let m_cylinder = mass * height / (height + four_thirds * radius);
+        let m_caps = mass - m_cylinder;
+        let two_fifths = nalgebra::convert::<f64, T>(0.4);
+        let i_axis = m_cylinder * r2 * T::half() + m_caps * two_fifths * r2;
+        let i_perp = m_cylinder * (r2 * T::half() * T::half() + h2 / nalgebra::convert::<f64, T>(12.0))
+            + m_caps * (two_fifths * r2 + h2 * T::half() * T::half()
+                + nalgebra::convert::<f64, T>(0.375) * height * radius);
+        Self::new(mass, Vector3::zeros(), Matrix3::from_diagonal(&Vector3::new(i_perp, i_axis, i_perp)))
+    }
+
+    /// Creates the mass distribution of a solid cylinder with the specified mass, radius and
+    /// height, whose mass is distributed uniformly over its volume. The axis of the cylinder is the
+    /// y-axis of the reference frame and the center of mass lies at its origin.
+    ///
+    /// Returns an error, if the radius is zero.
+    pub fn solid_cylinder(mass: T, radius: T, height: T) -> Result<Self, Error> {
+        let (r2, h2) = (radius * radius, height * height);
+        let i_perp = mass * (nalgebra::convert::<f64, T>(3.0) * r2 + h2) / nalgebra::convert::<f64, T>(12.0);
+        Self::new(mass, Vector3::zeros(), Matrix3::from_diagonal(&Vector3::new(i_perp, mass * r2 * T::half(), i_perp)))
+    }
+
+    /// Combines the mass distributions of multiple parts into the mass distribution of the whole
+    /// body, like for the children of a `CompoundCollider`. Each part is specified by its state in
+    /// the reference frame of the body and its mass distribution in its own reference frame.
+    ///
+    /// The total mass is the sum of the masses of the parts and the center of mass is their mass
+    /// weighted mean. The inertia tensors of the parts are rotated into the reference frame of the
+    /// body and shifted to the common center of mass with the parallel axis theorem. The scale of
+    /// the states only moves the centers of mass of the parts, so the mass distributions have to
+    /// be specified for the scaled parts. If any of the parts is immovable, so is the body.
+    ///
+    /// Returns an error, if there are no parts or if the combined inertia tensor cannot be
+    /// inverted.
+    pub fn combine<'a>(
+        parts: impl IntoIterator<Item = (&'a Transformer<T>, &'a MassDistribution<T>)>
+    ) -> Result<Self, Error> where T: 'a {
+        let parts = parts.into_iter()
+            .map(|(state, mass)| (state.trafo_point(&mass.center_of_mass), state.rot, mass))
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            return Err(err!(physics "Cannot combine the mass distributions of zero parts"));
+        }
+        if parts.iter().any(|(_, _, mass)| mass.is_immovable()) {
+            return Ok(Self::immovable());
+        }
+
+        let mass = parts.iter().fold(T::zero(), |sum, (_, _, m)| sum + m.mass);
+        let com = parts.iter().fold(Vector3::zeros(), |sum, (c, _, m)| sum + c * m.mass) / mass;
+        let inertia = parts.iter().fold(Matrix3::zeros(), |sum, (c, rot, m)| {
+            let r = rot.to_rotation_matrix();
+            let d = c - com;
+            let shift = (Matrix3::identity() * d.norm_squared() - d * d.transpose()) * m.mass;
+            sum + r.matrix() * m.inertia * r.matrix().transpose() + shift
+        });
+        Self::new(mass, com, inertia)
+    }
+}
+
 impl<T> MassDistribution<T> {
-    /// Returns the total mass of the mass distribution.
+    /// Returns the total mass of the mass distribution. For static bodies, the mass is infinite.
     pub fn mass(&self) -> &T {
         &self.mass
     }
 
+    /// Returns the inverse of the total mass, which is zero for static bodies.
+    pub fn inverse_mass(&self) -> &T {
+        &self.inv_mass
+    }
+
     /// Returns the center of mass of the mass distribution.
     ///
     /// In an inertia system (`IS`) the center of mass will usually be used as the `offset` in the
@@ -368,8 +730,7 @@ impl<T> Default for Transformer<T>
 where T: Scalar + Zero + One + RealField {
     fn default() -> Self {
         Transformer {
-            mat: Matrix4::identity(),
-            inv_mat: Matrix4::identity(),
+            matrices: OnceLock::from((Matrix4::identity(), Matrix4::identity())),
             pos: Vector3::zeros(),
             offset: Vector3::zeros(),
             rot: UnitQuaternion::identity(),
@@ -378,13 +739,25 @@ where T: Scalar + Zero + One + RealField {
     }
 }
 
+/// Tolerance of `Transformer::from_matrix` for the deviation of the normalized matrix columns
+/// from an orthonormal basis, and of the last matrix row from the affine row.
+const DECOMPOSITION_TOLERANCE: f64 = 1e-6;
+
 impl<T> Transformer<T>
 where T: BaseFloat {
 
+    /// Creates a new transformer state and generates its transformation matrices.
+    ///
+    /// The inverse transformation divides by each scale component. A scale component of zero
+    /// flattens the state, which cannot be inverted: the inverse matrix then contains infinities
+    /// and every inverse transform yields non-finite values. Use `try_new` for scales that are not
+    /// known to be valid.
     pub fn new(pos: Vector3<T>, rot: UnitQuaternion<T>, scale: Vector3<T>, offset: Vector3<T>) -> Self {
         Transformer {
-            mat: Self::gen_mat(&pos, &rot, &scale, &offset),
-            inv_mat: Self::gen_inv_mat(&pos, &rot, &scale, &offset),
+            matrices: OnceLock::from((
+                Self::gen_mat(&pos, &rot, &scale, &offset),
+                Self::gen_inv_mat(&pos, &rot, &scale, &offset),
+            )),
             pos,
             rot,
             scale,
@@ -392,10 +765,185 @@ where T: BaseFloat {
         }
     }
 
-    /// Updates the transformation matrices of this transformer.
+    /// Creates a new transformer state like `new`, but returns a math error instead, if any scale
+    /// component is zero or not finite, such that the state cannot be inverted.
+    pub fn try_new(
+        pos: Vector3<T>,
+        rot: UnitQuaternion<T>,
+        scale: Vector3<T>,
+        offset: Vector3<T>
+    ) -> Result<Self, Error> {
+        Self::check_scale(&scale)?;
+        Ok(Self::new(pos, rot, scale, offset))
+    }
+
+    /// Creates a transformer state with the translation and rotation of the isometry. The scale of
+    /// the state is one and its offset is zero.
+    pub fn from_isometry(iso: &Isometry3<T>) -> Self {
+        Self::new(iso.translation.vector, iso.rotation, Vector3::repeat(T::one()), Vector3::zeros())
+    }
+
+    /// Decomposes the affine transformation matrix `m` into a transformer state with a zero
+    /// offset. The scale is given by the norms of the columns of the upper 3x3 block and the
+    /// rotation by the normalized columns. Mirroring matrices are decomposed with a negative x
+    /// scale. The state keeps `m` and its inverse as its transformation matrices.
+    ///
+    /// Returns a math error, if `m` is not affine, contains a zero scale, or shears, such that its
+    /// normalized columns are not orthogonal.
+    pub fn from_matrix(m: &Matrix4<T>) -> Result<Self, Error> {
+        let tolerance: T = nalgebra::convert(DECOMPOSITION_TOLERANCE);
+        let bottom = m.fixed_view::<1, 4>(3, 0);
+        if (0..3).any(|i| <T as Signed>::abs(&bottom[i]) > tolerance)
+            || <T as Signed>::abs(&(bottom[3] - T::one())) > tolerance {
+            return Err(err!(math "Transformation matrix must be affine"));
+        }
+
+        let linear = m.fixed_view::<3, 3>(0, 0).into_owned();
+        let mut scale = Vector3::from_fn(|i, _| linear.column(i).norm());
+        Self::check_scale(&scale)?;
+        let mut basis = Matrix3::from_fn(|r, c| linear[(r, c)] / scale[c]);
+        if ((basis.transpose() * basis) - Matrix3::identity()).amax() > tolerance {
+            return Err(err!(math "Transformation matrix must not contain shear"));
+        }
+        if basis.determinant() < T::zero() {
+            scale.x = -scale.x;
+            basis.set_column(0, &-basis.column(0));
+        }
+
+        let inv_mat = m.try_inverse()
+            .ok_or_else(|| err!(math "Transformation matrix must be invertible"))?;
+        Ok(Transformer {
+            pos: m.fixed_view::<3, 1>(0, 3).into_owned(),
+            offset: Vector3::zeros(),
+            scale,
+            rot: UnitQuaternion::from_matrix(&basis),
+            matrices: OnceLock::from((*m, inv_mat)),
+        })
+    }
+
+    /// Returns the isometry that rotates and translates points like this transformer state. The
+    /// offset is folded into the translation, while the scale is dropped, so the isometry is only
+    /// equivalent to the state for a scale of one.
+    pub fn to_isometry(&self) -> Isometry3<T> {
+        Isometry3::from_parts(Translation3::from(self.pos + self.rot * self.scale.component_mul(&self.offset)), self.rot)
+    }
+
+    /// Creates a transformer state with the translation, rotation and uniform scale of the
+    /// similarity. The offset of the state is zero.
+    pub fn from_similarity(sim: &Similarity3<T>) -> Self {
+        Self::new(
+            sim.isometry.translation.vector,
+            sim.isometry.rotation,
+            Vector3::repeat(sim.scaling()),
+            Vector3::zeros()
+        )
+    }
+
+    /// Returns the similarity that transforms points like this transformer state, with the offset
+    /// folded into the translation. Similarities only support uniform scales, so `None` is
+    /// returned if the scale components differ, or if the scale is zero.
+    pub fn to_similarity(&self) -> Option<Similarity3<T>> {
+        let scale = self.scale.x;
+        if self.scale.y != scale || self.scale.z != scale || scale == T::zero() {
+            return None;
+        }
+        Some(Similarity3::from_parts(
+            Translation3::from(self.pos + self.rot * self.offset.scale(scale)),
+            self.rot,
+            scale
+        ))
+    }
+
+    /// Interpolates between this and the `other` transformer state, where `t = 0` yields this
+    /// state and `t = 1` yields `other`. The position, offset and scale are interpolated linearly
+    /// and the rotation is interpolated spherically along the shortest arc. `t` is clamped to
+    /// `[0, 1]`.
+    ///
+    /// This is used to render states in between two fixed simulation steps, with the interpolation
+    /// factor returned by `PhysicsEngine::step`.
+    pub fn interpolate(&self, other: &Transformer<T>, t: T) -> Transformer<T> {
+        if t <= T::zero() {
+            return self.clone();
+        } else if t >= T::one() {
+            return other.clone();
+        }
+
+        let rot = self.rot.try_slerp(&other.rot, t, T::default_epsilon())
+            .unwrap_or_else(|| UnitQuaternion::new_normalize(self.rot.lerp(&other.rot, t)));
+        Transformer::new(
+            self.pos.lerp(&other.pos, t),
+            rot,
+            self.scale.lerp(&other.scale, t),
+            self.offset.lerp(&other.offset, t),
+        )
+    }
+
+    /// Updates the transformation matrices of this transformer. For a scale component of zero,
+    /// the inverse matrix contains infinities (see `new`).
     pub fn update_transformation(&mut self) {
-        self.mat = Self::gen_mat(&self.pos, &self.rot, &self.scale, &self.offset);
-        self.inv_mat = Self::gen_inv_mat(&self.pos, &self.rot, &self.scale, &self.offset);
+        self.matrices = OnceLock::from((
+            Self::gen_mat(&self.pos, &self.rot, &self.scale, &self.offset),
+            Self::gen_inv_mat(&self.pos, &self.rot, &self.scale, &self.offset),
+        ));
+    }
+
+    /// Sets the position of the state. The transformation matrices are generated again on their
+    /// next access, such that many changes only require a single update.
+    pub fn set_pos(&mut self, pos: Vector3<T>) {
+        self.pos = pos;
+        self.matrices = OnceLock::new();
+    }
+
+    /// Sets the rotation of the state, see `set_pos`.
+    pub fn set_rot(&mut self, rot: UnitQuaternion<T>) {
+        self.rot = rot;
+        self.matrices = OnceLock::new();
+    }
+
+    /// Sets the scale of the state, see `set_pos`.
+    pub fn set_scale(&mut self, scale: Vector3<T>) {
+        self.scale = scale;
+        self.matrices = OnceLock::new();
+    }
+
+    /// Sets the offset of the state, see `set_pos`.
+    pub fn set_offset(&mut self, offset: Vector3<T>) {
+        self.offset = offset;
+        self.matrices = OnceLock::new();
+    }
+
+    /// Returns true, if the state was changed through one of the setters since the transformation
+    /// matrices were generated last. Changes to the public fields are not tracked; after changing
+    /// the fields directly, `update_transformation` has to be called.
+    pub fn is_dirty(&self) -> bool {
+        self.matrices.get().is_none()
+    }
+
+    /// Returns the transformation matrix and its inverse, and generates them first, if the state
+    /// is dirty.
+    fn matrices(&self) -> &(Matrix4<T>, Matrix4<T>) {
+        self.matrices.get_or_init(|| (
+            Self::gen_mat(&self.pos, &self.rot, &self.scale, &self.offset),
+            Self::gen_inv_mat(&self.pos, &self.rot, &self.scale, &self.offset),
+        ))
+    }
+
+    /// Updates the transformation matrices of this transformer like `update_transformation`, but
+    /// returns a math error instead, if any scale component is zero or not finite. In this case,
+    /// the matrices keep their previous values.
+    pub fn try_update_transformation(&mut self) -> Result<(), Error> {
+        Self::check_scale(&self.scale)?;
+        self.update_transformation();
+        Ok(())
+    }
+
+    /// Checks that the scale can be inverted, which requires all components to be finite and
+    /// non-zero.
+    fn check_scale(scale: &Vector3<T>) -> Result<(), Error> {
+        if scale.iter().any(|s| *s == T::zero() || !s.is_finite()) {
+            return Err(err!(math "Transformer scale must be finite and non-zero"));
+        }
+        Ok(())
     }
 
     /// Generates a transformation matrix for the specified transformer state.
@@ -416,15 +964,58 @@ where T: BaseFloat {
 
     /// Returns the transformation matrix for this transformer.
     pub fn tsro(&self) -> &Matrix4<T> {
-        &self.mat
+        &self.matrices().0
     }
 
     /// Returns the inverse transformation matrix for this transformer.
     pub fn inv_tsro(&self) -> &Matrix4<T> {
-        &self.inv_mat
+        &self.matrices().1
     }
 }
 
+/// Implements the conversions between bevy's `Transform` and the transformer state for a float
+/// type. Bevy stores its quaternions in x, y, z, w order, while nalgebra stores them as w, i, j, k.
+///
+/// Bevy transforms have no offset. When converting into a `Transform`, the offset is folded into
+/// the translation, such that both transform points the same way; converting back then yields a
+/// transformer state with a zero offset and the shifted position. Transformer states that are
+/// converted from a `Transform` always have a zero offset.
+#[cfg(feature="bevy_support")]
+macro_rules! bevy_transform_conversions {
+    ($T:ty) => (
+        #[allow(clippy::unnecessary_cast)]
+        impl From<&Transform> for Transformer<$T> {
+            fn from(t: &Transform) -> Self {
+                Transformer::new(
+                    Vector3::new(t.translation.x as $T, t.translation.y as $T, t.translation.z as $T),
+                    UnitQuaternion::new_normalize(nalgebra::Quaternion::new(
+                        t.rotation.w as $T, t.rotation.x as $T, t.rotation.y as $T, t.rotation.z as $T,
+                    )),
+                    Vector3::new(t.scale.x as $T, t.scale.y as $T, t.scale.z as $T),
+                    Vector3::zeros(),
+                )
+            }
+        }
+
+        #[allow(clippy::unnecessary_cast)]
+        impl From<&Transformer<$T>> for Transform {
+            fn from(t: &Transformer<$T>) -> Self {
+                let translation = t.pos + t.rot * t.scale.component_mul(&t.offset);
+                Transform {
+                    translation: Vec3::new(translation.x as f32, translation.y as f32, translation.z as f32),
+                    rotation: Quat::from_xyzw(t.rot.i as f32, t.rot.j as f32, t.rot.k as f32, t.rot.w as f32),
+                    scale: Vec3::new(t.scale.x as f32, t.scale.y as f32, t.scale.z as f32),
+                }
+            }
+        }
+    );
+}
+
+#[cfg(feature="bevy_support")]
+bevy_transform_conversions!(f32);
+#[cfg(feature="bevy_support")]
+bevy_transform_conversions!(f64);
+
 macro_rules! mat_vec_mul_row {
     ($mat:expr, point $point:expr, ($row:tt)) => (
         unsafe {
@@ -446,34 +1037,38 @@ macro_rules! mat_vec_mul_row {
 impl<T> Transformer<T>
 where T: BaseFloat {
     pub fn trafo_point(&self, point: &Vector3<T>) -> Vector3<T> {
+        let mat = self.tsro();
         Vector3::new(
-            mat_vec_mul_row!(self.mat, point point, (0)),
-            mat_vec_mul_row!(self.mat, point point, (1)),
-            mat_vec_mul_row!(self.mat, point point, (2)),
+            mat_vec_mul_row!(mat, point point, (0)),
+            mat_vec_mul_row!(mat, point point, (1)),
+            mat_vec_mul_row!(mat, point point, (2)),
         )
     }
 
     pub fn trafo_vec(&self, vec: &Vector3<T>) -> Vector3<T> {
+        let mat = self.tsro();
         Vector3::new(
-            mat_vec_mul_row!(self.mat, vec vec, (0)),
-            mat_vec_mul_row!(self.mat, vec vec, (1)),
-            mat_vec_mul_row!(self.mat, vec vec, (2)),
+            mat_vec_mul_row!(mat, vec vec, (0)),
+            mat_vec_mul_row!(mat, vec vec, (1)),
+            mat_vec_mul_row!(mat, vec vec, (2)),
         )
     }
 
     pub fn inv_trafo_point(&self, point: &Vector3<T>) -> Vector3<T> {
+        let mat = self.inv_tsro();
         Vector3::new(
-            mat_vec_mul_row!(self.inv_mat, point point, (0)),
-            mat_vec_mul_row!(self.inv_mat, point point, (1)),
-            mat_vec_mul_row!(self.inv_mat, point point, (2)),
+            mat_vec_mul_row!(mat, point point, (0)),
+            mat_vec_mul_row!(mat, point point, (1)),
+            mat_vec_mul_row!(mat, point point, (2)),
         )
     }
 
     pub fn inv_trafo_vec(&self, vec: &Vector3<T>) -> Vector3<T> {
+        let mat = self.inv_tsro();
         Vector3::new(
-            mat_vec_mul_row!(self.inv_mat, vec vec, (0)),
-            mat_vec_mul_row!(self.inv_mat, vec vec, (1)),
-            mat_vec_mul_row!(self.inv_mat, vec vec, (2)),
+            mat_vec_mul_row!(mat, vec vec, (0)),
+            mat_vec_mul_row!(mat, vec vec, (1)),
+            mat_vec_mul_row!(mat, vec vec, (2)),
         )
     }
 
@@ -492,18 +1087,17 @@ where T: BaseFloat {
             rot: self.trafo_rot(&trafo.rot),
             scale: self.scale.component_mul(&trafo.scale),
 
-            mat: self.mat * trafo.mat,
-            inv_mat: trafo.inv_mat * self.inv_mat,
+            matrices: OnceLock::from((self.tsro() * trafo.tsro(), trafo.inv_tsro() * self.inv_tsro())),
         }
     }
 
     pub fn trafo_mut(&self, trafo: &mut Transformer<T>) {
+        let matrices = (self.tsro() * trafo.tsro(), trafo.inv_tsro() * self.inv_tsro());
         trafo.pos = self.trafo_point(&trafo.pos);
         trafo.offset = self.trafo_vec(&trafo.offset);
         trafo.rot = self.trafo_rot(&trafo.rot);
         trafo.scale.component_mul_assign(&self.scale);
-        trafo.mat = self.mat * trafo.mat;
-        trafo.inv_mat = trafo.inv_mat * self.inv_mat;
+        trafo.matrices = OnceLock::from(matrices);
     }
 
     pub fn inv_trafo(&self, trafo: &Transformer<T>) -> Transformer<T> {
@@ -513,18 +1107,17 @@ where T: BaseFloat {
             rot: self.inv_trafo_rot(&trafo.rot),
             scale: trafo.scale.component_div(&self.scale),
 
-            mat: self.inv_mat * trafo.mat,
-            inv_mat: trafo.inv_mat * self.mat,
+            matrices: OnceLock::from((self.inv_tsro() * trafo.tsro(), trafo.inv_tsro() * self.tsro())),
         }
     }
 
     pub fn inv_trafo_mut(&self, trafo: &mut Transformer<T>) {
+        let matrices = (self.inv_tsro() * trafo.tsro(), trafo.inv_tsro() * self.tsro());
         trafo.pos = self.inv_trafo_point(&trafo.pos);
         trafo.offset = self.inv_trafo_vec(&trafo.offset);
         trafo.rot = self.inv_trafo_rot(&trafo.rot);
         trafo.scale.component_div_assign(&self.scale);
-        trafo.mat = self.inv_mat * trafo.mat;
-        trafo.inv_mat = trafo.inv_mat * self.mat;
+        trafo.matrices = OnceLock::from(matrices);
     }
 
     /// Generates an inverted copy of the transformation state.
@@ -534,18 +1127,18 @@ where T: BaseFloat {
             offset: -self.offset,
             rot: self.rot.conjugate(),
             scale: Vector3::repeat(T::one()).component_div(&self.scale),
-            mat: self.inv_mat,
-            inv_mat: self.mat,
+            matrices: OnceLock::from((*self.inv_tsro(), *self.tsro())),
         }
     }
 
     /// Inverts the current transformation state instance.
     pub fn inverse_mut(&mut self) {
+        let (mat, inv_mat) = *self.matrices();
         self.pos = -self.pos;
         self.offset = -self.offset;
         self.rot.conjugate_mut();
         self.scale = Vector3::repeat(T::one()).component_div(&self.scale);
-        mem::swap(&mut self.inv_mat, &mut self.mat);
+        self.matrices = OnceLock::from((inv_mat, mat));
     }
 
     /// Returns the vector pointing to the 'right' in the laboratory frame for the current transformer
@@ -590,3 +1183,449 @@ where T: BaseFloat {
         -mat::forward(&self.rot)
     }
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Isometry3, Matrix3, Matrix4, Point3, Similarity3, UnitQuaternion, Vector3};
+    use crate::system::inertia::{Error, ErrorType, IS, MassDistribution, relative_point_velocity, Transformer};
+
+    #[test]
+    fn interpolate() {
+        let a = Transformer::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            UnitQuaternion::identity(),
+            Vector3::repeat(1.0),
+            Vector3::zeros(),
+        );
+        let b = Transformer::new(
+            Vector3::new(2.0, 4.0, 0.0),
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f64::consts::FRAC_PI_2),
+            Vector3::repeat(3.0),
+            Vector3::new(1.0, 0.0, 0.0),
+        );
+
+        let mid = a.interpolate(&b, 0.5);
+        assert_eq!(mid.pos, Vector3::new(1.0, 2.0, 0.0));
+        assert_eq!(mid.scale, Vector3::repeat(2.0));
+        assert_eq!(mid.offset, Vector3::new(0.5, 0.0, 0.0));
+        assert!((mid.rot.angle() - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+        // the matrices are regenerated for the interpolated state
+        assert!((mid.trafo_point(&Vector3::zeros()) - (mid.pos + mid.rot * mid.offset * 2.0)).norm() < 1e-12);
+
+        // the endpoints are returned as they are, even for factors outside of [0, 1]
+        assert_eq!(a.interpolate(&b, 0.0).pos, a.pos);
+        assert_eq!(a.interpolate(&b, -1.0).rot, a.rot);
+        assert_eq!(a.interpolate(&b, 1.0).pos, b.pos);
+        assert_eq!(a.interpolate(&b, 2.0).rot, b.rot);
+    }
+
+    #[test]
+    fn velocities() {
+        let mass = MassDistribution::new(
+            2.0,
+            Vector3::zeros(),
+            Matrix3::from_diagonal(&Vector3::new(1.0, 2.0, 4.0))
+        ).ok().unwrap();
+        let mut is = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), mass);
+
+        is.set_linear_velocity(Vector3::new(3.0, 0.0, -1.0));
+        is.set_angular_velocity(Vector3::new(1.0, 0.5, 0.25));
+        assert_eq!(is.momentum, Vector3::new(6.0, 0.0, -2.0));
+        assert_eq!(is.angular_mom, Vector3::new(1.0, 1.0, 1.0));
+        assert!((is.get_angular_vel() - Vector3::new(1.0, 0.5, 0.25)).norm() < 1e-12);
+        // 0.5 * 2 * 10 + 0.5 * (1 * 1 + 2 * 0.25 + 4 * 0.0625)
+        assert!((is.kinetic_energy() - 10.875).abs() < 1e-12);
+
+        let mut floor = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), MassDistribution::immovable());
+        floor.set_linear_velocity(Vector3::repeat(1.0));
+        floor.set_angular_velocity(Vector3::repeat(1.0));
+        assert_eq!(floor.momentum, Vector3::zeros());
+        assert_eq!(floor.angular_mom, Vector3::zeros());
+        assert_eq!(floor.kinetic_energy(), 0.0);
+    }
+
+    #[test]
+    fn point_velocity() {
+        let mass = MassDistribution::new(1.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        let state = Transformer::new(
+            Vector3::new(5.0, 0.0, 0.0),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+            Vector3::repeat(1.0),
+            Vector3::zeros(),
+        );
+        let mut is = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state, mass);
+        // translating along x while spinning about z
+        is.set_linear_velocity(Vector3::new(1.0, 0.0, 0.0));
+        is.set_angular_velocity(Vector3::new(0.0, 0.0, 2.0));
+
+        // the spin cancels the translation one unit above the center and doubles it below
+        assert!((is.point_velocity_world(&Vector3::new(5.0, 1.0, 0.0)) - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-12);
+        assert!((is.point_velocity_world(&Vector3::new(5.0, -1.0, 0.0)) - Vector3::new(3.0, 0.0, 0.0)).norm() < 1e-12);
+        assert!((is.point_velocity_world(&Vector3::new(5.0, 0.0, 0.0)) - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-12);
+
+        // the same point in the reference frame of the rotated system lies on its x-axis
+        let local = is.trafo_point_into(&Vector3::new(5.0, 1.0, 0.0));
+        assert!((local - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-12);
+        assert!((is.get_point_vel(&local) - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-12);
+
+        let floor = IS::<f64>::new(Vector3::repeat(1.0), Vector3::repeat(1.0), Transformer::default(), MassDistribution::immovable());
+        assert_eq!(floor.point_velocity_world(&Vector3::new(1.0, 2.0, 3.0)), Vector3::zeros());
+    }
+
+    #[test]
+    fn rotated_spin() {
+        // the x-axis of the body is the y-axis of the laboratory frame, and the inertia tensor
+        // differs for every axis
+        let inertia = Matrix3::from_diagonal(&Vector3::new(1.0, 2.0, 4.0));
+        let mass = MassDistribution::new(1.0, Vector3::zeros(), inertia).ok().unwrap();
+        let state = Transformer::new(
+            Vector3::new(5.0, 0.0, 0.0),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+            Vector3::repeat(1.0),
+            Vector3::zeros(),
+        );
+        let mut is = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state, mass);
+        is.set_angular_velocity(Vector3::new(1.0, 0.0, 0.0));
+        assert!((is.world_angular_vel() - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-12);
+
+        // spinning about the y-axis of the laboratory frame moves the point beside the center
+        // along -z
+        let point = Vector3::new(6.0, 0.0, 0.0);
+        assert!((is.point_velocity_world(&point) - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-12);
+        let local = is.trafo_point_into(&point);
+        assert!((is.trafo_vec_outof(&is.get_point_vel(&local)) - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-12);
+
+        // without torque, the angular momentum is conserved in the laboratory frame
+        is.angular_mom = Vector3::new(1.0, 1.0, 1.0);
+        let world_mom = is.state.rot * is.angular_mom;
+        for _ in 0..10 {
+            is.integrate(0.01);
+        }
+        assert!((is.state.rot * is.angular_mom - world_mom).norm() < 1e-12);
+    }
+
+    #[test]
+    fn relative_velocity() {
+        let mass = MassDistribution::new(1.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        let rot = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2);
+        let state = |x: f64| Transformer::new(Vector3::new(x, 0.0, 0.0), rot, Vector3::repeat(1.0), Vector3::zeros());
+        let mut a = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state(0.0), mass.clone());
+        let mut b = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state(2.0), mass);
+        a.set_linear_velocity(Vector3::new(1.0, 0.0, 0.0));
+        a.set_angular_velocity(Vector3::new(0.0, 0.0, 2.0));
+        b.set_linear_velocity(Vector3::new(-1.0, 0.0, 0.0));
+
+        // the point of contact moves up on the spinning body A, while B approaches along x
+        let point = Vector3::new(1.0, 0.0, 0.0);
+        let rel = relative_point_velocity(&a, &b, &point);
+        assert!((rel - Vector3::new(-2.0, -2.0, 0.0)).norm() < 1e-12);
+        assert_eq!(relative_point_velocity(&b, &a, &point), -rel);
+
+        // the same as the velocities of the point in the reference frames of both bodies
+        let va = a.trafo_vec_outof(&a.get_point_vel(&a.trafo_point_into(&point)));
+        let vb = b.trafo_vec_outof(&b.get_point_vel(&b.trafo_point_into(&point)));
+        assert!((rel - (vb - va)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn world_impulse() {
+        let inertia = Matrix3::from_diagonal(&Vector3::new(1.0, 2.0, 4.0));
+        let mass = MassDistribution::new(2.0, Vector3::new(0.5, 0.0, 0.0), inertia).ok().unwrap();
+        let rot = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.3);
+        let state = Transformer::new(Vector3::new(1.0, 2.0, 3.0), rot, Vector3::repeat(1.0), Vector3::zeros());
+        let mut is = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state, mass);
+        // the center of mass is rotated onto the y-axis of the laboratory frame
+        let com = Vector3::new(1.0, 2.5, 3.0);
+        assert!((is.world_center_of_mass() - com).norm() < 1e-12);
+
+        // an impulse through the center of mass only pushes the system
+        is.apply_impulse_world(&Vector3::new(0.0, 0.0, 4.0), &com);
+        assert!((is.momentum - Vector3::new(0.0, 0.0, 4.0)).norm() < 1e-12);
+        assert!(is.angular_mom.norm() < 1e-12);
+
+        // an impulse one unit beside the center of mass also spins the system about z
+        is.apply_impulse_world(&Vector3::new(0.0, 1.0, 0.0), &(com + Vector3::x()));
+        assert!((is.momentum - Vector3::new(0.0, 1.0, 4.0)).norm() < 1e-12);
+        assert!((is.state.rot * is.angular_mom - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-12);
+
+        // the world frame helper matches the manually transformed body frame helper
+        let (imp, point) = (Vector3::new(0.3, -1.0, 2.0), Vector3::new(-1.0, 4.0, 2.5));
+        let mut manual = is.clone();
+        is.apply_impulse_world(&imp, &point);
+        manual.apply_impulse(&manual.trafo_vec_into(&imp), &manual.trafo_point_into(&point));
+        assert!((is.momentum - manual.momentum).norm() < 1e-12);
+        assert!((is.angular_mom - manual.angular_mom).norm() < 1e-12);
+
+        let mut floor = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), MassDistribution::immovable());
+        floor.apply_impulse_world(&Vector3::repeat(1.0), &Vector3::x());
+        assert_eq!(floor.momentum, Vector3::zeros());
+        assert_eq!(floor.angular_mom, Vector3::zeros());
+    }
+
+    #[test]
+    fn world_force() {
+        let inertia = Matrix3::from_diagonal(&Vector3::new(3.0, 1.0, 2.0));
+        let mass = MassDistribution::new(2.0, Vector3::new(0.0, 0.5, 0.0), inertia).ok().unwrap();
+        let rot = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.7);
+        let state = Transformer::new(Vector3::new(-2.0, 1.0, 0.5), rot, Vector3::repeat(1.0), Vector3::zeros());
+        let mut a = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), state, mass);
+        let mut b = a.clone();
+
+        // a force is an impulse over time, applied in the reference frame of the system
+        let (force, point) = (Vector3::new(2.0, 0.0, -4.0), Vector3::new(-1.0, 2.0, 1.0));
+        a.apply_force_world(&force, &point, 0.25);
+        b.apply_impulse(&b.trafo_vec_into(&force.scale(0.25)), &b.trafo_point_into(&point));
+        assert!((a.momentum - Vector3::new(0.5, 0.0, -1.0)).norm() < 1e-12);
+        assert!((a.momentum - b.momentum).norm() < 1e-12);
+        assert!((a.angular_mom - b.angular_mom).norm() < 1e-12);
+
+        // the torque about the center of mass, rotated into the reference frame of the system
+        let r = point - a.world_center_of_mass();
+        let torque = rot.inverse_transform_vector(&r.cross(&force.scale(0.25)));
+        assert!((a.angular_mom - torque).norm() < 1e-12);
+    }
+
+    #[test]
+    fn slow_spin() {
+        let mass = MassDistribution::new(1.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        let mut is = IS::<f64>::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), mass);
+        let omega = 1e-6;
+        is.set_angular_velocity(Vector3::new(0.0, 0.0, omega));
+
+        let dt = 1.0 / 60.0;
+        let steps = 100_000;
+        for _ in 0..steps {
+            is.integrate(dt);
+        }
+        let expected = omega * dt * steps as f64;
+        assert!((is.state.rot.angle() - expected).abs() < 1e-12);
+        assert!((is.state.rot.axis().unwrap().into_inner() - Vector3::z()).norm() < 1e-9);
+        assert!((is.state.rot.quaternion().norm() - 1.0).abs() < 1e-12);
+
+        // fast rotations use the exact exponential map
+        is.state.rot = UnitQuaternion::identity();
+        is.set_angular_velocity(Vector3::new(0.0, std::f64::consts::PI, 0.0));
+        is.integrate(0.5);
+        assert!((is.state.rot.angle() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invalid_scale() {
+        let state = Transformer::try_new(
+            Vector3::new(1.0, 2.0, 3.0),
+            UnitQuaternion::identity(),
+            Vector3::new(2.0, 1.0, 0.5),
+            Vector3::zeros(),
+        ).ok().unwrap();
+        assert_eq!(state.inv_trafo_point(&Vector3::new(3.0, 3.0, 3.5)), Vector3::new(1.0, 1.0, 1.0));
+
+        for scale in [Vector3::new(1.0, 0.0, 1.0), Vector3::new(f64::INFINITY, 1.0, 1.0), Vector3::repeat(f64::NAN)] {
+            assert!(Transformer::try_new(Vector3::zeros(), UnitQuaternion::identity(), scale, Vector3::zeros()).is_err());
+
+            // the matrices are not touched by a failed update
+            let mut invalid = state.clone();
+            invalid.scale = scale;
+            assert!(invalid.try_update_transformation().is_err());
+            assert_eq!(invalid.inv_tsro(), state.inv_tsro());
+        }
+    }
+
+    #[test]
+    fn isometry() {
+        let points = [Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, -2.0, 0.5), Vector3::new(-3.0, 4.0, 8.0)];
+        let iso = Isometry3::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.4, -0.2, 1.1));
+        let state = Transformer::from_isometry(&iso);
+        for p in &points {
+            assert!((state.trafo_point(p) - (iso * Point3::from(*p)).coords).norm() < 1e-12);
+        }
+
+        // offsets are folded into the translation
+        let mut state = Transformer::new(
+            Vector3::new(-1.0, 0.5, 2.0),
+            UnitQuaternion::from_euler_angles(1.0, 0.2, -0.7),
+            Vector3::repeat(1.0),
+            Vector3::new(0.25, 0.0, -1.0),
+        );
+        let iso = state.to_isometry();
+        for p in &points {
+            assert!((state.trafo_point(p) - (iso * Point3::from(*p)).coords).norm() < 1e-12);
+        }
+
+        state.scale = Vector3::repeat(2.5);
+        state.update_transformation();
+        let sim = state.to_similarity().unwrap();
+        for p in &points {
+            assert!((state.trafo_point(p) - (sim * Point3::from(*p)).coords).norm() < 1e-12);
+        }
+        let sim = Similarity3::new(Vector3::new(0.0, 1.0, -1.0), Vector3::new(0.0, 2.0, 0.0), 0.5);
+        let state = Transformer::from_similarity(&sim);
+        for p in &points {
+            assert!((state.trafo_point(p) - (sim * Point3::from(*p)).coords).norm() < 1e-12);
+        }
+
+        let state = Transformer::new(Vector3::zeros(), UnitQuaternion::identity(), Vector3::new(1.0, 2.0, 1.0), Vector3::zeros());
+        assert!(state.to_similarity().is_none());
+    }
+
+    #[cfg(feature="bevy_support")]
+    #[test]
+    fn bevy_round_trip() {
+        use bevy::prelude::Transform;
+
+        let state = Transformer::<f32>::new(
+            Vector3::new(1.0, -2.0, 3.5),
+            UnitQuaternion::from_euler_angles(0.3, -1.2, 2.0),
+            Vector3::new(1.0, 2.0, 0.5),
+            Vector3::zeros(),
+        );
+        let transform: Transform = (&state).into();
+        assert_eq!(transform.rotation.w, state.rot.w);
+        let back: Transformer<f32> = (&transform).into();
+        assert!((back.pos - state.pos).norm() < 1e-6);
+        assert!(back.rot.angle_to(&state.rot) < 1e-6);
+        assert!((back.scale - state.scale).norm() < 1e-6);
+
+        // the offset is folded into the translation
+        let mut offset = state.clone();
+        offset.offset = Vector3::new(0.5, 0.0, 1.0);
+        offset.update_transformation();
+        let back: Transformer<f32> = (&Transform::from(&offset)).into();
+        let p = Vector3::new(0.2, 0.4, -0.6);
+        assert!((back.trafo_point(&p) - offset.trafo_point(&p)).norm() < 1e-5);
+    }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn serde_round_trip() {
+        let state = Transformer::new(
+            Vector3::new(1.0, -2.0, 3.5),
+            UnitQuaternion::from_euler_angles(0.3, -1.2, 2.0),
+            Vector3::new(1.0, 2.0, 0.5),
+            Vector3::new(0.5, 0.0, 1.0),
+        );
+        // the matrices are not part of the serialized state
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(!json.contains("mat"));
+        let back: Transformer<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.tsro(), state.tsro());
+        assert_eq!(back.inv_tsro(), state.inv_tsro());
+
+        let inertia = Matrix3::new(2.0, 0.5, 0.0, 0.5, 3.0, 0.0, 0.0, 0.0, 1.0);
+        let is = IS::new(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.5, 0.0),
+            state,
+            MassDistribution::new(2.0, Vector3::zeros(), inertia).ok().unwrap()
+        );
+        let back: IS<f64> = serde_json::from_str(&serde_json::to_string(&is).unwrap()).unwrap();
+        assert_eq!(back.momentum, is.momentum);
+        assert_eq!(*back.mass.inverse_mass(), 0.5);
+        assert_eq!(back.mass.inv_inertia(), is.mass.inv_inertia());
+        assert_eq!(back.state.tsro(), is.state.tsro());
+    }
+
+    #[test]
+    fn from_matrix() {
+        let rot = UnitQuaternion::from_euler_angles(0.4, -1.1, 2.3);
+        let t = Transformer::new(Vector3::new(1.0, -2.0, 3.0), rot, Vector3::new(2.0, 0.5, 3.0), Vector3::zeros());
+        let decomposed = Transformer::from_matrix(t.tsro()).ok().unwrap();
+        assert!((decomposed.pos - t.pos).norm() < 1e-12);
+        assert!((decomposed.scale - t.scale).norm() < 1e-12);
+        assert!(decomposed.rot.angle_to(&rot) < 1e-9);
+        assert!((decomposed.inv_tsro() - t.inv_tsro()).amax() < 1e-12);
+
+        // mirroring matrices result in a negative x scale
+        let mirrored = t.tsro() * Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, -1.0));
+        let decomposed = Transformer::from_matrix(&mirrored).ok().unwrap();
+        assert!(decomposed.scale.x < 0.0);
+        let p = Vector3::new(0.3, 0.7, -1.2);
+        assert!((decomposed.trafo_point(&p) - mirrored.transform_point(&Point3::from(p)).coords).norm() < 1e-12);
+
+        let mut shear = Matrix4::<f64>::identity();
+        shear[(0, 1)] = 0.5;
+        assert!(Transformer::from_matrix(&shear).is_err());
+        let flat = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 0.0, 1.0));
+        assert!(Transformer::from_matrix(&flat).is_err());
+        let projective = Matrix4::new_perspective(1.0, 1.0, 0.1, 10.0);
+        assert!(Transformer::from_matrix(&projective).is_err());
+    }
+
+    #[test]
+    fn error_display() {
+        let error = Transformer::<f64>::try_new(Vector3::zeros(), UnitQuaternion::identity(), Vector3::zeros(), Vector3::zeros())
+            .unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::MathError);
+        let text = format!("{}", error);
+        assert!(text.starts_with("math error: "));
+        assert!(text.contains(error.message().unwrap()));
+        assert_eq!(format!("{}", Error::new(ErrorType::PhysicsError, None)), "physics error");
+
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert_eq!(boxed.to_string(), text);
+    }
+
+    #[test]
+    fn lazy_matrices() {
+        let mut t = Transformer::<f64>::default();
+        let rot = UnitQuaternion::from_euler_angles(0.2, 0.4, -0.6);
+        t.set_pos(Vector3::new(1.0, 2.0, 3.0));
+        t.set_rot(rot);
+        t.set_scale(Vector3::new(2.0, 1.0, 0.5));
+        t.set_offset(Vector3::new(0.0, -1.0, 0.0));
+        assert!(t.is_dirty());
+
+        // the first access generates the matrices for all changes at once
+        let expected = Transformer::new(t.pos, rot, t.scale, t.offset);
+        assert!((t.tsro() - expected.tsro()).amax() < 1e-12);
+        assert!(!t.is_dirty());
+        assert!((t.inv_tsro() - expected.inv_tsro()).amax() < 1e-12);
+        let p = Vector3::new(0.5, -0.5, 2.0);
+        assert!((t.inv_trafo_point(&t.trafo_point(&p)) - p).norm() < 1e-12);
+
+        // composing with a dirty state uses its current fields
+        let mut other = Transformer::<f64>::default();
+        other.set_pos(Vector3::new(0.0, 0.0, 5.0));
+        t.trafo_mut(&mut other);
+        assert!((other.tsro() - expected.tsro() * Matrix4::new_translation(&Vector3::new(0.0, 0.0, 5.0))).amax() < 1e-12);
+    }
+
+    #[test]
+    fn inverse_mass() {
+        let mass = MassDistribution::new(4.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        assert_eq!(*mass.inverse_mass() * *mass.mass(), 1.0);
+        assert!(MassDistribution::<f64>::immovable().is_immovable());
+
+        // integration moves the body by the momentum over the mass
+        let mut is = IS::new(Vector3::new(2.0, 0.0, -1.0), Vector3::zeros(), Transformer::default(), mass);
+        is.integrate(0.5);
+        assert_eq!(is.state.pos, Vector3::new(0.25, 0.0, -0.125));
+    }
+
+    #[test]
+    fn combine() {
+        let part = MassDistribution::new(1.0, Vector3::zeros(), Matrix3::from_diagonal(&Vector3::new(0.1, 0.2, 0.3)))
+            .ok().unwrap();
+        let quarter = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2);
+        let left = Transformer::new(Vector3::new(-1.0, 0.0, 0.0), UnitQuaternion::identity(), Vector3::repeat(1.0), Vector3::zeros());
+        let right = Transformer::new(Vector3::new(1.0, 0.0, 0.0), quarter, Vector3::repeat(1.0), Vector3::zeros());
+
+        // the rotated part swaps its moments about the x- and y-axis, and both parts are shifted
+        // by one unit along the x-axis
+        let combined = MassDistribution::combine([(&left, &part), (&right, &part)]).ok().unwrap();
+        assert_eq!(*combined.mass(), 2.0);
+        assert!(combined.center_of_mass().norm() < 1e-12);
+        assert!((combined.inertia() - Matrix3::from_diagonal(&Vector3::new(0.3, 2.3, 2.6))).norm() < 1e-12);
+
+        // a heavier part pulls the center of mass towards it
+        let heavy = MassDistribution::new(3.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        let combined = MassDistribution::combine([(&left, &part), (&right, &heavy)]).ok().unwrap();
+        assert!((combined.center_of_mass() - Vector3::new(0.5, 0.0, 0.0)).norm() < 1e-12);
+
+        let floor = MassDistribution::immovable();
+        assert!(MassDistribution::combine([(&left, &part), (&right, &floor)]).ok().unwrap().is_immovable());
+        assert!(MassDistribution::<f64>::combine([]).is_err());
+    }
+}
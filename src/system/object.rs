@@ -1,54 +1,170 @@
+use crate::collision::contact::{Contact, shape_shape};
 use crate::helper::BaseFloat;
 use crate::system::inertia::{IS, MassDistribution, Transformer};
 use crate::volume::aabb::AABB;
+use crate::volume::BoundingVolume;
+use crate::volume::capsule::Capsule;
 use crate::volume::oriented::OBB;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
 use crate::volume::tlas::TLASElement;
 
 #[cfg(feature="bevy_support")]
-use bevy::prelude::{Component, Res, Time};
-
+use bevy::prelude::Component;
 
-use nalgebra::Vector3;
-use crate::volume::BoundingVolume;
 
+use nalgebra::{Matrix3, UnitQuaternion, Vector3};
 
-#[cfg(feature="bevy_support")]
-#[derive(Clone, PartialEq, Component)]
-pub struct PhyEntityID {
-    pub world_id: u8,
-    pub chunk_id: usize,
-    pub entity_id: usize,
-}
 
-#[cfg(not(feature="bevy_support"))]
-#[derive(Clone, PartialEq)]
+/// Identifier of an entity in the physics engine. With the `bevy_support` feature, the id is a
+/// bevy component, such that it can be attached to the entity that renders the body.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature="bevy_support", derive(Component))]
 pub struct PhyEntityID {
     pub world_id: u8,
     pub chunk_id: usize,
     pub entity_id: usize,
+    /// Generation of the entity id. Entity ids are reused after an entity was removed from the
+    /// engine, but with an increased generation, such that stale ids can be detected.
+    pub generation: u32,
 }
 
 
 pub struct PhyEntity<T: BaseFloat> {
     pub id: PhyEntityID,
     pub is: IS<T>,
+    /// Factor that is applied to the global gravity of the engine for this body. A scale of zero
+    /// lets the body float, while a negative scale makes it rise.
+    pub gravity_scale: T,
+    /// Static entities never move, like the floor of a level. They are kept in a separate subtree
+    /// of the TLAS, which is not refit every step, and they are neither integrated nor affected by
+    /// gravity. The contact and constraint solvers treat them as immovable bodies at rest,
+    /// whatever their mass distribution (see `MassDistribution::immovable`).
+    pub is_static: bool,
     collider_id: usize,
-    obb: OBB<T>,
+    /// Collision shape in the reference frame of the body.
+    shape: ShapeVolume<T>,
+    /// Collision shape in the laboratory frame, as of the last call to `sync`.
+    volume: ShapeVolume<T>,
 }
 
 impl<T: BaseFloat> PhyEntity<T> {
-    pub fn cube(id: PhyEntityID, size: Vector3<T>) -> Self {
+    /// Creates an entity with the specified collision shape and mass distribution, placed at the
+    /// origin of the laboratory frame.
+    fn with_shape(id: PhyEntityID, shape: ShapeVolume<T>, mass: MassDistribution<T>) -> Self {
+        let state = Transformer::default();
         PhyEntity {
             id,
-            is: IS::new(Vector3::zeros(), Vector3::zeros(), Transformer::default(), MassDistribution::default()),
+            volume: shape.transformed(&state),
+            is: IS::new(Vector3::zeros(), Vector3::zeros(), state, mass),
+            gravity_scale: T::one(),
+            is_static: false,
             collider_id: 0,
-            obb: OBB { half_size: size.scale(T::half()), transform: Transformer::default() }
+            shape,
         }
     }
 
+    /// Creates a box with the specified edge lengths. The box has unit mass, which is distributed
+    /// uniformly over its volume.
+    pub fn cube(id: PhyEntityID, size: Vector3<T>) -> Self {
+        let mass = MassDistribution::solid_box(T::one(), size)
+            .expect("the edges of a box must not be zero");
+        let obb = OBB { half_size: size.scale(T::half()), transform: Transformer::default() };
+        Self::with_shape(id, ShapeVolume::Box(obb), mass)
+    }
+
+    /// Creates a sphere with the specified radius. The sphere has unit mass, which is distributed
+    /// uniformly over its volume.
+    pub fn sphere(id: PhyEntityID, radius: T) -> Self {
+        let mass = MassDistribution::solid_sphere(T::one(), radius)
+            .expect("the radius of a sphere must be positive");
+        Self::with_shape(id, ShapeVolume::Sphere(Sphere::new(Vector3::zeros(), radius)), mass)
+    }
+
+    /// Creates a capsule with the specified radius, whose axis is the y-axis of the body. The
+    /// `height` is the length of the cylindrical part of the capsule, such that its total height
+    /// is `height + 2 * radius`. The capsule has unit mass, which is distributed uniformly over its
+    /// volume.
+    pub fn capsule(id: PhyEntityID, radius: T, height: T) -> Self {
+        let mass = MassDistribution::solid_capsule(T::one(), radius, height)
+            .expect("the radius of a capsule must be positive");
+        let half = Vector3::new(T::zero(), height * T::half(), T::zero());
+        Self::with_shape(id, ShapeVolume::Capsule(Capsule::new(-half, half, radius)), mass)
+    }
+
+    /// Creates a capsule around the segment from `a` to `b` with the specified radius, like
+    /// `capsule`. The entity is placed at the center of the segment, such that it rotates around
+    /// its center of mass.
+    pub fn capsule_between(id: PhyEntityID, a: Vector3<T>, b: Vector3<T>, radius: T) -> Self {
+        let center = (a + b) * T::half();
+        let axis = b - a;
+        let height = axis.norm();
+        let mass = MassDistribution::solid_capsule(T::one(), radius, height)
+            .expect("the radius of a capsule must be positive");
+
+        // rotate the tensor from the y-axis onto the axis of the segment
+        let (i_perp, i_axis) = (mass.inertia()[(0, 0)], mass.inertia()[(1, 1)]);
+        let inertia = if height <= T::default_epsilon() {
+            *mass.inertia()
+        } else {
+            let u = axis / height;
+            Matrix3::from_diagonal_element(i_perp) + u * u.transpose() * (i_axis - i_perp)
+        };
+        let mass = MassDistribution::new(T::one(), Vector3::zeros(), inertia)
+            .expect("the radius of a capsule must be positive");
+
+        let mut entity = Self::with_shape(
+            id,
+            ShapeVolume::Capsule(Capsule::new(a - center, b - center, radius)),
+            mass
+        );
+        entity.is.state.pos = center;
+        entity.sync();
+        entity
+    }
+
+    /// Creates a cylinder with the specified radius and height, whose axis is the y-axis of the
+    /// body. The cylinder has unit mass, which is distributed uniformly over its volume.
+    ///
+    /// The cylinder volume (see `volume::cylinder::Cylinder`) has no intersectors with the other
+    /// collision shapes yet, so the entity collides as the bounding box of the cylinder. Such a
+    /// wheel does not roll.
+    pub fn cylinder(id: PhyEntityID, radius: T, height: T) -> Self {
+        let mass = MassDistribution::solid_cylinder(T::one(), radius, height)
+            .expect("the radius of a cylinder must be positive");
+        let obb = OBB {
+            half_size: Vector3::new(radius, height * T::half(), radius),
+            transform: Transformer::default(),
+        };
+        Self::with_shape(id, ShapeVolume::Box(obb), mass)
+    }
+
+    /// Returns the collision shape of the entity in the reference frame of the body.
+    pub fn shape(&self) -> &ShapeVolume<T> {
+        &self.shape
+    }
+
     pub fn sync(&mut self) {
         self.is.sync();
-        self.obb.transform = self.is.state.clone();
+        self.volume = self.shape.transformed(&self.is.state);
+    }
+
+    /// Returns the body that stands in for a static entity in the contact and constraint solvers:
+    /// an immovable body at rest in the state of the entity, regardless of the mass distribution
+    /// and momentum of the entity itself.
+    pub(crate) fn static_body(&self) -> IS<T> {
+        let mass = MassDistribution::immovable();
+        IS::new(Vector3::zeros(), Vector3::zeros(), self.is.state.clone(), mass)
+    }
+
+    /// Generates the contact between this and the `other` entity, if their collision shapes
+    /// overlap. Following the normal convention of `ContactManifold`, the normal always points
+    /// from the entity with the lower id to the entity with the higher id, regardless of which
+    /// entity this method is called on.
+    pub fn contact(&self, other: &Self) -> Option<Contact<T>> {
+        let (a, b) = if self.id <= other.id { (self, other) } else { (other, self) };
+        let manifold = shape_shape(&a.volume, &b.volume)?;
+        Some(Contact::new(a.id.clone(), b.id.clone(), manifold))
     }
 
     pub fn tick(&mut self, time: f64) {
@@ -57,17 +173,190 @@ impl<T: BaseFloat> PhyEntity<T> {
     }
 }
 
+/// Builder for entities, which starts from an entity of one of the shape constructors, like
+/// `PhyEntity::cube`, and sets up its state through chained setters. `build` syncs the entity,
+/// such that its collision shape matches the state right away.
+///
+/// Velocities are applied once the entity is built, so they take the final mass distribution into
+/// account regardless of the order of the setters.
+pub struct PhyEntityBuilder<T: BaseFloat> {
+    entity: PhyEntity<T>,
+    linear_velocity: Option<Vector3<T>>,
+    angular_velocity: Option<Vector3<T>>,
+}
+
+impl<T: BaseFloat> PhyEntityBuilder<T> {
+    pub fn new(entity: PhyEntity<T>) -> Self {
+        PhyEntityBuilder {
+            entity,
+            linear_velocity: None,
+            angular_velocity: None,
+        }
+    }
+
+    /// Sets the position of the entity in the laboratory frame.
+    pub fn position(mut self, pos: Vector3<T>) -> Self {
+        self.entity.is.state.pos = pos;
+        self
+    }
+
+    /// Sets the rotation of the entity in the laboratory frame.
+    pub fn rotation(mut self, rot: UnitQuaternion<T>) -> Self {
+        self.entity.is.state.rot = rot;
+        self
+    }
+
+    /// Sets the velocity of the center of mass of the entity (see `IS::set_linear_velocity`).
+    pub fn linear_velocity(mut self, vel: Vector3<T>) -> Self {
+        self.linear_velocity = Some(vel);
+        self
+    }
+
+    /// Sets the angular velocity of the entity (see `IS::set_angular_velocity`).
+    pub fn angular_velocity(mut self, omega: Vector3<T>) -> Self {
+        self.angular_velocity = Some(omega);
+        self
+    }
+
+    /// Replaces the mass distribution of the entity.
+    pub fn mass(mut self, mass: MassDistribution<T>) -> Self {
+        self.entity.is.mass = mass;
+        self
+    }
+
+    /// Replaces the collision shape of the entity, which is specified in the reference frame of
+    /// the body.
+    pub fn collider(mut self, shape: ShapeVolume<T>) -> Self {
+        self.entity.shape = shape;
+        self
+    }
+
+    /// Sets the factor that is applied to the global gravity for the entity.
+    pub fn gravity_scale(mut self, scale: T) -> Self {
+        self.entity.gravity_scale = scale;
+        self
+    }
+
+    /// Marks the entity as static, see `PhyEntity::is_static`. Static entities are built with an
+    /// immovable mass distribution, which replaces the mass set by `mass`, and without velocities.
+    pub fn is_static(mut self, is_static: bool) -> Self {
+        self.entity.is_static = is_static;
+        self
+    }
+
+    /// Applies the velocities and returns the synced entity.
+    pub fn build(mut self) -> PhyEntity<T> {
+        if self.entity.is_static {
+            self.entity.is.mass = MassDistribution::immovable();
+        }
+        if let Some(vel) = self.linear_velocity {
+            self.entity.is.set_linear_velocity(vel);
+        }
+        if let Some(omega) = self.angular_velocity {
+            self.entity.is.set_angular_velocity(omega);
+        }
+        self.entity.sync();
+        self.entity
+    }
+}
+
 impl<T: BaseFloat> TLASElement<T, 3> for PhyEntity<T> {
-    type BV = OBB<T>;
+    type BV = ShapeVolume<T>;
 
     fn wrap(&self) -> AABB<T, 3> {
-        AABB {
-            min: self.obb.min(),
-            max: self.obb.max(),
-        }
+        AABB { min: self.volume.min(), max: self.volume.max() }
     }
 
     fn bounding_volume(&self) -> &Self::BV {
-        &self.obb
+        &self.volume
+    }
+
+    fn is_static(&self) -> bool {
+        self.is_static
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+    use crate::system::inertia::MassDistribution;
+    use crate::system::object::{PhyEntity, PhyEntityBuilder};
+    use crate::test_util::id;
+    use crate::volume::shape::ShapeVolume;
+    use crate::volume::sphere::Sphere;
+    use crate::volume::BoundingVolume;
+    use crate::volume::tlas::TLASElement;
+
+    #[test]
+    fn shapes() {
+        let sphere = PhyEntity::<f64>::sphere(id(0), 0.5);
+        assert_eq!(*sphere.is.mass.inertia(), nalgebra::Matrix3::from_diagonal_element(0.1));
+        assert_eq!(sphere.wrap().max, Vector3::repeat(0.5));
+
+        // the capsule is centered on its segment and spins most easily around its axis
+        let capsule = PhyEntity::<f64>::capsule_between(id(1), Vector3::new(1.0, 1.0, 0.0), Vector3::new(1.0, 3.0, 0.0), 0.5);
+        assert_eq!(capsule.is.state.pos, Vector3::new(1.0, 2.0, 0.0));
+        assert!((capsule.bounding_volume().center() - Vector3::new(1.0, 2.0, 0.0)).norm() < 1e-12);
+        assert!((capsule.wrap().min - Vector3::new(0.5, 0.5, -0.5)).norm() < 1e-12);
+        let inertia = capsule.is.mass.inertia();
+        assert!(inertia[(1, 1)] < inertia[(0, 0)]);
+        assert!((inertia[(0, 0)] - inertia[(2, 2)]).abs() < 1e-12);
+        let upright = PhyEntity::<f64>::capsule(id(2), 0.5, 2.0);
+        assert!((upright.is.mass.inertia() - inertia).norm() < 1e-12);
+        assert!((upright.wrap().max - Vector3::new(0.5, 1.5, 0.5)).norm() < 1e-12);
+
+        // the contact normal points from the lower to the higher id
+        let mut sphere = sphere;
+        sphere.is.state.pos = Vector3::new(1.0, 0.2, 0.0);
+        sphere.sync();
+        let contact = capsule.contact(&sphere).unwrap();
+        assert_eq!(contact.a, id(0));
+        assert!((contact.normal() - Vector3::y()).norm() < 1e-12);
+        assert!((contact.depth() - 0.2).abs() < 1e-12);
+
+        // the cylinder has the inertia tensor of a uniform solid and is bounded by a box
+        let cylinder = PhyEntity::<f64>::cylinder(id(3), 0.5, 2.0);
+        let expected = Vector3::new(0.75 + 4.0, 1.5, 0.75 + 4.0) / 12.0;
+        assert!((cylinder.is.mass.inertia().diagonal() - expected).norm() < 1e-12);
+        assert!((cylinder.wrap().max - Vector3::new(0.5, 1.0, 0.5)).norm() < 1e-12);
+
+        // the box has the inertia tensor of a uniform solid
+        let cube = PhyEntity::<f64>::cube(id(4), Vector3::new(1.0, 2.0, 3.0));
+        let expected = Vector3::new(4.0 + 9.0, 1.0 + 9.0, 1.0 + 4.0) / 12.0;
+        assert!((cube.is.mass.inertia().diagonal() - expected).norm() < 1e-12);
+    }
+
+    #[test]
+    fn builder() {
+        let entity = PhyEntityBuilder::new(PhyEntity::<f64>::cube(id(0), Vector3::repeat(1.0)))
+            .linear_velocity(Vector3::new(1.0, 0.0, 0.0))
+            .mass(MassDistribution::new(2.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap())
+            .position(Vector3::new(0.0, 3.0, 0.0))
+            .rotation(UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_4))
+            .angular_velocity(Vector3::new(0.0, 1.0, 0.0))
+            .collider(ShapeVolume::Sphere(Sphere::new(Vector3::zeros(), 0.5)))
+            .gravity_scale(0.5)
+            .build();
+        // the velocities respect the mass, even though it was set afterwards
+        assert_eq!(entity.is.momentum, Vector3::new(2.0, 0.0, 0.0));
+        assert_eq!(entity.is.angular_mom, Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(entity.gravity_scale, 0.5);
+
+        // the collision shape is synced with the state
+        assert!((entity.bounding_volume().center() - Vector3::new(0.0, 3.0, 0.0)).norm() < 1e-12);
+        assert!((entity.wrap().max - Vector3::new(0.5, 3.5, 0.5)).norm() < 1e-12);
+        assert!((entity.is.state.trafo_point(&Vector3::x()) - Vector3::new(0.5_f64.sqrt(), 3.0 + 0.5_f64.sqrt(), 0.0)).norm() < 1e-12);
+
+        // static entities are immovable, regardless of the order of the setters
+        let floor = PhyEntityBuilder::new(PhyEntity::<f64>::cube(id(1), Vector3::new(20.0, 1.0, 20.0)))
+            .is_static(true)
+            .mass(MassDistribution::new(2.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap())
+            .linear_velocity(Vector3::new(1.0, 0.0, 0.0))
+            .build();
+        assert!(floor.is_static);
+        assert!(floor.is.mass.is_immovable());
+        assert_eq!(floor.is.momentum, Vector3::zeros());
     }
 }
@@ -0,0 +1,50 @@
+//! Fixtures that are shared between the unit tests of the crate.
+
+use nalgebra::{UnitQuaternion, Vector3};
+use crate::system::inertia::Transformer;
+use crate::system::object::{PhyEntity, PhyEntityID};
+use crate::volume::oriented::OBB;
+
+/// Returns the id of the entity with index `entity_id` in the first chunk of the first world.
+pub fn id(entity_id: usize) -> PhyEntityID {
+    PhyEntityID { world_id: 0, chunk_id: 0, entity_id, generation: 0 }
+}
+
+/// Returns the unscaled transformation to the position `pos` with the rotation `rot`.
+pub fn at(pos: Vector3<f64>, rot: UnitQuaternion<f64>) -> Transformer<f64> {
+    Transformer::new(pos, rot, Vector3::repeat(1.0), Vector3::zeros())
+}
+
+/// Returns the box with the half size `half_size`, centered at `pos` and rotated by `rot`.
+pub fn cuboid(pos: Vector3<f64>, rot: UnitQuaternion<f64>, half_size: Vector3<f64>) -> OBB<f64> {
+    OBB { half_size, transform: at(pos, rot) }
+}
+
+/// Returns the box with an edge length of 1, centered at `pos` and rotated by `rot`.
+pub fn unit_box(pos: Vector3<f64>, rot: UnitQuaternion<f64>) -> OBB<f64> {
+    cuboid(pos, rot, Vector3::repeat(0.5))
+}
+
+/// Returns the corners of the box with an edge length of 1, centered at `center` and rotated by
+/// `rot`.
+pub fn cube_corners(center: Vector3<f64>, rot: UnitQuaternion<f64>) -> Vec<Vector3<f64>> {
+    (0..8)
+        .map(|i| {
+            let corner = Vector3::new(
+                if i & 1 == 0 { -0.5 } else { 0.5 },
+                if i & 2 == 0 { -0.5 } else { 0.5 },
+                if i & 4 == 0 { -0.5 } else { 0.5 },
+            );
+            center + rot * corner
+        })
+        .collect()
+}
+
+/// Returns the cube entity with an edge length of 1 and the id `id(entity_id)`, which rests at
+/// the position `pos`.
+pub fn cube_entity(entity_id: usize, pos: Vector3<f64>) -> PhyEntity<f64> {
+    let mut entity = PhyEntity::cube(id(entity_id), Vector3::repeat(1.0));
+    entity.is.state.pos = pos;
+    entity.sync();
+    entity
+}
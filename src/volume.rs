@@ -1,11 +1,22 @@
+use std::mem;
+use std::ops::Range;
 use nalgebra::SVector;
+use crate::helper::BaseFloat;
 
 pub mod aabb;
+pub mod capsule;
+pub mod compound;
+pub mod cylinder;
 pub mod tlas;
 pub mod bvh;
 pub mod bvh_splitting;
+pub mod frustum;
 pub mod oriented;
 pub mod point;
+pub mod shape;
+pub mod plane;
+pub mod sphere;
+pub mod swept;
 
 
 pub trait BoundingVolume<T, const DIM: usize> {
@@ -21,6 +32,12 @@ pub trait BoundingVolume<T, const DIM: usize> {
     /// representative for it. This way, computational cost may be saved by returning an
     /// approximation for the total area. The return value of this method may be used, for example,
     /// for a traversal-cost analysis using BVH construction.
+    ///
+    /// The only promise is that the value grows monotonically with the measure of the boundary of
+    /// the volume in `DIM` dimensions (the perimeter in 2D and the surface area in 3D), such that
+    /// the values of volumes of the same type can be compared. Values of different volume types
+    /// are not comparable, and geometric quantities should use dedicated methods like
+    /// `AABB::surface_area` instead.
     fn area(&self) -> T;
 
     /// Returns the minimal euclidean x, y & z coordinate of this bounding volume.
@@ -41,6 +58,31 @@ pub trait BoundingVolume<T, const DIM: usize> {
     /// to `size() * 0.5` but that may be more inefficient in certain contexts, like OBBs, which
     /// store the half size of the box directly.
     fn half_size(&self) -> SVector<T, DIM>;
+
+    /// Returns the euclidean distance from the specified point to the closest point of the volume.
+    /// Points on or inside of the volume have a distance of zero. This is used by nearest
+    /// neighbour queries, like `BVH::nearest`.
+    ///
+    /// The default implementation measures the distance to the box between `min` and `max`, by
+    /// clamping the point to the box and measuring the remaining offset. This is exact for AABBs
+    /// and a lower bound for all other volumes, which should override it with their exact
+    /// distance where one is available.
+    fn distance_to(&self, point: &SVector<T, DIM>) -> T
+    where T: BaseFloat {
+        let (min, max) = (self.min(), self.max());
+        let mut dist = T::zero();
+        for i in 0..DIM {
+            let d = if point[i] < min[i] {
+                min[i] - point[i]
+            } else if point[i] > max[i] {
+                point[i] - max[i]
+            } else {
+                T::zero()
+            };
+            dist += d * d;
+        }
+        dist.sqrt()
+    }
 }
 
 pub trait BVIntersector<T, O: BoundingVolume<T, DIM>, const DIM: usize> {
@@ -48,3 +90,194 @@ pub trait BVIntersector<T, O: BoundingVolume<T, DIM>, const DIM: usize> {
     /// specified bounding volume.
     fn intersects(&self, other: &O) -> bool;
 }
+
+/// Volumes and elements that opt in to distance based queries, like `BVH::nearest` and the sphere
+/// casts. The distance to a point is measured through `BoundingVolume::distance_to`.
+pub trait DistanceTo<T: BaseFloat, const DIM: usize>: BoundingVolume<T, DIM> {
+    /// Moves a sphere with the specified `radius` from `origin` along the normalized direction
+    /// `dir` and returns the distance it travelled when it first touches the volume, or `None` if
+    /// it does not touch the volume within `max_dist`. A sphere that already touches the volume at
+    /// its origin returns zero.
+    ///
+    /// The default implementation uses conservative advancement, which requires the volume to be
+    /// convex: since the distance to the volume shrinks at most as fast as the sphere moves, the
+    /// sphere can always be advanced by its current distance to the volume without passing
+    /// through it. Once the distance grows again, the sphere has missed the volume.
+    fn sphere_cast(&self, origin: &SVector<T, DIM>, dir: &SVector<T, DIM>, radius: T, max_dist: T) -> Option<T> {
+        let tolerance = T::default_epsilon().sqrt();
+        let mut t = T::zero();
+        let mut gap = self.distance_to(origin) - radius;
+        for _ in 0..SPHERE_CAST_ITERATIONS {
+            if gap <= tolerance {
+                return Some(t);
+            }
+            t += gap;
+            if t > max_dist {
+                return None;
+            }
+            let next = self.distance_to(&(origin + dir.scale(t))) - radius;
+            if next >= gap {
+                return None;
+            }
+            gap = next;
+        }
+        None
+    }
+}
+
+/// Maximum number of steps of `DistanceTo::sphere_cast`, after which a sphere that still has not
+/// touched the volume counts as a miss. Only spheres that graze the volume need that many steps.
+const SPHERE_CAST_ITERATIONS: usize = 256;
+
+/// Result buffer for queries like `BVH::intersect_into`, which is owned by the caller and reused
+/// across many queries, e.g. one per entity and frame. This way, the allocation of the result
+/// vector is only done once instead of for every query.
+///
+/// The query results borrow from the queried structure, so the buffer cannot hold on to them.
+/// Instead, it keeps the allocation as an empty vector of raw pointers and lends it out as an empty
+/// `Vec` of references for the duration of a closure through `with`.
+pub struct QueryBuffer<E> {
+    buf: Vec<*const E>,
+}
+
+// SAFETY: the buffer only stands in for a `Vec<&E>`, which is `Send` and `Sync` if `E` is `Sync`.
+// Outside of `with`, the pointer vector is empty anyway.
+unsafe impl<E: Sync> Send for QueryBuffer<E> {}
+unsafe impl<E: Sync> Sync for QueryBuffer<E> {}
+
+impl<E> QueryBuffer<E> {
+    pub fn new() -> Self {
+        QueryBuffer { buf: Vec::new() }
+    }
+
+    pub fn with_capacity(n: usize) -> Self {
+        QueryBuffer { buf: Vec::with_capacity(n) }
+    }
+
+    /// Returns the number of results the buffer can hold without allocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Calls `f` with the empty result vector of this buffer. Results pushed into the vector are
+    /// dropped when `f` returns, but the capacity they took up is kept for the next call.
+    pub fn with<'a, R>(&mut self, f: impl FnOnce(&mut Vec<&'a E>) -> R) -> R
+    where
+        E: 'a,
+    {
+        let mut v = lend(mem::take(&mut self.buf));
+        let r = f(&mut v);
+        self.buf = reclaim(v);
+        r
+    }
+}
+
+impl<E> Default for QueryBuffer<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clears the pointer vector and hands out its allocation as an empty vector of references.
+fn lend<'a, E>(mut v: Vec<*const E>) -> Vec<&'a E> {
+    v.clear();
+    let mut v = mem::ManuallyDrop::new(v);
+    // SAFETY: `*const E` and `&E` have the same size and alignment, and the vector is empty, so
+    // no reference is ever read from a pointer.
+    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as *mut &'a E, 0, v.capacity()) }
+}
+
+/// Clears the vector of references and takes its allocation back as an empty pointer vector.
+fn reclaim<E>(mut v: Vec<&E>) -> Vec<*const E> {
+    v.clear();
+    let mut v = mem::ManuallyDrop::new(v);
+    // SAFETY: see `lend`.
+    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as *mut *const E, 0, v.capacity()) }
+}
+
+/// Hierarchical structure whose nodes and elements can be traversed lazily through an
+/// `IntersectIter`. Nodes and elements are addressed by their indices within the structure.
+pub trait IntersectTraversal<I, E> {
+    /// Returns true, if the bounds of the node with index `node` intersect the intersector.
+    fn node_intersects(&self, intersector: &I, node: usize) -> bool;
+
+    /// Returns the indices of the two children of the node with index `node`, or `None` if the
+    /// node is a leaf node.
+    fn children(&self, node: usize) -> Option<(usize, usize)>;
+
+    /// Returns the index range of the elements that are referenced by the leaf node `node`.
+    fn leaf_elements(&self, node: usize) -> Range<usize>;
+
+    /// Returns the element with index `element`, if it intersects the intersector.
+    fn intersecting_element(&self, intersector: &I, element: usize) -> Option<&E>;
+}
+
+/// Iterator over the elements of a hierarchical structure that intersect an intersector. The
+/// structure is traversed lazily, such that iterating can be stopped early without visiting the
+/// remaining nodes. The traversal stack is owned by the iterator and does not allocate.
+pub struct IntersectIter<'a, I, E> {
+    tree: &'a dyn IntersectTraversal<I, E>,
+    intersector: &'a I,
+    node: Option<usize>,
+    stack: [usize; 64],
+    stack_ptr: usize,
+    elements: Range<usize>,
+}
+
+impl<'a, I, E> IntersectIter<'a, I, E> {
+    /// Creates a new iterator that starts the traversal of `tree` at the node with index `node`.
+    /// If `node` is `None`, the iterator does not yield any elements.
+    pub fn new(tree: &'a dyn IntersectTraversal<I, E>, intersector: &'a I, node: Option<usize>) -> Self {
+        IntersectIter {
+            tree,
+            intersector,
+            node,
+            stack: [0; 64],
+            stack_ptr: 0,
+            elements: 0..0,
+        }
+    }
+}
+
+impl<'a, I, E> Iterator for IntersectIter<'a, I, E> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // check the pending elements of the last leaf node first
+            if let Some(i) = self.elements.next() {
+                if let Some(element) = self.tree.intersecting_element(self.intersector, i) {
+                    return Some(element);
+                }
+                continue;
+            }
+
+            let node = match self.node.take() {
+                Some(node) => node,
+                None if self.stack_ptr == 0 => return None,
+                None => {
+                    self.stack_ptr -= 1;
+                    self.stack[self.stack_ptr]
+                }
+            };
+
+            match self.tree.children(node) {
+                None => self.elements = self.tree.leaf_elements(node),
+                Some((child1, child2)) => {
+                    let inter1 = self.tree.node_intersects(self.intersector, child1);
+                    let inter2 = self.tree.node_intersects(self.intersector, child2);
+                    if inter1 {
+                        // checkout child 1 first and save child 2 for later
+                        self.node = Some(child1);
+                        if inter2 {
+                            self.stack[self.stack_ptr] = child2;
+                            self.stack_ptr += 1;
+                        }
+                    } else if inter2 {
+                        self.node = Some(child2);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,18 @@
 use nalgebra::{DimMin, SVector, Vector3};
+use num::Signed;
+use crate::collision::model::VertexBuffer;
 use crate::helper::{BaseFloat, separated_axis};
-use crate::volume::{BoundingVolume, BVIntersector};
-use crate::volume::oriented::OBB;
+use crate::system::inertia::Transformer;
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+use crate::volume::oriented::{OBB, OBB2D};
 
 /// Axis aligned bounding box.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: BaseFloat + serde::Serialize",
+    deserialize = "T: BaseFloat + serde::Deserialize<'de>"
+)))]
 pub struct AABB<T, const DIM: usize> {
     pub min: SVector<T, DIM>,
     pub max: SVector<T, DIM>
@@ -49,6 +57,13 @@ where T: BaseFloat {
         }
     }
 
+    /// Returns true, if the `other` AABB lies completely within this AABB. Empty boxes, whose min
+    /// bounds exceed their max bounds (see `new()`), are contained in every AABB.
+    pub fn contains(&self, other: &AABB<T, DIM>) -> bool {
+        (0..DIM).all(|i| other.min[i] > other.max[i])
+            || (0..DIM).all(|i| other.min[i] >= self.min[i] && other.max[i] <= self.max[i])
+    }
+
     /// Grows the size of the AABB to wrap the specified point `p`. As the name of this method
     /// implies, this process can only grow the AABB, not shrink it to any extend.
     pub fn grow(&mut self, p: &SVector<T, DIM>) {
@@ -75,6 +90,145 @@ where T: BaseFloat {
             self.max[i] = T::max(self.max[i], p[i]);
         }
     }
+
+    /// Returns a copy of this AABB, which is inflated by `margin` on both sides along each axis.
+    /// This is used to build fat boxes, which keep covering a moving object for a couple of steps.
+    /// Empty boxes (see `new()`) stay empty for non-negative margins.
+    pub fn expanded(&self, margin: &SVector<T, DIM>) -> Self {
+        let mut aabb = *self;
+        aabb.expand_mut(margin);
+        aabb
+    }
+
+    /// Inflates this AABB by `margin` on both sides along each axis (see `expanded`).
+    pub fn expand_mut(&mut self, margin: &SVector<T, DIM>) {
+        self.min -= margin;
+        self.max += margin;
+    }
+
+    /// Returns the smallest AABB that wraps all of the specified `points`. For an empty slice, the
+    /// returned box is the empty, inverted box of `new()`.
+    pub fn from_points(points: &[SVector<T, DIM>]) -> Self {
+        let mut aabb = Self::new();
+        points.iter().for_each(|p| aabb.grow(p));
+        aabb
+    }
+
+    /// Returns the smallest AABB that wraps all vertices of the vertex buffer `vbo` (see
+    /// `from_points`).
+    pub fn fit(vbo: &VertexBuffer<T, DIM>) -> Self {
+        Self::from_points(vbo.vertices())
+    }
+
+    /// Returns the cost estimate of the AABB that is used for the surface area heuristic. This is
+    /// half of the measure of the boundary of the box, i.e. the half perimeter in 2D and half of
+    /// the surface area in 3D. In 1D, the length of the box is used instead.
+    ///
+    /// All acceleration structures use this estimate, such that their costs are comparable.
+    pub fn sah_cost(&self) -> T {
+        let size = self.max - self.min;
+        match DIM {
+            1 => size[0],
+            2 => size[0] + size[1],
+            3 => size[0] * size[1] + size[1] * size[2] + size[2] * size[0],
+            _ => {
+                // sum of the volumes of all faces that touch the min corner
+                let mut sum = T::zero();
+                for i in 0..DIM {
+                    let mut face = T::one();
+                    for j in (0..DIM).filter(|j| *j != i) {
+                        face *= size[j];
+                    }
+                    sum += face;
+                }
+                sum
+            }
+        }
+    }
+
+    /// Returns the true surface area of the AABB, i.e. the measure of its boundary. Unlike
+    /// `area()`, which is a cost estimate for acceleration structures, this is the geometrically
+    /// correct value (the perimeter in 2D).
+    pub fn surface_area(&self) -> T {
+        let size = self.max - self.min;
+        let mut sum = T::zero();
+        for i in 0..DIM {
+            let mut face = T::one();
+            for j in (0..DIM).filter(|j| *j != i) {
+                face *= size[j];
+            }
+            sum += face;
+        }
+        sum * T::two()
+    }
+
+    /// Sweeps this AABB along `velocity` and returns the fraction of the step in `[0, 1]` at which
+    /// it first touches the static AABB `other`, or `None` if both boxes do not touch during the
+    /// step. `velocity` is the displacement of this box over the whole step.
+    ///
+    /// For each axis, the interval of the step in which the boxes overlap along that axis is
+    /// computed; the boxes touch where the intervals of all axes overlap. Along axes without
+    /// movement, the boxes either overlap for the whole step or never. Boxes that already overlap
+    /// at the start of the step return zero.
+    pub fn sweep(&self, velocity: &SVector<T, DIM>, other: &AABB<T, DIM>) -> Option<T> {
+        let mut t_enter = T::zero();
+        let mut t_exit = T::one();
+        for i in 0..DIM {
+            if velocity[i] == T::zero() {
+                if self.max[i] < other.min[i] || self.min[i] > other.max[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let r_vel = T::one() / velocity[i];
+            let t0 = (other.min[i] - self.max[i]) * r_vel;
+            let t1 = (other.max[i] - self.min[i]) * r_vel;
+            t_enter = T::max(t_enter, T::min(t0, t1));
+            t_exit = T::min(t_exit, T::max(t0, t1));
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+        Some(t_enter)
+    }
+
+    /// Returns the volume of the AABB (the area in 2D).
+    pub fn volume(&self) -> T {
+        let size = self.max - self.min;
+        let mut volume = T::one();
+        for i in 0..DIM {
+            volume *= size[i];
+        }
+        volume
+    }
+}
+
+impl<T: BaseFloat> AABB<T, 3> {
+    /// Returns the tight axis aligned box that encloses this box after it has been transformed by
+    /// `t`, including the rotation, scale and offset of the transform. This is the box that is
+    /// needed to place local collision geometry in the laboratory frame.
+    ///
+    /// The extent of the transformed box along each world axis is the sum of the absolute values
+    /// of the transformation matrix row times the half size of this box. An empty box stays empty.
+    pub fn transformed(&self, t: &Transformer<T>) -> AABB<T, 3> {
+        if self.min[0] > self.max[0] {
+            return AABB::new();
+        }
+        let center = t.trafo_point(&self.center());
+        let half_size = self.half_size();
+        let mat = t.tsro();
+        let mut extent = Vector3::zeros();
+        for i in 0..3 {
+            for j in 0..3 {
+                extent[i] += <T as Signed>::abs(&mat[(i, j)]) * half_size[j];
+            }
+        }
+        AABB {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
 }
 
 impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for AABB<T, DIM> {
@@ -83,12 +237,7 @@ impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for AABB<T, DIM> {
     }
 
     fn area(&self) -> T {
-        let size = self.max - self.min;
-        let mut sum = T::zero();
-        for i in 0..DIM {
-            sum += size[i] * size[(i + 1) % DIM];
-        }
-        sum
+        self.sah_cost()
     }
 
     fn min(&self) -> SVector<T, DIM> {
@@ -108,6 +257,8 @@ impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for AABB<T, DIM> {
     }
 }
 
+impl<T: BaseFloat, const DIM: usize> DistanceTo<T, DIM> for AABB<T, DIM> {}
+
 impl<T: BaseFloat, const DIM: usize> BVIntersector<T, AABB<T, DIM>, DIM> for AABB<T, DIM> {
     fn intersects(&self, other: &AABB<T, DIM>) -> bool {
         separated_axis::intersects_aabb_aabb(
@@ -125,6 +276,12 @@ impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for AABB<T, 3> {
     }
 }
 
+impl<T: BaseFloat> BVIntersector<T, OBB2D<T>, 2> for AABB<T, 2> {
+    fn intersects(&self, other: &OBB2D<T>) -> bool {
+        other.intersects(self)
+    }
+}
+
 impl<T: BaseFloat, const DIM: usize> BVIntersector<T, SVector<T, DIM>, DIM> for AABB<T, DIM> {
     fn intersects(&self, other: &SVector<T, DIM>) -> bool {
         // AABB-point intersections are already implemented for the SVector struct. Use that
@@ -132,3 +289,181 @@ impl<T: BaseFloat, const DIM: usize> BVIntersector<T, SVector<T, DIM>, DIM> for
         other.intersects(self)
     }
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{SVector, UnitQuaternion, Vector3};
+    use crate::collision::model::VertexBuffer;
+    use crate::system::inertia::Transformer;
+    use crate::volume::aabb::AABB;
+    use crate::volume::BoundingVolume;
+    use crate::volume::sphere::Sphere;
+
+    fn unit_box(x: f64, y: f64) -> AABB<f64, 2> {
+        AABB { min: SVector::<f64, 2>::new(x, y), max: SVector::<f64, 2>::new(x + 1.0, y + 1.0) }
+    }
+
+    #[test]
+    fn sweep() {
+        let target = unit_box(10.0, 0.0);
+
+        // moving towards the target, the boxes touch after 9 of 20 units
+        let moving = unit_box(0.0, 0.0);
+        assert_eq!(moving.sweep(&SVector::<f64, 2>::new(20.0, 0.0), &target), Some(0.45));
+        assert_eq!(moving.sweep(&SVector::<f64, 2>::new(9.0, 0.0), &target), Some(1.0));
+        // the step ends before the boxes touch
+        assert_eq!(moving.sweep(&SVector::<f64, 2>::new(8.0, 0.0), &target), None);
+        // moving away from the target
+        assert_eq!(moving.sweep(&SVector::<f64, 2>::new(-20.0, 0.0), &target), None);
+
+        // passing just below the target
+        let below = unit_box(0.0, -1.1);
+        assert_eq!(below.sweep(&SVector::<f64, 2>::new(20.0, 0.0), &target), None);
+        // a diagonal movement that passes the corner of the target
+        assert_eq!(moving.sweep(&SVector::<f64, 2>::new(20.0, 20.0), &target), None);
+        // a diagonal movement that hits the target
+        assert_eq!(unit_box(0.0, -9.0).sweep(&SVector::<f64, 2>::new(18.0, 18.0), &target), Some(0.5));
+
+        // already overlapping boxes touch at the start of the step
+        let overlapping = unit_box(10.5, 0.5);
+        assert_eq!(overlapping.sweep(&SVector::zeros(), &target), Some(0.0));
+        assert_eq!(overlapping.sweep(&SVector::<f64, 2>::new(-5.0, 0.0), &target), Some(0.0));
+        assert_eq!(moving.sweep(&SVector::zeros(), &target), None);
+    }
+
+    #[test]
+    fn area() {
+        // a 2 x 3 rectangle has a perimeter of 10; the cost estimate is half of it
+        let rect = AABB { min: SVector::<f64, 2>::zeros(), max: SVector::<f64, 2>::new(2.0, 3.0) };
+        assert_eq!(rect.surface_area(), 10.0);
+        assert_eq!(rect.sah_cost(), 5.0);
+        assert_eq!(rect.area(), rect.sah_cost());
+
+        let cuboid = AABB { min: Vector3::zeros(), max: Vector3::new(1.0, 2.0, 3.0) };
+        assert_eq!(cuboid.surface_area(), 22.0);
+        assert_eq!(cuboid.sah_cost(), 11.0);
+    }
+
+    #[test]
+    fn transformed() {
+        let aabb = AABB { min: Vector3::new(-1.0, -0.5, 0.0), max: Vector3::new(1.0, 0.5, 1.0) };
+
+        // a quarter turn about the z axis swaps the x and y extents
+        let t = Transformer::new(
+            Vector3::new(10.0, 0.0, 0.0),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+            Vector3::new(2.0, 1.0, 1.0),
+            Vector3::zeros(),
+        );
+        let world: AABB<f64, 3> = aabb.transformed(&t);
+        assert!((world.min - Vector3::new(9.5, -2.0, 0.0)).norm() < 1e-12);
+        assert!((world.max - Vector3::new(10.5, 2.0, 1.0)).norm() < 1e-12);
+
+        // an eighth turn grows the unit box to the diagonal of its face
+        let unit = AABB { min: Vector3::repeat(-0.5), max: Vector3::repeat(0.5) };
+        let t = Transformer::new(
+            Vector3::zeros(),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4),
+            Vector3::repeat(1.0),
+            Vector3::zeros(),
+        );
+        let world: AABB<f64, 3> = unit.transformed(&t);
+        let diag = 0.5f64.sqrt();
+        assert!((world.max - Vector3::new(diag, diag, 0.5)).norm() < 1e-12);
+        assert!((world.min + world.max).norm() < 1e-12);
+
+        // all corners of the transformed box lie inside of the result
+        let t = Transformer::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            UnitQuaternion::from_euler_angles(0.3, -0.7, 1.1),
+            Vector3::new(1.0, 2.0, 0.5),
+            Vector3::new(0.1, 0.0, -0.2),
+        );
+        let world = aabb.transformed(&t);
+        for i in 0..8 {
+            let corner = Vector3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            let p = t.trafo_point(&corner);
+            assert!((0..3).all(|k| p[k] >= world.min[k] - 1e-12 && p[k] <= world.max[k] + 1e-12));
+        }
+
+        assert_eq!(AABB::<f64, 3>::new().transformed(&t).min, Vector3::repeat(f64::MAX));
+    }
+
+    #[test]
+    fn from_points() {
+        let points = [
+            Vector3::new(1.0, -2.0, 0.5),
+            Vector3::new(-3.0, 4.0, 0.0),
+            Vector3::new(0.0, 1.0, -6.5),
+        ];
+        let aabb = AABB::from_points(&points);
+        assert_eq!(aabb.min, Vector3::new(-3.0, -2.0, -6.5));
+        assert_eq!(aabb.max, Vector3::new(1.0, 4.0, 0.5));
+        let fitted = AABB::fit(&VertexBuffer::new(points.to_vec()));
+        assert_eq!((fitted.min, fitted.max), (aabb.min, aabb.max));
+
+        // no points result in the empty box
+        let empty = AABB::<f64, 3>::from_points(&[]);
+        assert!(empty.min[0] > empty.max[0]);
+        assert!(aabb.contains(&empty));
+    }
+
+    #[test]
+    fn expanded() {
+        let aabb = unit_box(0.0, 1.0);
+        let margin = SVector::<f64, 2>::new(0.5, 0.25);
+        let fat = aabb.expanded(&margin);
+        assert_eq!(fat.min, SVector::<f64, 2>::new(-0.5, 0.75));
+        assert_eq!(fat.max, SVector::<f64, 2>::new(1.5, 2.25));
+        assert!(fat.contains(&aabb));
+
+        let mut grown = aabb;
+        grown.expand_mut(&margin);
+        assert_eq!((grown.min, grown.max), (fat.min, fat.max));
+
+        // empty boxes stay empty
+        let empty = AABB::<f64, 2>::new().expanded(&margin);
+        assert!(empty.min[0] > empty.max[0]);
+    }
+
+    #[test]
+    fn distance_to() {
+        let aabb = AABB { min: Vector3::<f64>::new(-1.0, 0.0, 2.0), max: Vector3::new(1.0, 4.0, 3.0) };
+
+        // outside of each face, the distance is measured along the face normal
+        for i in 0..3 {
+            let mut below = aabb.center();
+            below[i] = aabb.min[i] - 0.5;
+            assert_eq!(aabb.distance_to(&below), 0.5);
+            let mut above = aabb.center();
+            above[i] = aabb.max[i] + 2.0;
+            assert_eq!(aabb.distance_to(&above), 2.0);
+        }
+        // outside of each corner, the distance is measured to the corner
+        for i in 0..8 {
+            let corner = Vector3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            let dir = (corner - aabb.center()).map(f64::signum);
+            let p = corner + dir.component_mul(&Vector3::new(1.0, 2.0, 2.0));
+            assert!((aabb.distance_to(&p) - 3.0).abs() < 1e-12);
+        }
+        // points on the surface and inside of the box have no distance
+        assert_eq!(aabb.distance_to(&Vector3::new(1.0, 2.0, 2.5)), 0.0);
+        assert_eq!(aabb.distance_to(&aabb.center()), 0.0);
+
+        // volumes with an exact distance override the distance to their bounds
+        let sphere = Sphere::new(Vector3::zeros(), 1.0);
+        let corner = Vector3::repeat(1.0);
+        assert_eq!(AABB { min: sphere.min(), max: sphere.max() }.distance_to(&corner), 0.0);
+        assert!((sphere.distance_to(&corner) - (3f64.sqrt() - 1.0)).abs() < 1e-12);
+    }
+}
@@ -1,11 +1,16 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, Range};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use nalgebra::SVector;
+use crate::collision::intersection::Ray;
 use crate::helper::BaseFloat;
 use crate::volume::aabb::AABB;
-use crate::volume::{BoundingVolume, BVIntersector};
-use crate::volume::bvh_splitting::BVHSplitting;
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo, IntersectIter, IntersectTraversal};
+use crate::volume::bvh_splitting::{BVHSplit, BVHSplitting, SAHCost};
 
 
 /// Basic data structure for a BVH node.
@@ -146,6 +151,9 @@ impl<T: Sized, E: BVHElement<T, DIM>, const DIM: usize> BVHElementPool<T, E, DIM
 
 
 
+/// Default for the maximal number of elements in a leaf node of the BVH, below which nodes are not
+/// subdivided any further (see `BVH::rebuild_with_leaf_size`).
+pub const DEFAULT_MAX_LEAF_PRIMS: usize = 2;
 
 pub struct BVH<T, E, NodePool, ElementPool, const DIM: usize>
 where
@@ -157,6 +165,10 @@ where
     pub elements: ElementPool,
     root: usize,
     nodes_in_use: usize,
+    /// Splits found during the last build, if recording is enabled (see `record_splits`).
+    build_splits: Option<Vec<(usize, BVHSplit<T>)>>,
+    /// Costs that decide whether splitting a node pays off (see `set_sah_cost`).
+    sah_cost: SAHCost<T>,
 
 
     _t: PhantomData<T>,
@@ -177,7 +189,7 @@ where T: BaseFloat + From<u32>,
     /// bvh.rebuild<BVHSplitting>();
     /// ``
     pub fn new(elements: ElementPool) -> Self {
-        let mut pool = VecPool::with_capacity(elements.capacity() * 2 - 1);
+        let mut pool = VecPool::with_capacity(usize::max(elements.capacity() * 2, 2) - 1);
         for _ in 0..pool.vec.capacity() {
             pool.push(BVHNode::new());
         }
@@ -187,11 +199,83 @@ where T: BaseFloat + From<u32>,
             elements,
             root: 0,
             nodes_in_use: 1,
+            build_splits: None,
+            sah_cost: SAHCost::default(),
 
             _t: PhantomData::default(),
             _e: PhantomData::default(),
         }
     }
+
+    /// Deserializes a BVH-tree, that was previously written by `serialize`, from the specified
+    /// reader.
+    ///
+    /// Since elements may not be serializable, the element pool is not part of the serialized data
+    /// and has to be supplied separately. The elements in the pool must be in the same order as
+    /// the elements of the serialized tree *after* it was build, as building the tree reorders the
+    /// element pool.
+    ///
+    /// The loaded tree is checked with `validate`, such that corrupted data, like out of range
+    /// node or element indices, fails with `io::ErrorKind::InvalidData` instead of a panic during
+    /// later queries.
+    pub fn deserialize(r: &mut impl Read, elements: ElementPool) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != SERIAL_MAGIC {
+            return Err(invalid_data("not a serialized BVH"));
+        }
+        if r.read_u16::<LittleEndian>()? != SERIAL_VERSION {
+            return Err(invalid_data("unsupported BVH version"));
+        }
+        if r.read_u8()? as usize != DIM {
+            return Err(invalid_data("BVH dimension mismatch"));
+        }
+        let float_size = r.read_u8()? as usize;
+        if float_size != 4 && float_size != 8 {
+            return Err(invalid_data("unsupported BVH float size"));
+        }
+        if r.read_u64::<LittleEndian>()? as usize != elements.len() {
+            return Err(invalid_data("BVH element count mismatch"));
+        }
+        let root = r.read_u64::<LittleEndian>()? as usize;
+        let nodes_in_use = r.read_u64::<LittleEndian>()? as usize;
+
+        let mut bvh = Self::new(elements);
+        if nodes_in_use > bvh.pool.vec.len() || root >= nodes_in_use {
+            return Err(invalid_data("BVH node count exceeds the node pool"));
+        }
+        bvh.root = root;
+        bvh.nodes_in_use = nodes_in_use;
+
+        let read_float = |r: &mut dyn Read| -> io::Result<T> {
+            if float_size == 4 {
+                Ok(nalgebra::convert(r.read_f32::<LittleEndian>()? as f64))
+            } else {
+                Ok(nalgebra::convert(r.read_f64::<LittleEndian>()?))
+            }
+        };
+        for node in bvh.pool.vec[..nodes_in_use].iter_mut() {
+            for i in 0..DIM {
+                node.aabb.min[i] = read_float(r)?;
+            }
+            for i in 0..DIM {
+                node.aabb.max[i] = read_float(r)?;
+            }
+            node.left_first = r.read_u64::<LittleEndian>()? as usize;
+            node.num_prims = r.read_u64::<LittleEndian>()? as usize;
+        }
+        bvh.validate().map_err(|msg| invalid_data(&format!("invalid BVH: {msg}")))?;
+        Ok(bvh)
+    }
+}
+
+/// Magic bytes at the start of a serialized BVH.
+const SERIAL_MAGIC: [u8; 4] = *b"CPBV";
+/// Version of the serialization format of the BVH.
+const SERIAL_VERSION: u16 = 1;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
 }
 
 impl<T, E, NodePool, ElementPool, const DIM: usize> BVH<T, E, NodePool, ElementPool, DIM>
@@ -200,15 +284,96 @@ where T: BaseFloat + From<u32>,
       NodePool: BVHPool<T, DIM>,
       ElementPool: BVHElementPool<T, E, DIM> {
 
-    /// Rebuilds the BVH-tree using the specified splitting function `SF`.
+    /// Serializes the node pool of the BVH-tree into the specified writer. The data is written in a
+    /// compact little-endian format, prefixed by a version header. Floating point values are
+    /// stored as `f32`, if `T` has the size of an `f32`, and as `f64` otherwise. The header records
+    /// the width that is used.
+    ///
+    /// The element pool is not serialized and has to be supplied separately when loading the tree
+    /// through `deserialize`.
+    pub fn serialize(&self, w: &mut impl Write) -> io::Result<()> {
+        let float_size = if mem::size_of::<T>() == 4 { 4 } else { 8 };
+        w.write_all(&SERIAL_MAGIC)?;
+        w.write_u16::<LittleEndian>(SERIAL_VERSION)?;
+        w.write_u8(DIM as u8)?;
+        w.write_u8(float_size as u8)?;
+        w.write_u64::<LittleEndian>(self.elements.len() as u64)?;
+        w.write_u64::<LittleEndian>(self.root as u64)?;
+        w.write_u64::<LittleEndian>(self.nodes_in_use as u64)?;
+
+        let write_float = |w: &mut dyn Write, val: T| -> io::Result<()> {
+            let val: f64 = nalgebra::convert_unchecked(val);
+            if float_size == 4 {
+                w.write_f32::<LittleEndian>(val as f32)
+            } else {
+                w.write_f64::<LittleEndian>(val)
+            }
+        };
+        for i in 0..self.nodes_in_use {
+            let node = &self.pool[i];
+            for j in 0..DIM {
+                write_float(w, node.aabb.min[j])?;
+            }
+            for j in 0..DIM {
+                write_float(w, node.aabb.max[j])?;
+            }
+            w.write_u64::<LittleEndian>(node.left_first as u64)?;
+            w.write_u64::<LittleEndian>(node.num_prims as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Enables or disables the recording of the splits that are found while building the tree.
+    /// Recording does not change the resulting tree. The recorded splits of the last build can be
+    /// inspected through `last_build_splits`.
+    pub fn record_splits(&mut self, enabled: bool) {
+        self.build_splits = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the splits that were found by the splitting function during the last build, together
+    /// with the index of the node they were found for, in the order in which the nodes were
+    /// subdivided. Splits that do not pay off for the SAH costs of the tree (see `set_sah_cost`)
+    /// were rejected, such that their node remained a leaf.
+    ///
+    /// The returned slice is empty if recording is disabled (see `record_splits`).
+    pub fn last_build_splits(&self) -> &[(usize, BVHSplit<T>)] {
+        self.build_splits.as_deref().unwrap_or_default()
+    }
+
+    /// Sets the relative costs of traversal steps and element tests, which decide whether
+    /// splitting a node pays off on the next build. The default (see `SAHCost::default`) results
+    /// in the same trees as before the costs were configurable.
+    pub fn set_sah_cost(&mut self, cost: SAHCost<T>) {
+        self.sah_cost = cost;
+    }
+
+    /// Returns the relative costs of traversal steps and element tests (see `set_sah_cost`).
+    pub fn sah_cost(&self) -> SAHCost<T> {
+        self.sah_cost
+    }
+
+    /// Rebuilds the BVH-tree using the specified splitting function `SF`. Nodes with at most
+    /// `DEFAULT_MAX_LEAF_PRIMS` elements are not subdivided.
     pub fn rebuild<SF: BVHSplitting<T, E, NodePool, ElementPool, DIM>>(&mut self) {
+        self.rebuild_with_leaf_size::<SF>(DEFAULT_MAX_LEAF_PRIMS);
+    }
+
+    /// Rebuilds the BVH-tree using the specified splitting function `SF`. Nodes with at most
+    /// `max_leaf_prims` elements become leaf nodes, regardless of the SAH. Larger leaves result in
+    /// a shallower tree, trading traversal steps for linear scans over the leaf elements.
+    pub fn rebuild_with_leaf_size<SF: BVHSplitting<T, E, NodePool, ElementPool, DIM>>(
+        &mut self, max_leaf_prims: usize
+    ) {
         self.nodes_in_use = 1;
+        if let Some(splits) = &mut self.build_splits {
+            splits.clear();
+        }
         let root = &mut self.pool[self.root];
         root.left_first = 0;
         root.num_prims = self.elements.len();
 
         self.update_bounds(self.root);
-        self.subdivide::<SF>(self.root);
+        self.subdivide_with_leaf_size::<SF>(self.root, max_leaf_prims);
     }
 
     /// Refits the BVH-tree to the current state of the tree nodes.
@@ -239,29 +404,45 @@ where T: BaseFloat + From<u32>,
         }
     }
 
-    /// Subdivides the node specified by `node_id` by using the specified splitting function.
+    /// Subdivides the node specified by `node_id` by using the specified splitting function. Nodes
+    /// with at most `DEFAULT_MAX_LEAF_PRIMS` elements are not subdivided.
     pub fn subdivide<SF: BVHSplitting<T, E, NodePool, ElementPool, DIM>>(
         &mut self, node_id: usize
+    ) {
+        self.subdivide_with_leaf_size::<SF>(node_id, DEFAULT_MAX_LEAF_PRIMS);
+    }
+
+    /// Subdivides the node specified by `node_id` by using the specified splitting function. Nodes
+    /// with at most `max_leaf_prims` elements are not subdivided.
+    pub fn subdivide_with_leaf_size<SF: BVHSplitting<T, E, NodePool, ElementPool, DIM>>(
+        &mut self, node_id: usize, max_leaf_prims: usize
     ) {
         let node = &self.pool[node_id];
+        if node.num_prims <= usize::max(max_leaf_prims, 1) {
+            return; // a single element cannot be split any further
+        }
 
         // split plane axis and position
         let split = SF::find(self, node);
-        if split.cost >= Self::calc_node_cost(node) {
+        if let Some(splits) = &mut self.build_splits {
+            splits.push((node_id, split));
+        }
+        let node = &self.pool[node_id];
+        if self.calc_split_cost(node, &split) >= self.calc_node_cost(node) {
             return; // not splitting is more cost-effective
         }
 
         // split the group in two halves
         let mut i = node.left_first;
-        let mut j = i + node.num_prims - 1;
-        while i <= j {
+        let mut j = i + node.num_prims;
+        while i < j {
             if self.elements[i].centroid()[split.axis] < split.pos {
                 // element is to the left of the split
                 i += 1;
             } else {
                 // element is to the right of the split
-                self.elements.swap(i, j);
                 j -= 1;
+                self.elements.swap(i, j);
             }
         }
 
@@ -295,8 +476,8 @@ where T: BaseFloat + From<u32>,
         self.update_bounds(left_child_idx);
         self.update_bounds(right_child_idx);
         // try to recursively subdivide the children
-        self.subdivide::<SF>(left_child_idx);
-        self.subdivide::<SF>(right_child_idx);
+        self.subdivide_with_leaf_size::<SF>(left_child_idx, max_leaf_prims);
+        self.subdivide_with_leaf_size::<SF>(right_child_idx, max_leaf_prims);
     }
 
     /// Returns the SAH evaluation for the specified `node` with the specified splitting `pos` along
@@ -327,9 +508,14 @@ where T: BaseFloat + From<u32>,
         }
     }
 
-    /// Returns a cost approximation for searching the specified node.
-    fn calc_node_cost(node: &BVHNode<T, DIM>) -> T {
-        T::from(node.num_prims as u32) * node.aabb.area()
+    /// Returns a cost approximation for searching the specified node as a leaf.
+    fn calc_node_cost(&self, node: &BVHNode<T, DIM>) -> T {
+        self.sah_cost.intersection * T::from(node.num_prims as u32) * node.aabb.area()
+    }
+
+    /// Returns a cost approximation for searching the specified node, if it is split at `split`.
+    fn calc_split_cost(&self, node: &BVHNode<T, DIM>, split: &BVHSplit<T>) -> T {
+        self.sah_cost.traversal * node.aabb.area() + self.sah_cost.intersection * split.cost
     }
 
     /// Returns a `Vec` to references of the member elements of this tree that intersect the
@@ -340,6 +526,21 @@ where T: BaseFloat + From<u32>,
         &self, intersector: &I, node_idx: usize) -> Vec<&E> {
 
         let mut v = Vec::<&E>::with_capacity(64);
+        self.intersect_into(intersector, node_idx, &mut v);
+        v
+    }
+
+    /// Pushes references to the member elements of this tree that intersect the specified
+    /// intersector into `out`, like `intersect`. Elements that are already in `out` are kept.
+    ///
+    /// Unlike `intersect`, this method does not allocate when `out` has enough capacity, such that
+    /// the buffer can be reused for many queries (see `QueryBuffer`).
+    pub fn intersect_into<'a, I: BVIntersector<T, E, DIM> + BVIntersector<T, AABB<T, DIM>, DIM>>(
+        &'a self, intersector: &I, node_idx: usize, out: &mut Vec<&'a E>) {
+
+        if self.elements.len() == 0 {
+            return;
+        }
 
         let mut node = &self.pool[node_idx];
         let mut stack = [node; 64];
@@ -349,7 +550,7 @@ where T: BaseFloat + From<u32>,
             if node.is_leaf() {
                 for i in 0..node.num_prims {
                     if intersector.intersects(&self.elements[node.left_first + i]) {
-                        v.push(&self.elements[node.left_first + i]);
+                        out.push(&self.elements[node.left_first + i]);
                     }
                 }
 
@@ -390,7 +591,341 @@ where T: BaseFloat + From<u32>,
                 }
             }
         }
-        v
+    }
+
+    /// Returns the bounds of all nodes in the tree as pairs of min and max points, in depth-first
+    /// order starting at the root. This is meant for debugging, e.g. to draw the nodes of the tree
+    /// as wireframe boxes.
+    pub fn debug_boxes(&self) -> Vec<(SVector<T, DIM>, SVector<T, DIM>)> {
+        self.debug_boxes_with_depth().into_iter()
+            .map(|(min, max, _)| (min, max))
+            .collect()
+    }
+
+    /// Returns the bounds of all nodes in the tree, like `debug_boxes`, together with the depth of
+    /// each node within the tree. The root node has a depth of zero.
+    pub fn debug_boxes_with_depth(&self) -> Vec<(SVector<T, DIM>, SVector<T, DIM>, usize)> {
+        let mut boxes = Vec::with_capacity(self.nodes_in_use);
+        if self.elements.len() == 0 {
+            return boxes;
+        }
+
+        let mut stack = vec![(self.root, 0usize)];
+        while let Some((node_idx, depth)) = stack.pop() {
+            let node = &self.pool[node_idx];
+            boxes.push((node.aabb.min, node.aabb.max, depth));
+            if !node.is_leaf() {
+                stack.push((node.right_child(), depth + 1));
+                stack.push((node.left_child(), depth + 1));
+            }
+        }
+        boxes
+    }
+
+    /// Checks the invariants of the tree and returns a description of the first violation that is
+    /// found. This is meant for debugging corrupted trees, which otherwise only show up as wrong
+    /// query results or panics deep within a traversal.
+    ///
+    /// The checks are that all nodes reachable from the root are in use, that every interior node
+    /// references children that were created after it, that the bounds of every node contain the
+    /// bounds of its children or elements, and that the leaves reference each element exactly once.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.elements.len() == 0 {
+            return Ok(());
+        }
+        if self.root >= self.nodes_in_use {
+            return Err(format!("root {} is not in use ({} nodes in use)", self.root, self.nodes_in_use));
+        }
+
+        let mut leaf_prims = 0usize;
+        let mut stack = vec![self.root];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.pool[node_idx];
+            if node.is_leaf() {
+                let end = node.left_first + node.num_prims;
+                if end > self.elements.len() {
+                    return Err(format!(
+                        "leaf {node_idx} references elements {}..{end}, but there are only {} elements",
+                        node.left_first, self.elements.len()
+                    ));
+                }
+                if let Some(i) = (node.left_first..end).find(|i| !node.aabb.contains(&self.elements[*i].wrap())) {
+                    return Err(format!("leaf {node_idx} does not contain its element {i}"));
+                }
+                leaf_prims += node.num_prims;
+                continue;
+            }
+
+            for child in [node.left_child(), node.right_child()] {
+                if child <= node_idx || child >= self.nodes_in_use {
+                    return Err(format!(
+                        "node {node_idx} references child {child}, which is out of range ({} nodes in use)",
+                        self.nodes_in_use
+                    ));
+                }
+                if !node.aabb.contains(&self.pool[child].aabb) {
+                    return Err(format!("node {node_idx} does not contain its child {child}"));
+                }
+                stack.push(child);
+            }
+        }
+
+        if leaf_prims != self.elements.len() {
+            return Err(format!(
+                "the leaves reference {leaf_prims} elements, but the tree contains {}",
+                self.elements.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the member elements of this tree that intersect the specified
+    /// intersector. Unlike `intersect`, the tree is traversed lazily while iterating, which avoids
+    /// the allocation of the result and allows to stop the query early.
+    pub fn iter_intersect<'a, I>(&'a self, intersector: &'a I, node_idx: usize) -> IntersectIter<'a, I, E>
+    where I: BVIntersector<T, E, DIM> + BVIntersector<T, AABB<T, DIM>, DIM> {
+        let node = if self.elements.len() == 0 { None } else { Some(node_idx) };
+        IntersectIter::new(self, intersector, node)
+    }
+
+    /// Returns the member element of this tree that is closest to the specified point, like
+    /// `nearest`, but measures the distance of the elements with the function `distance`. This
+    /// allows elements that cannot compute their distance on their own, like elements that only
+    /// reference external geometry.
+    ///
+    /// The distance of an element must not be smaller than the distance from the point to the
+    /// bounds of the element (see `BVHElement::wrap`), since the bounds are used to skip nodes.
+    pub fn nearest_by<F>(&self, point: &SVector<T, DIM>, distance: F) -> Option<(&E, T)>
+    where F: Fn(&E) -> T {
+        if self.elements.len() == 0 {
+            return None;
+        }
+
+        let mut best: Option<(usize, T)> = None;
+        let mut queue = BinaryHeap::new();
+        queue.push(NodeDistance { dist: self.pool[self.root].aabb.distance_to(point), index: self.root });
+
+        while let Some(NodeDistance { dist, index: node }) = queue.pop() {
+            if best.is_some_and(|(_, best_dist)| dist >= best_dist) {
+                // all remaining nodes are at least as far away as the best element
+                break;
+            }
+
+            let node = &self.pool[node];
+            if node.is_leaf() {
+                for i in node.left_first..node.left_first + node.num_prims {
+                    let dist = distance(&self.elements[i]);
+                    if best.is_none_or(|(_, best_dist)| dist < best_dist) {
+                        best = Some((i, dist));
+                    }
+                }
+            } else {
+                for child in [node.left_child(), node.right_child()] {
+                    let dist = self.pool[child].aabb.distance_to(point);
+                    if best.is_none_or(|(_, best_dist)| dist < best_dist) {
+                        queue.push(NodeDistance { dist, index: child });
+                    }
+                }
+            }
+        }
+        best.map(|(i, dist)| (&self.elements[i], dist))
+    }
+}
+
+impl<T, E, NodePool, ElementPool, const DIM: usize> BVH<T, E, NodePool, ElementPool, DIM>
+where T: BaseFloat + From<u32>,
+      E: BVHElement<T, DIM> + DistanceTo<T, DIM>,
+      NodePool: BVHPool<T, DIM>,
+      ElementPool: BVHElementPool<T, E, DIM> {
+
+    /// Returns the member element of this tree that is closest to the specified point, together
+    /// with its distance to the point. If the tree does not contain any elements, `None` is
+    /// returned. Like `intersect`, the point has to be in the reference frame of the BVH.
+    ///
+    /// The tree is traversed best-first: nodes are visited in the order of the distance from the
+    /// point to their bounds, and the search stops as soon as the closest remaining node is not
+    /// closer than the best element found so far.
+    pub fn nearest(&self, point: &SVector<T, DIM>) -> Option<(&E, T)> {
+        self.nearest_by(point, |element| element.distance_to(point))
+    }
+
+    /// Returns the `k` member elements of this tree that are closest to the specified point,
+    /// together with their distances to the point, sorted nearest-first. If the tree contains less
+    /// than `k` elements, all elements are returned. Like `intersect`, the point has to be in the
+    /// reference frame of the BVH.
+    ///
+    /// The tree is traversed best-first, like in `nearest`. The `k` closest elements found so far
+    /// are kept in a bounded max-heap, such that nodes that are not closer than the worst of them
+    /// can be skipped once `k` elements are found.
+    pub fn k_nearest(&self, point: &SVector<T, DIM>, k: usize) -> Vec<(&E, T)> {
+        if k == 0 || self.elements.len() == 0 {
+            return Vec::new();
+        }
+
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        let mut queue = BinaryHeap::new();
+        queue.push(NodeDistance { dist: self.pool[self.root].aabb.distance_to(point), index: self.root });
+
+        while let Some(NodeDistance { dist, index: node }) = queue.pop() {
+            let worst = Self::worst_distance(&best, k);
+            if worst.is_some_and(|worst| dist >= worst) {
+                // all remaining nodes are at least as far away as the k best elements
+                break;
+            }
+
+            let node = &self.pool[node];
+            if node.is_leaf() {
+                for i in node.left_first..node.left_first + node.num_prims {
+                    let dist = self.elements[i].distance_to(point);
+                    if Self::worst_distance(&best, k).is_none_or(|worst| dist < worst) {
+                        best.push(Reverse(NodeDistance { dist, index: i }));
+                        if best.len() > k {
+                            best.pop();
+                        }
+                    }
+                }
+            } else {
+                for child in [node.left_child(), node.right_child()] {
+                    let dist = self.pool[child].aabb.distance_to(point);
+                    if worst.is_none_or(|worst| dist < worst) {
+                        queue.push(NodeDistance { dist, index: child });
+                    }
+                }
+            }
+        }
+        best.into_sorted_vec().into_iter()
+            .map(|Reverse(NodeDistance { dist, index })| (&self.elements[index], dist))
+            .collect()
+    }
+
+    /// Moves a sphere with the specified `radius` from `origin` along `dir` and returns the first
+    /// member element of this tree that the sphere touches, together with the distance the sphere
+    /// travelled until the contact. Only contacts within `max_dist` are reported. Like for the
+    /// TLAS (see `TLAS::spherecast`), the direction does not have to be normalized and the sphere
+    /// has to be in the reference frame of the BVH.
+    ///
+    /// A sphere touches the bounds of a node, if its center passes through the bounds expanded by
+    /// the radius. Nodes are therefore pruned by intersecting the ray of the center with the
+    /// expanded bounds, and visited in the order of the distance at which the ray enters them. The
+    /// exact contact with the elements is found through `DistanceTo::sphere_cast`.
+    pub fn spherecast(
+        &self,
+        origin: &SVector<T, DIM>,
+        dir: &SVector<T, DIM>,
+        radius: T,
+        max_dist: T
+    ) -> Option<(&E, T)> {
+        if self.elements.len() == 0 || dir.norm() == T::zero() {
+            return None;
+        }
+        let ray = Ray::new(*origin, *dir, max_dist);
+        let margin = SVector::repeat(radius);
+        let entry = |node: usize| ray.entry(&self.pool[node].aabb.expanded(&margin));
+
+        let mut best: Option<(usize, T)> = None;
+        let mut queue = BinaryHeap::new();
+        if let Some(dist) = entry(self.root) {
+            queue.push(NodeDistance { dist, index: self.root });
+        }
+
+        while let Some(NodeDistance { dist, index: node }) = queue.pop() {
+            if best.is_some_and(|(_, best_dist)| dist >= best_dist) {
+                // all remaining nodes are entered after the first contact
+                break;
+            }
+
+            let node = &self.pool[node];
+            if node.is_leaf() {
+                for i in node.left_first..node.left_first + node.num_prims {
+                    let limit = best.map_or(max_dist, |(_, best_dist)| best_dist);
+                    let hit = self.elements[i].sphere_cast(origin, &ray.dir, radius, limit);
+                    if let Some(dist) = hit.filter(|dist| best.is_none_or(|(_, best_dist)| *dist < best_dist)) {
+                        best = Some((i, dist));
+                    }
+                }
+            } else {
+                for child in [node.left_child(), node.right_child()] {
+                    if let Some(dist) = entry(child) {
+                        queue.push(NodeDistance { dist, index: child });
+                    }
+                }
+            }
+        }
+        best.map(|(i, dist)| (&self.elements[i], dist))
+    }
+
+    /// Returns the distance of the farthest element in the heap of `k_nearest`, once the heap is
+    /// full. Until then, any element may still be part of the result and `None` is returned.
+    fn worst_distance(best: &BinaryHeap<Reverse<NodeDistance<T>>>, k: usize) -> Option<T> {
+        if best.len() < k {
+            None
+        } else {
+            best.peek().map(|Reverse(entry)| entry.dist)
+        }
+    }
+}
+
+/// Entry of the priority queues in `BVH::nearest`, `BVH::k_nearest` and `BVH::spherecast`, which
+/// is the index of a node or an element with its distance to the query point. The entries are
+/// ordered reversely by their distance, such that a `BinaryHeap` pops the closest entry first.
+/// Wrapped in `Reverse`, the farthest entry is popped first instead.
+struct NodeDistance<T> {
+    dist: T,
+    index: usize,
+}
+
+impl<T: PartialOrd> PartialEq for NodeDistance<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for NodeDistance<T> {}
+
+impl<T: PartialOrd> PartialOrd for NodeDistance<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> Ord for NodeDistance<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.dist.partial_cmp(&self.dist).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<T, E, NodePool, ElementPool, I, const DIM: usize> IntersectTraversal<I, E> for BVH<T, E, NodePool, ElementPool, DIM>
+where T: BaseFloat + From<u32>,
+      E: BVHElement<T, DIM>,
+      NodePool: BVHPool<T, DIM>,
+      ElementPool: BVHElementPool<T, E, DIM>,
+      I: BVIntersector<T, E, DIM> + BVIntersector<T, AABB<T, DIM>, DIM> {
+
+    fn node_intersects(&self, intersector: &I, node: usize) -> bool {
+        intersector.intersects(&self.pool[node].aabb)
+    }
+
+    fn children(&self, node: usize) -> Option<(usize, usize)> {
+        let node = &self.pool[node];
+        if node.is_leaf() {
+            None
+        } else {
+            Some((node.left_child(), node.right_child()))
+        }
+    }
+
+    fn leaf_elements(&self, node: usize) -> Range<usize> {
+        let node = &self.pool[node];
+        node.left_first..node.left_first + node.num_prims
+    }
+
+    fn intersecting_element(&self, intersector: &I, element: usize) -> Option<&E> {
+        let element = &self.elements[element];
+        if intersector.intersects(element) {
+            Some(element)
+        } else {
+            None
+        }
     }
 }
 
@@ -399,10 +934,12 @@ where T: BaseFloat + From<u32>,
 
 #[cfg(test)]
 mod test {
+    use std::io;
     use nalgebra::SVector;
     use crate::volume::aabb::AABB;
-    use crate::volume::{BoundingVolume, bvh_splitting};
-    use crate::volume::bvh::{BVH, BVHElement, BVHNode, VecPool};
+    use crate::volume::{BoundingVolume, BVIntersector, bvh_splitting, DistanceTo};
+    use crate::volume::bvh::{BVH, BVHElement, BVHElementPool, BVHNode, VecPool};
+    use crate::volume::bvh_splitting::SAHCost;
 
     struct Test<const DIM: usize> {
         bounds: AABB<f64, DIM>
@@ -444,6 +981,32 @@ mod test {
         }
     }
 
+    impl<const DIM: usize> BVIntersector<f64, Test<DIM>, DIM> for AABB<f64, DIM> {
+        fn intersects(&self, other: &Test<DIM>) -> bool {
+            self.intersects(&other.bounds)
+        }
+    }
+
+    impl<const DIM: usize> DistanceTo<f64, DIM> for Test<DIM> {}
+
+    type TestBVH<const DIM: usize> = BVH::<f64, Test<DIM>, VecPool<BVHNode<f64, DIM>>, VecPool<Test<DIM>>, DIM>;
+
+    /// Builds a grid of `n * n * n` unit boxes with a spacing of `1.5`.
+    fn grid(n: usize) -> VecPool<Test<3>> {
+        let mut elements = VecPool::with_capacity(n * n * n);
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    let min = SVector::<f64, 3>::new(x as f64, y as f64, z as f64) * 1.5;
+                    elements.push(Test {
+                        bounds: AABB { min, max: min + SVector::repeat(1.0) }
+                    });
+                }
+            }
+        }
+        elements
+    }
+
     #[test]
     fn test() {
         let mut elements = VecPool::<Test<2>>::with_capacity(10);
@@ -451,5 +1014,357 @@ mod test {
         let mut bvh = BVH::<f64, Test<2>, VecPool<BVHNode<f64, 2>>, VecPool<Test<2>>, 2>::new(elements);
         bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
     }
-}
 
+    #[test]
+    fn serialize_round_trip() {
+        let mut bvh = TestBVH::new(grid(5));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        let mut data = Vec::new();
+        bvh.serialize(&mut data).unwrap();
+
+        let elements = || VecPool {
+            vec: bvh.elements.vec.iter().map(|e| Test { bounds: e.bounds }).collect()
+        };
+        let loaded = TestBVH::deserialize(&mut data.as_slice(), elements()).unwrap();
+
+        let query = AABB {
+            min: SVector::<f64, 3>::new(1.2, 0.5, 2.0),
+            max: SVector::<f64, 3>::new(4.0, 3.1, 4.6),
+        };
+        let expected: Vec<_> = bvh.intersect(&query, 0).iter().map(|e| e.bounds.min).collect();
+        let actual: Vec<_> = loaded.intersect(&query, 0).iter().map(|e| e.bounds.min).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, actual);
+
+        // mismatching element pools are rejected
+        assert!(TestBVH::deserialize(&mut data.as_slice(), grid(2)).is_err());
+
+        // the header records the width of the floats that are written
+        let node_size = 3 * 2 * 8 + 2 * 8;
+        assert_eq!(data[7], 8);
+        assert_eq!(data.len(), 32 + bvh.nodes_in_use * node_size);
+
+        // corrupted indices are rejected instead of being followed by later queries
+        let left_first = 32 + bvh.root * node_size + 3 * 2 * 8;
+        let mut corrupted = data.clone();
+        corrupted[left_first..left_first + 8].copy_from_slice(&1_000_000u64.to_le_bytes());
+        let error = TestBVH::deserialize(&mut corrupted.as_slice(), elements()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn intersect_into() {
+        let mut bvh = TestBVH::new(grid(4));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        let query = AABB { min: SVector::repeat(1.25), max: SVector::repeat(3.25) };
+
+        let mut out = Vec::new();
+        bvh.intersect_into(&query, 0, &mut out);
+        assert_eq!(out.len(), bvh.intersect(&query, 0).len());
+        assert_eq!(out.len(), 8);
+        // results are appended to the buffer
+        bvh.intersect_into(&query, 0, &mut out);
+        assert_eq!(out.len(), 16);
+
+        let empty = TestBVH::<3>::new(VecPool::with_capacity(0));
+        let mut out = Vec::new();
+        empty.intersect_into(&query, 0, &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn iter_intersect() {
+        let mut bvh = TestBVH::new(grid(6));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        let queries = [
+            AABB { min: SVector::<f64, 3>::new(1.2, 0.5, 2.0), max: SVector::<f64, 3>::new(4.0, 3.1, 4.6) },
+            AABB { min: SVector::repeat(-1.0), max: SVector::repeat(100.0) },
+            AABB { min: SVector::repeat(20.0), max: SVector::repeat(21.0) },
+        ];
+        for query in queries {
+            let expected: Vec<_> = bvh.intersect(&query, 0).iter().map(|e| e.bounds.min).collect();
+            let actual: Vec<_> = bvh.iter_intersect(&query, 0).map(|e| e.bounds.min).collect();
+            assert_eq!(expected, actual);
+        }
+
+        // the query may be stopped early
+        let all = AABB { min: SVector::repeat(-1.0), max: SVector::repeat(100.0) };
+        assert_eq!(bvh.iter_intersect(&all, 0).take(3).count(), 3);
+
+        // an empty tree does not yield any elements
+        let empty = TestBVH::new(VecPool::with_capacity(0));
+        assert_eq!(empty.iter_intersect(&all, 0).count(), 0);
+    }
+
+    /// Returns the element ranges of all leaf nodes that can be reached from the node `node_idx`.
+    fn leaf_ranges(bvh: &TestBVH<3>, node_idx: usize, ranges: &mut Vec<(usize, usize)>) {
+        let node = &bvh.pool[node_idx];
+        if node.is_leaf() {
+            ranges.push((node.left_first, node.left_first + node.num_prims));
+        } else {
+            leaf_ranges(bvh, node.left_child(), ranges);
+            leaf_ranges(bvh, node.right_child(), ranges);
+        }
+    }
+
+    #[test]
+    fn nan_centroids() {
+        let mut elements = grid(4);
+        for i in (0..elements.len()).step_by(5) {
+            elements.vec[i].bounds.min[i % 3] = f64::NAN;
+            elements.vec[i].bounds.max[i % 3] = f64::NAN;
+        }
+        let mut bvh = TestBVH::new(elements);
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        // every element is referenced by exactly one leaf
+        let mut ranges = Vec::new();
+        leaf_ranges(&bvh, 0, &mut ranges);
+        ranges.sort();
+        assert_eq!(ranges.first().unwrap().0, 0);
+        assert_eq!(ranges.last().unwrap().1, bvh.elements.len());
+        assert!(ranges.windows(2).all(|w| w[0].1 == w[1].0));
+
+        // all finite elements can still be found
+        for i in 0..bvh.elements.len() {
+            let bounds = bvh.elements[i].bounds;
+            if bounds.min.iter().all(|x| x.is_finite()) {
+                let hits = bvh.intersect(&bounds, 0);
+                assert!(hits.iter().any(|e| e.bounds.min == bounds.min));
+            }
+        }
+    }
+
+    #[test]
+    fn debug_boxes() {
+        let mut bvh = TestBVH::new(grid(3));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        let boxes = bvh.debug_boxes_with_depth();
+        assert_eq!(boxes.len(), bvh.nodes_in_use);
+        // the root wraps the whole grid
+        assert_eq!(boxes[0], (SVector::zeros(), SVector::repeat(4.0), 0));
+        assert!(boxes[1..].iter().all(|(_, _, depth)| *depth > 0));
+        assert_eq!(bvh.debug_boxes()[0], (SVector::zeros(), SVector::repeat(4.0)));
+
+        let empty = TestBVH::<3>::new(VecPool::with_capacity(0));
+        assert!(empty.debug_boxes().is_empty());
+    }
+
+    #[test]
+    fn leaf_size() {
+        let mut nodes_in_use = Vec::new();
+        for max_leaf_prims in [1, 4, 16] {
+            let mut bvh = TestBVH::new(grid(5));
+            bvh.rebuild_with_leaf_size::<bvh_splitting::BinnedSAHSplit<8>>(max_leaf_prims);
+
+            // inner nodes always contain more elements than the leaf size
+            for node_idx in 0..bvh.nodes_in_use {
+                if !bvh.pool[node_idx].is_leaf() {
+                    let mut ranges = Vec::new();
+                    leaf_ranges(&bvh, node_idx, &mut ranges);
+                    let count: usize = ranges.iter().map(|(first, last)| last - first).sum();
+                    assert!(count > max_leaf_prims);
+                }
+            }
+            nodes_in_use.push(bvh.nodes_in_use);
+        }
+        // larger leaves result in smaller trees
+        assert!(nodes_in_use[0] > nodes_in_use[1] && nodes_in_use[1] > nodes_in_use[2]);
+    }
+
+    #[test]
+    fn sah_cost() {
+        let mut bvh = TestBVH::new(grid(4));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        let default = (bvh.debug_boxes(), bvh.nodes_in_use);
+
+        // only the ratio of both costs matters
+        bvh.set_sah_cost(SAHCost::new(0.0, 2.0));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert_eq!((bvh.debug_boxes(), bvh.nodes_in_use), default);
+
+        // expensive traversal steps result in fewer, larger leaves
+        bvh.set_sah_cost(SAHCost::new(4.0, 1.0));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert!(bvh.nodes_in_use < default.1);
+        assert!(bvh.nodes_in_use > 1);
+        let query = AABB { min: SVector::repeat(1.25), max: SVector::repeat(3.25) };
+        assert_eq!(bvh.intersect(&query, 0).len(), 8);
+
+        // if a traversal step costs more than testing all elements, the root remains a leaf
+        bvh.set_sah_cost(SAHCost::new(100.0, 1.0));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert_eq!(bvh.nodes_in_use, 1);
+        assert_eq!(bvh.sah_cost(), SAHCost::new(100.0, 1.0));
+    }
+
+    #[test]
+    fn build_splits() {
+        let mut bvh = TestBVH::new(grid(4));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert!(bvh.last_build_splits().is_empty());
+        let unrecorded = bvh.debug_boxes();
+
+        bvh.record_splits(true);
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        // recording does not change the tree
+        assert_eq!(bvh.debug_boxes(), unrecorded);
+
+        // every inner node was split at its recorded split
+        let splits = bvh.last_build_splits().to_vec();
+        for node_idx in 0..bvh.nodes_in_use {
+            let node = &bvh.pool[node_idx];
+            if node.is_leaf() {
+                continue;
+            }
+            let (_, split) = splits.iter().find(|(idx, _)| *idx == node_idx).unwrap();
+            assert!(split.axis < 3);
+            assert!(bvh.pool[node.left_child()].aabb.max[split.axis] <= split.pos + 0.5);
+            assert!(bvh.pool[node.right_child()].aabb.min[split.axis] >= split.pos - 0.5);
+        }
+
+        // the records are replaced on every build
+        bvh.rebuild::<bvh_splitting::FullSAHSplit>();
+        assert!(!bvh.last_build_splits().is_empty());
+        assert_eq!(bvh.last_build_splits()[0].0, 0);
+
+        bvh.record_splits(false);
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert!(bvh.last_build_splits().is_empty());
+    }
+
+    #[test]
+    fn nearest() {
+        let mut bvh = TestBVH::new(grid(6));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        // points in the gaps of the grid and around it, compared against a brute force search
+        let points = [
+            SVector::<f64, 3>::new(0.5, 0.5, 0.5),
+            SVector::<f64, 3>::new(1.2, 4.4, 2.6),
+            SVector::<f64, 3>::new(-3.0, 2.0, 20.0),
+            SVector::<f64, 3>::new(3.9, 3.9, 3.9),
+            SVector::<f64, 3>::new(8.25, -1.0, 5.0),
+        ];
+        for p in points {
+            let (element, dist) = bvh.nearest(&p).unwrap();
+            let expected = (0..bvh.elements.len())
+                .map(|i| bvh.elements[i].distance_to(&p))
+                .fold(f64::MAX, f64::min);
+            assert_eq!(dist, expected);
+            assert_eq!(element.distance_to(&p), dist);
+        }
+
+        // inside of the box at the grid cell (2, 3, 1)
+        let (element, dist) = bvh.nearest(&SVector::<f64, 3>::new(3.25, 4.75, 1.75)).unwrap();
+        assert_eq!(dist, 0.0);
+        assert_eq!(element.bounds.min, SVector::<f64, 3>::new(3.0, 4.5, 1.5));
+
+        let empty = TestBVH::<3>::new(VecPool::with_capacity(0));
+        assert!(empty.nearest(&SVector::zeros()).is_none());
+    }
+
+    #[test]
+    fn spherecast() {
+        // a wall with a gap of 1.2 around the x-axis and a backdrop behind it, surrounded by boxes
+        // off the path of the spheres
+        let bounds = [
+            ([5.0, -5.0, -5.0], [6.0, -0.6, 5.0]),
+            ([5.0, 0.6, -5.0], [6.0, 5.0, 5.0]),
+            ([10.0, -5.0, -5.0], [11.0, 5.0, 5.0]),
+            ([-4.0, -1.0, -1.0], [-3.0, 1.0, 1.0]),
+            ([2.0, 6.0, -1.0], [3.0, 7.0, 1.0]),
+            ([2.0, -1.0, 6.0], [3.0, 1.0, 7.0]),
+            ([7.0, -1.0, -8.0], [8.0, 1.0, -7.0]),
+        ];
+        let mut elements = VecPool::with_capacity(bounds.len());
+        for (min, max) in bounds {
+            elements.push(Test { bounds: AABB { min: SVector::from(min), max: SVector::from(max) } });
+        }
+        let mut bvh = TestBVH::new(elements);
+        bvh.rebuild_with_leaf_size::<bvh_splitting::BinnedSAHSplit<8>>(1);
+
+        // the sphere stops in front of the wall
+        let (element, t) = bvh.spherecast(&SVector::<f64, 3>::new(0.0, 3.0, 0.0), &SVector::x(), 0.5, 20.0).unwrap();
+        assert_eq!(element.bounds.min, SVector::<f64, 3>::new(5.0, 0.6, -5.0));
+        assert!((t - 4.5).abs() < 1e-6);
+
+        // the gap is wider than the sphere, which passes through it, unlike a larger sphere
+        let (element, t) = bvh.spherecast(&SVector::zeros(), &SVector::<f64, 3>::new(2.0, 0.0, 0.0), 0.5, 20.0).unwrap();
+        assert_eq!(element.bounds.min.x, 10.0);
+        assert!((t - 9.5).abs() < 1e-6);
+        assert!(bvh.spherecast(&SVector::zeros(), &SVector::x(), 0.5, 9.0).is_none());
+        let (element, t) = bvh.spherecast(&SVector::zeros(), &SVector::x(), 0.7, 20.0).unwrap();
+        assert_eq!(element.bounds.min.x, 5.0);
+        assert!((t - (5.0 - 0.13f64.sqrt())).abs() < 1e-6);
+
+        // spheres that start in contact hit at zero
+        let (_, t) = bvh.spherecast(&SVector::<f64, 3>::new(4.75, 3.0, 0.0), &SVector::x(), 0.5, 20.0).unwrap();
+        assert_eq!(t, 0.0);
+        assert!(bvh.spherecast(&SVector::zeros(), &SVector::zeros(), 0.5, 20.0).is_none());
+    }
+
+    #[test]
+    fn k_nearest() {
+        let mut bvh = TestBVH::new(grid(5));
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+
+        let points = [
+            SVector::<f64, 3>::new(0.3, 0.6, 0.2),
+            SVector::<f64, 3>::new(3.7, 2.2, 4.9),
+            SVector::<f64, 3>::new(-2.0, 10.0, 3.1),
+        ];
+        for p in points {
+            // brute force reference, sorted nearest-first
+            let mut expected: Vec<_> = (0..bvh.elements.len())
+                .map(|i| bvh.elements[i].distance_to(&p))
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            for k in [1, 4, 17] {
+                let result = bvh.k_nearest(&p, k);
+                assert_eq!(result.len(), k);
+                let dists: Vec<_> = result.iter().map(|(_, d)| *d).collect();
+                assert_eq!(dists, expected[..k]);
+                assert!(result.iter().all(|(e, d)| e.distance_to(&p) == *d));
+            }
+            assert_eq!(bvh.k_nearest(&p, 1)[0].1, bvh.nearest(&p).unwrap().1);
+        }
+
+        // asking for more elements than there are returns all of them
+        let all = bvh.k_nearest(&points[1], 1000);
+        assert_eq!(all.len(), 125);
+        assert!(all.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(bvh.k_nearest(&points[1], 0).is_empty());
+
+        let empty = TestBVH::<3>::new(VecPool::with_capacity(0));
+        assert!(empty.k_nearest(&SVector::zeros(), 3).is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        let mut bvh = TestBVH::new(grid(5));
+        assert!(bvh.validate().is_err()); // not built yet
+        bvh.rebuild::<bvh_splitting::BinnedSAHSplit<8>>();
+        assert_eq!(bvh.validate(), Ok(()));
+        assert_eq!(TestBVH::<3>::new(VecPool::with_capacity(0)).validate(), Ok(()));
+
+        // moved elements need a refit
+        bvh.elements[7].bounds.max.x += 20.0;
+        assert!(bvh.validate().is_err());
+        bvh.refit();
+        assert_eq!(bvh.validate(), Ok(()));
+
+        // stale node counts and bounds are reported
+        let nodes_in_use = bvh.nodes_in_use;
+        bvh.nodes_in_use = 3;
+        assert!(bvh.validate().is_err());
+        bvh.nodes_in_use = nodes_in_use;
+        let child = bvh.pool[bvh.root].left_child();
+        bvh.pool[child].aabb.min.y -= 1.0;
+        assert!(bvh.validate().is_err());
+    }
+}
@@ -3,12 +3,41 @@ use crate::volume::aabb::AABB;
 use crate::volume::BoundingVolume;
 use crate::volume::bvh::{BVH, BVHElement, BVHElementPool, BVHNode, BVHPool};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BVHSplit<T> {
     pub cost: T,
     pub pos: T,
     pub axis: usize,
 }
 
+/// Relative costs of the steps of a traversal, which decide whether splitting a node pays off.
+///
+/// Searching a leaf costs `intersection` per element, weighted by the area of the leaf. Splitting
+/// the node adds a traversal step, which costs `traversal` weighted by the area of the node, and
+/// replaces the leaf by the cost of the split that was found by the splitting function (see
+/// `BVHSplit::cost`). Expensive elements, like meshes in a narrow phase, favour more and smaller
+/// leaves, while expensive traversal steps favour fewer and larger leaves.
+///
+/// Only the ratio of both costs matters. The default treats traversal steps as free and only
+/// counts element tests, which splits every node for which the split reduces the element tests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SAHCost<T> {
+    pub traversal: T,
+    pub intersection: T,
+}
+
+impl<T: BaseFloat> SAHCost<T> {
+    pub fn new(traversal: T, intersection: T) -> Self {
+        SAHCost { traversal, intersection }
+    }
+}
+
+impl<T: BaseFloat> Default for SAHCost<T> {
+    fn default() -> Self {
+        SAHCost::new(T::zero(), T::one())
+    }
+}
+
 pub trait BVHSplitting<T, E, NPool, EPool, const DIM: usize>
 where E: BVHElement<T, DIM>,
       NPool: BVHPool<T, DIM>,
@@ -202,8 +231,10 @@ where E: BVHElement<T, DIM>,
         let mut rightbox = AABB::<T, DIM>::new();
 
         for axis in 0..DIM {
+            // NaN centroids are ignored by the bounds. Skip the axis, if the bounds are empty,
+            // degenerate or not finite, since the bins cannot be scaled to the bounds then.
             let (bounds_min, bounds_max) = axis_min_max!(T, bvh, node, axis);
-            if bounds_min == bounds_max {
+            if bounds_min >= bounds_max || !T::is_finite(&(bounds_max - bounds_min)) {
                 continue;
             }
 
@@ -214,9 +245,16 @@ where E: BVHElement<T, DIM>,
             let mut scale = T::from(NUM_BINS as u32) / (bounds_max - bounds_min);
             for i in 0..*node.num_prims() {
                 let element = &bvh.elements[node.left_child() + i];
-                let bin_idx = usize::min(
-                    NUM_BINS - 1,
-                    T::floor_to_u32((element.centroid()[axis] - bounds_min) * scale) as usize);
+                let offset = (element.centroid()[axis] - bounds_min) * scale;
+                let bin_idx = if offset >= T::zero() {
+                    usize::min(NUM_BINS - 1, T::floor_to_u32(offset) as usize)
+                } else if offset < T::zero() {
+                    0
+                } else {
+                    // NaN centroids are never to the left of a split plane when the elements are
+                    // partitioned, so they are counted to the right-most bin
+                    NUM_BINS - 1
+                };
                 bins[bin_idx].prime_count += 1;
                 bins[bin_idx].aabb.grow_other(&element.wrap());
             }
@@ -0,0 +1,231 @@
+use nalgebra::Vector3;
+use crate::helper::BaseFloat;
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+use crate::volume::aabb::AABB;
+use crate::volume::oriented::OBB;
+use crate::volume::sphere::Sphere;
+
+/// Number of golden section steps used to find the point of the segment of a capsule that lies
+/// closest to a box. Each step shrinks the search interval to 62% of its length.
+const SEGMENT_SEARCH_ITERATIONS: usize = 48;
+
+/// Capsule volume, which consists of all points within `radius` of the line segment from `a` to
+/// `b`.
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule<T> {
+    pub a: Vector3<T>,
+    pub b: Vector3<T>,
+    pub radius: T,
+}
+
+impl<T: BaseFloat> Capsule<T> {
+    pub fn new(a: Vector3<T>, b: Vector3<T>, radius: T) -> Self {
+        Capsule { a, b, radius }
+    }
+
+    /// Returns the point on the segment of the capsule that lies closest to the point `p`.
+    pub fn closest_segment_point(&self, p: &Vector3<T>) -> Vector3<T> {
+        let ab = self.b - self.a;
+        let len2 = ab.norm_squared();
+        if len2 <= T::default_epsilon() {
+            return self.a;
+        }
+        let t = ((p - self.a).dot(&ab) / len2).clamp(T::zero(), T::one());
+        self.a + ab.scale(t)
+    }
+
+    /// Returns the closest points between the segments of this and the `other` capsule, as the
+    /// point on this segment and the point on the other segment. For parallel segments, one of the
+    /// closest pairs is chosen.
+    pub fn closest_segment_points(&self, other: &Capsule<T>) -> (Vector3<T>, Vector3<T>) {
+        let (d1, d2) = (self.b - self.a, other.b - other.a);
+        let r = self.a - other.a;
+        let (a, e, f) = (d1.norm_squared(), d2.norm_squared(), d2.dot(&r));
+        let eps = T::default_epsilon();
+
+        let (s, t) = if a <= eps && e <= eps {
+            (T::zero(), T::zero())
+        } else if a <= eps {
+            (T::zero(), (f / e).clamp(T::zero(), T::one()))
+        } else {
+            let c = d1.dot(&r);
+            if e <= eps {
+                ((-c / a).clamp(T::zero(), T::one()), T::zero())
+            } else {
+                let b = d1.dot(&d2);
+                let denom = a * e - b * b;
+                let mut s = if denom > eps {
+                    ((b * f - c * e) / denom).clamp(T::zero(), T::one())
+                } else {
+                    T::zero()
+                };
+                let mut t = (b * s + f) / e;
+                if t < T::zero() {
+                    t = T::zero();
+                    s = (-c / a).clamp(T::zero(), T::one());
+                } else if t > T::one() {
+                    t = T::one();
+                    s = ((b - c) / a).clamp(T::zero(), T::one());
+                }
+                (s, t)
+            }
+        };
+        (self.a + d1.scale(s), other.a + d2.scale(t))
+    }
+
+    /// Returns the point on the segment of the capsule that minimizes the distance function `dist`,
+    /// together with the minimal distance. The distance function has to be convex along the
+    /// segment, like the (signed) distance to a convex volume, for the minimum to be found.
+    ///
+    /// The minimum is found through a golden section search, which only needs evaluations of
+    /// `dist` and therefore works with any convex volume.
+    pub fn closest_segment_point_by<F>(&self, dist: F) -> (Vector3<T>, T)
+    where F: Fn(&Vector3<T>) -> T {
+        let ab = self.b - self.a;
+        let ratio = (nalgebra::convert::<f64, T>(5.0).sqrt() - T::one()) * T::half();
+        let (mut lo, mut hi) = (T::zero(), T::one());
+        let mut x1 = hi - (hi - lo) * ratio;
+        let mut x2 = lo + (hi - lo) * ratio;
+        let (mut f1, mut f2) = (dist(&(self.a + ab.scale(x1))), dist(&(self.a + ab.scale(x2))));
+        for _ in 0..SEGMENT_SEARCH_ITERATIONS {
+            if f1 <= f2 {
+                hi = x2;
+                x2 = x1;
+                f2 = f1;
+                x1 = hi - (hi - lo) * ratio;
+                f1 = dist(&(self.a + ab.scale(x1)));
+            } else {
+                lo = x1;
+                x1 = x2;
+                f1 = f2;
+                x2 = lo + (hi - lo) * ratio;
+                f2 = dist(&(self.a + ab.scale(x2)));
+            }
+        }
+
+        // the end points are not covered by the interior samples of the search
+        let mid = self.a + ab.scale((lo + hi) * T::half());
+        [self.a, self.b, mid].into_iter()
+            .map(|p| (p, dist(&p)))
+            .fold((self.a, T::MAX), |best, (p, d)| if d < best.1 { (p, d) } else { best })
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 3> for Capsule<T> {
+    fn center(&self) -> Vector3<T> {
+        (self.a + self.b) * T::half()
+    }
+
+    fn area(&self) -> T {
+        // proportional to the surface area of the capsule
+        self.radius * (self.radius * T::two() + (self.b - self.a).norm())
+    }
+
+    fn min(&self) -> Vector3<T> {
+        self.a.inf(&self.b) - Vector3::repeat(self.radius)
+    }
+
+    fn max(&self) -> Vector3<T> {
+        self.a.sup(&self.b) + Vector3::repeat(self.radius)
+    }
+
+    fn size(&self) -> Vector3<T> {
+        self.max() - self.min()
+    }
+
+    fn half_size(&self) -> Vector3<T> {
+        self.size() * T::half()
+    }
+
+    fn distance_to(&self, point: &Vector3<T>) -> T {
+        ((point - self.closest_segment_point(point)).norm() - self.radius).max(T::zero())
+    }
+}
+
+impl<T: BaseFloat> DistanceTo<T, 3> for Capsule<T> {}
+
+impl<T: BaseFloat> BVIntersector<T, Capsule<T>, 3> for Capsule<T> {
+    fn intersects(&self, other: &Capsule<T>) -> bool {
+        let (p, q) = self.closest_segment_points(other);
+        (q - p).norm() <= self.radius + other.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Sphere<T, 3>, 3> for Capsule<T> {
+    fn intersects(&self, other: &Sphere<T, 3>) -> bool {
+        (other.center - self.closest_segment_point(&other.center)).norm() <= self.radius + other.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for Capsule<T> {
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        self.closest_segment_point_by(|p| other.closest_point(p).1).1 <= self.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for Capsule<T> {
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        self.closest_segment_point_by(|p| other.distance_to(p)).1 <= self.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Vector3<T>, 3> for Capsule<T> {
+    fn intersects(&self, other: &Vector3<T>) -> bool {
+        (other - self.closest_segment_point(other)).norm() <= self.radius
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::system::inertia::Transformer;
+    use crate::volume::aabb::AABB;
+    use crate::volume::BVIntersector;
+    use crate::volume::capsule::Capsule;
+    use crate::volume::oriented::OBB;
+    use crate::volume::sphere::Sphere;
+
+    #[test]
+    fn intersects() {
+        let capsule = Capsule::new(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), 0.5);
+
+        // crossing capsules only touch in the middle of both segments
+        let crossing = Capsule::new(Vector3::new(0.0, 0.9, -1.0), Vector3::new(0.0, 0.9, 1.0), 0.5);
+        assert!(capsule.intersects(&crossing));
+        let parallel = Capsule::new(Vector3::new(0.0, 1.1, 0.0), Vector3::new(2.0, 1.1, 0.0), 0.5);
+        assert!(!capsule.intersects(&parallel));
+
+        assert!(capsule.intersects(&Sphere::new(Vector3::new(1.4, 0.0, 0.0), 0.1)));
+        assert!(!capsule.intersects(&Sphere::new(Vector3::new(1.0, 0.7, 0.0), 0.1)));
+        assert!(capsule.intersects(&Vector3::new(0.3, 0.0, 0.4)));
+
+        // the box touches the middle of the segment with its edge
+        let obb = OBB {
+            half_size: Vector3::repeat(0.5),
+            transform: Transformer::new(
+                Vector3::new(0.0, 1.1, 0.0),
+                UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_4),
+                Vector3::repeat(1.0),
+                Vector3::zeros(),
+            ),
+        };
+        assert!(capsule.intersects(&obb));
+        let far = OBB {
+            half_size: Vector3::repeat(0.5),
+            transform: Transformer::new(
+                Vector3::new(0.0, 1.3, 0.0),
+                UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_4),
+                Vector3::repeat(1.0),
+                Vector3::zeros(),
+            ),
+        };
+        assert!(!capsule.intersects(&far));
+
+        let aabb = AABB { min: Vector3::new(0.8, 0.45, -1.0), max: Vector3::new(2.0, 2.0, 1.0) };
+        assert!(capsule.intersects(&aabb));
+        let aabb = AABB { min: Vector3::new(1.3, 0.45, -1.0), max: Vector3::new(2.0, 2.0, 1.0) };
+        assert!(!capsule.intersects(&aabb));
+    }
+}
@@ -0,0 +1,213 @@
+use nalgebra::Vector3;
+use crate::helper::BaseFloat;
+use crate::system::inertia::{err, Error, ErrorType, MassDistribution, Transformer};
+use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::aabb::AABB;
+use crate::volume::oriented::OBB;
+use crate::volume::shape::ShapeVolume;
+
+/// Collision shape that is made of multiple child shapes, like an L-shaped block, a table or a
+/// ship. Each child is specified in its own reference frame, together with its state in the
+/// reference frame of the compound.
+///
+/// The children are kept in the reference frame of the compound as well, such that intersection
+/// tests do not have to transform them. The bounds of the compound are the union of the bounds of
+/// all children.
+pub struct CompoundCollider<T> {
+    children: Vec<(Transformer<T>, ShapeVolume<T>)>,
+    /// Child shapes in the reference frame of the compound.
+    volumes: Vec<ShapeVolume<T>>,
+    bounds: AABB<T, 3>,
+}
+
+impl<T: BaseFloat> Default for CompoundCollider<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: BaseFloat> CompoundCollider<T> {
+    /// Creates a new compound without any children.
+    pub fn new() -> Self {
+        CompoundCollider {
+            children: Vec::new(),
+            volumes: Vec::new(),
+            bounds: AABB::new(),
+        }
+    }
+
+    /// Adds the child `shape` with the state `local` in the reference frame of the compound.
+    pub fn push(&mut self, local: Transformer<T>, shape: ShapeVolume<T>) {
+        let volume = shape.transformed(&local);
+        self.bounds.grow(&volume.min());
+        self.bounds.grow(&volume.max());
+        self.volumes.push(volume);
+        self.children.push((local, shape));
+    }
+
+    /// Returns the amount of children in the compound.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns true, if the compound has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the children of the compound in their own reference frames, together with their
+    /// states in the reference frame of the compound.
+    pub fn children(&self) -> &[(Transformer<T>, ShapeVolume<T>)] {
+        &self.children
+    }
+
+    /// Returns the children of the compound in the reference frame of the compound.
+    pub fn volumes(&self) -> &[ShapeVolume<T>] {
+        &self.volumes
+    }
+
+    /// Returns this compound in the laboratory frame for the transformer state `t` of the body,
+    /// like `ShapeVolume::transformed`. The states of the children are composed with `t`.
+    pub fn transformed(&self, t: &Transformer<T>) -> Self {
+        let mut compound = Self::new();
+        for (local, shape) in &self.children {
+            compound.push(t.trafo(local), shape.clone());
+        }
+        compound
+    }
+
+    /// Combines the mass distributions of the children into the mass distribution of the compound
+    /// (see `MassDistribution::combine`), such that the inertia of the body matches its shape.
+    /// The mass distributions are specified in the reference frames of the children, in the order
+    /// of the children.
+    ///
+    /// Returns an error, if the amount of mass distributions does not match the amount of
+    /// children, or if the mass distributions cannot be combined.
+    pub fn mass(&self, masses: &[MassDistribution<T>]) -> Result<MassDistribution<T>, Error> {
+        if masses.len() != self.children.len() {
+            return Err(err!(physics format!(
+                "Expected {} mass distributions, one per child, but got {}",
+                self.children.len(),
+                masses.len()
+            )));
+        }
+        MassDistribution::combine(self.children.iter().map(|(local, _)| local).zip(masses))
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 3> for CompoundCollider<T> {
+    fn center(&self) -> Vector3<T> {
+        self.bounds.center()
+    }
+
+    fn area(&self) -> T {
+        self.bounds.area()
+    }
+
+    fn min(&self) -> Vector3<T> {
+        self.bounds.min
+    }
+
+    fn max(&self) -> Vector3<T> {
+        self.bounds.max
+    }
+
+    fn size(&self) -> Vector3<T> {
+        self.bounds.size()
+    }
+
+    fn half_size(&self) -> Vector3<T> {
+        self.bounds.half_size()
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for CompoundCollider<T> {
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        self.bounds.intersects(other) && self.volumes.iter().any(|v| v.intersects(other))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for CompoundCollider<T> {
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        self.bounds.intersects(other) && self.volumes.iter().any(|v| other.intersects(v))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Vector3<T>, 3> for CompoundCollider<T> {
+    fn intersects(&self, other: &Vector3<T>) -> bool {
+        self.bounds.intersects(other) && self.volumes.iter().any(|v| v.distance_to(other) <= T::zero())
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+    use crate::system::inertia::{ErrorType, MassDistribution};
+    use crate::test_util::{at, cuboid, unit_box};
+    use crate::volume::{BoundingVolume, BVIntersector};
+    use crate::volume::aabb::AABB;
+    use crate::volume::compound::CompoundCollider;
+    use crate::volume::shape::ShapeVolume;
+    use crate::volume::sphere::Sphere;
+
+    /// Compound of two unit boxes that only touch at an edge, such that half of the bounds of the
+    /// compound are empty.
+    fn diagonal() -> CompoundCollider<f64> {
+        let mut compound = CompoundCollider::new();
+        let unit = ShapeVolume::Box(unit_box(Vector3::zeros(), UnitQuaternion::identity()));
+        compound.push(at(Vector3::new(1.0, 0.0, 0.0), UnitQuaternion::identity()), unit.clone());
+        compound.push(at(Vector3::new(0.0, 1.0, 0.0), UnitQuaternion::identity()), unit);
+        compound
+    }
+
+    #[test]
+    fn bounds() {
+        let compound = diagonal();
+        assert_eq!(compound.len(), 2);
+        assert_eq!(compound.min(), Vector3::new(-0.5, -0.5, -0.5));
+        assert_eq!(compound.max(), Vector3::new(1.5, 1.5, 0.5));
+        assert_eq!(compound.center(), Vector3::new(0.5, 0.5, 0.0));
+
+        let moved = compound.transformed(&at(Vector3::new(0.0, 0.0, 2.0), UnitQuaternion::identity()));
+        assert_eq!(moved.min(), Vector3::new(-0.5, -0.5, 1.5));
+        assert_eq!(moved.max(), Vector3::new(1.5, 1.5, 2.5));
+    }
+
+    #[test]
+    fn intersects() {
+        let compound = diagonal();
+        let probe = |x: f64, y: f64| AABB { min: Vector3::new(x, y, -0.1), max: Vector3::new(x + 0.2, y + 0.2, 0.1) };
+
+        // either child is hit, but not the empty corners within the bounds
+        assert!(compound.intersects(&probe(1.0, 0.0)));
+        assert!(compound.intersects(&probe(0.0, 1.0)));
+        assert!(!compound.intersects(&probe(1.0, 1.0)));
+        assert!(!compound.intersects(&probe(3.0, 0.0)));
+
+        let eighth = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4);
+        let obb = |x: f64, y: f64| cuboid(Vector3::new(x, y, 0.0), eighth, Vector3::repeat(0.1));
+        assert!(compound.intersects(&obb(1.5, 0.5)));
+        assert!(compound.intersects(&obb(0.5, 1.5)));
+        assert!(!compound.intersects(&obb(1.2, 1.2)));
+
+        assert!(compound.intersects(&Vector3::new(1.4, 0.0, 0.0)));
+        assert!(compound.intersects(&Vector3::new(0.0, 1.4, 0.0)));
+        assert!(!compound.intersects(&Vector3::new(1.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn mass() {
+        let mut compound = diagonal();
+        compound.push(at(Vector3::new(-1.0, 0.0, 0.0), UnitQuaternion::identity()), ShapeVolume::Sphere(Sphere::new(Vector3::zeros(), 0.5)));
+        let part = MassDistribution::new(1.0, Vector3::zeros(), Matrix3::identity()).ok().unwrap();
+        let mass = compound.mass(&[part.clone(), part.clone(), part.clone()]).ok().unwrap();
+        assert_eq!(*mass.mass(), 3.0);
+        assert!((mass.center_of_mass() - Vector3::new(0.0, 1.0 / 3.0, 0.0)).norm() < 1e-12);
+
+        // every child needs a mass distribution
+        let error = compound.mass(&[part]).err().unwrap();
+        assert_eq!(error.error_type(), ErrorType::PhysicsError);
+    }
+}
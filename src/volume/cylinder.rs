@@ -0,0 +1,150 @@
+use nalgebra::Vector3;
+use crate::helper::{BaseFloat, gjk};
+use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::aabb::AABB;
+
+/// Solid cylinder, which consists of all points within `radius` of the axis through `a` and `b`
+/// that lie between the two flat caps at `a` and `b`. The end points must not coincide.
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder<T> {
+    pub a: Vector3<T>,
+    pub b: Vector3<T>,
+    pub radius: T,
+}
+
+impl<T: BaseFloat> Cylinder<T> {
+    pub fn new(a: Vector3<T>, b: Vector3<T>, radius: T) -> Self {
+        Cylinder { a, b, radius }
+    }
+
+    /// Returns the unit vector along the axis of the cylinder, which points from `a` to `b`.
+    pub fn axis(&self) -> Vector3<T> {
+        (self.b - self.a).normalize()
+    }
+
+    /// Returns the length of the axis between the two caps.
+    pub fn length(&self) -> T {
+        (self.b - self.a).norm()
+    }
+
+    /// Returns the point of the cylinder that lies farthest in the direction `dir`, which lies on
+    /// the rim of one of the caps, or in the center of a cap if `dir` is parallel to the axis.
+    pub fn support(&self, dir: &Vector3<T>) -> Vector3<T> {
+        let axis = self.axis();
+        let along = dir.dot(&axis);
+        let cap = if along > T::zero() { self.b } else { self.a };
+        let radial = dir - axis.scale(along);
+        match radial.try_normalize(T::default_epsilon()) {
+            Some(radial) => cap + radial.scale(self.radius),
+            None => cap,
+        }
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 3> for Cylinder<T> {
+    fn center(&self) -> Vector3<T> {
+        (self.a + self.b) * T::half()
+    }
+
+    fn area(&self) -> T {
+        // proportional to the surface area of the cylinder
+        self.radius * (self.radius + self.length())
+    }
+
+    fn min(&self) -> Vector3<T> {
+        self.center() - self.half_size()
+    }
+
+    fn max(&self) -> Vector3<T> {
+        self.center() + self.half_size()
+    }
+
+    fn size(&self) -> Vector3<T> {
+        self.half_size() * T::two()
+    }
+
+    fn half_size(&self) -> Vector3<T> {
+        // the caps are discs, which extend by `r * sqrt(1 - axis_i²)` along each coordinate axis
+        let axis = self.axis();
+        let half_length = self.length() * T::half();
+        axis.map(|u| {
+            let disc = (T::one() - u * u).max(T::zero()).sqrt() * self.radius;
+            u.abs() * half_length + disc
+        })
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Vector3<T>, 3> for Cylinder<T> {
+    fn intersects(&self, other: &Vector3<T>) -> bool {
+        let axis = self.axis();
+        let rel = other - self.a;
+        let along = rel.dot(&axis);
+        along >= T::zero() && along <= self.length()
+            && (rel - axis.scale(along)).norm_squared() <= self.radius * self.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for Cylinder<T> {
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        // the bounding box of the cylinder rejects most boxes without running GJK
+        if !other.intersects(&AABB { min: self.min(), max: self.max() }) {
+            return false;
+        }
+        gjk::intersects(
+            |d: &Vector3<T>| self.support(d),
+            |d: &Vector3<T>| Vector3::from_fn(|i, _| {
+                if d[i] > T::zero() { other.max[i] } else { other.min[i] }
+            }),
+        )
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Cylinder<T>, 3> for AABB<T, 3> {
+    fn intersects(&self, other: &Cylinder<T>) -> bool {
+        other.intersects(self)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+    use crate::volume::aabb::AABB;
+    use crate::volume::{BoundingVolume, BVIntersector};
+    use crate::volume::cylinder::Cylinder;
+
+    #[test]
+    fn bounds() {
+        // axis tilted by 45 degrees in the xy-plane, with length 2 and radius 0.5
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let cylinder = Cylinder::new(Vector3::new(-s, -s, 1.0), Vector3::new(s, s, 1.0), 0.5);
+        let half = Vector3::new(s + 0.5 * s, s + 0.5 * s, 0.5);
+        assert!((cylinder.half_size() - half).norm() < 1e-12);
+        assert!((cylinder.min() - (Vector3::new(0.0, 0.0, 1.0) - half)).norm() < 1e-12);
+        assert!((cylinder.max() - (Vector3::new(0.0, 0.0, 1.0) + half)).norm() < 1e-12);
+
+        // an upright cylinder is bounded by its radius in x and z
+        let upright = Cylinder::new(Vector3::zeros(), Vector3::new(0.0, 3.0, 0.0), 0.5);
+        assert!((upright.max() - Vector3::new(0.5, 3.0, 0.5)).norm() < 1e-12);
+        assert!((upright.min() - Vector3::new(-0.5, 0.0, -0.5)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn intersects() {
+        let cylinder = Cylinder::new(Vector3::zeros(), Vector3::new(0.0, 2.0, 0.0), 1.0);
+        assert!(cylinder.intersects(&Vector3::new(0.5, 1.9, 0.5)));
+        assert!(!cylinder.intersects(&Vector3::new(0.0, 2.1, 0.0)));
+        // the corner of the bounding box lies outside of the cylinder
+        assert!(!cylinder.intersects(&Vector3::new(0.9, 1.0, 0.9)));
+
+        let aabb = AABB { min: Vector3::new(0.5, 0.5, 0.5), max: Vector3::new(2.0, 1.0, 2.0) };
+        assert!(cylinder.intersects(&aabb));
+        // overlaps the bounding box, but not the cylinder
+        let corner = AABB { min: Vector3::new(0.75, 0.5, 0.75), max: Vector3::new(2.0, 1.0, 2.0) };
+        assert!(!cylinder.intersects(&corner));
+        assert!(!corner.intersects(&cylinder));
+        let above = AABB { min: Vector3::new(-0.5, 2.1, -0.5), max: Vector3::new(0.5, 3.0, 0.5) };
+        assert!(!cylinder.intersects(&above));
+    }
+}
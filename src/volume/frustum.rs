@@ -0,0 +1,119 @@
+use nalgebra::Vector3;
+use crate::helper::BaseFloat;
+use crate::volume::BVIntersector;
+use crate::volume::aabb::AABB;
+use crate::volume::oriented::OBB;
+use crate::volume::plane::Plane;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
+
+/// View frustum, which is the intersection of the solid half-spaces behind six planes. The normals
+/// of the planes point out of the frustum (see `Plane`).
+///
+/// The frustum can be used as an intersector for TLAS and BVH queries, like
+/// `world.intersect(&frustum, 0)`, to find the elements that are visible to a camera. A volume
+/// intersects the frustum, if it intersects the half-spaces of all planes. This test is
+/// conservative: large volumes near the corners of the frustum may be reported, even though they
+/// lie outside of it.
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum<T> {
+    pub planes: [Plane<T>; 6],
+}
+
+impl<T: BaseFloat> Frustum<T> {
+    pub fn new(planes: [Plane<T>; 6]) -> Self {
+        Frustum { planes }
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for Frustum<T> {
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects(other))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for Frustum<T> {
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects(other))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Sphere<T, 3>, 3> for Frustum<T> {
+    fn intersects(&self, other: &Sphere<T, 3>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects(other))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, ShapeVolume<T>, 3> for Frustum<T> {
+    fn intersects(&self, other: &ShapeVolume<T>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects(other))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Vector3<T>, 3> for Frustum<T> {
+    fn intersects(&self, other: &Vector3<T>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects(other))
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::test_util::cuboid;
+    use crate::volume::aabb::AABB;
+    use crate::volume::BVIntersector;
+    use crate::volume::frustum::Frustum;
+    use crate::volume::oriented::OBB;
+    use crate::volume::plane::Plane;
+    use crate::volume::shape::ShapeVolume;
+    use crate::volume::sphere::Sphere;
+
+    /// Frustum of a camera at the origin, which looks along the negative z-axis with a field of
+    /// view of 90 degrees, between the near and far planes at a distance of 1 and 100.
+    fn frustum() -> Frustum<f64> {
+        Frustum::new([
+            Plane::new(Vector3::new(0.0, 0.0, 1.0), -1.0),
+            Plane::new(Vector3::new(0.0, 0.0, -1.0), 100.0),
+            Plane::new(Vector3::new(-1.0, 0.0, 1.0), 0.0),
+            Plane::new(Vector3::new(1.0, 0.0, 1.0), 0.0),
+            Plane::new(Vector3::new(0.0, -1.0, 1.0), 0.0),
+            Plane::new(Vector3::new(0.0, 1.0, 1.0), 0.0),
+        ])
+    }
+
+    fn cube(center: Vector3<f64>, half_size: f64) -> AABB<f64, 3> {
+        AABB { min: center.add_scalar(-half_size), max: center.add_scalar(half_size) }
+    }
+
+    fn obb(center: Vector3<f64>, angle: f64) -> OBB<f64> {
+        cuboid(center, UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle), Vector3::repeat(0.4))
+    }
+
+    #[test]
+    fn visibility() {
+        let frustum = frustum();
+        assert!(frustum.intersects(&cube(Vector3::new(0.0, 0.0, -10.0), 1.0)));
+        // boxes that straddle the side or far planes are partially visible
+        assert!(frustum.intersects(&cube(Vector3::new(10.5, 0.0, -10.0), 1.0)));
+        assert!(frustum.intersects(&cube(Vector3::new(0.0, -5.0, -100.5), 1.0)));
+        // boxes beside, behind and in front of the near plane are not
+        assert!(!frustum.intersects(&cube(Vector3::new(20.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects(&cube(Vector3::new(0.0, 0.0, 5.0), 1.0)));
+        assert!(!frustum.intersects(&cube(Vector3::new(0.0, 0.0, -0.5), 0.25)));
+
+        // the side plane passes the box at a distance of 0.35, where the projected radius of the
+        // box is 0.57 when aligned with the axes, but only 0.4 when rotated onto the plane normal
+        assert!(frustum.intersects(&obb(Vector3::new(10.5, 0.0, -10.0), 0.0)));
+        assert!(frustum.intersects(&obb(Vector3::new(10.55, 0.0, -10.0), std::f64::consts::FRAC_PI_4)));
+        assert!(!frustum.intersects(&obb(Vector3::new(10.7, 0.0, -10.0), std::f64::consts::FRAC_PI_4)));
+        assert!(!frustum.intersects(&obb(Vector3::new(11.5, 0.0, -10.0), 0.0)));
+
+        let sphere = ShapeVolume::Sphere(Sphere::new(Vector3::new(0.0, 10.5, -10.0), 0.5));
+        assert!(frustum.intersects(&sphere));
+        let sphere = ShapeVolume::Sphere(Sphere::new(Vector3::new(0.0, 11.5, -10.0), 0.5));
+        assert!(!frustum.intersects(&sphere));
+        assert!(frustum.intersects(&Vector3::new(-9.0, 9.0, -10.0)));
+    }
+}
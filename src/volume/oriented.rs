@@ -1,15 +1,187 @@
-use nalgebra::{SVector, Vector3};
+use nalgebra::{Matrix3, Rotation3, SVector, UnitQuaternion, Vector2, Vector3};
+use num::Signed;
+use crate::collision::intersection::Ray;
+use crate::collision::model::VertexBuffer;
 use crate::helper::{BaseFloat, separated_axis};
 use crate::system::inertia::Transformer;
 use crate::volume::aabb::AABB;
-use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+use crate::volume::capsule::Capsule;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
 
 /// An implementation for an oriented bounding box
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: BaseFloat + serde::Serialize",
+    deserialize = "T: BaseFloat + serde::Deserialize<'de>"
+)))]
 pub struct OBB<T> {
     pub half_size: Vector3<T>,
     pub transform: Transformer<T>
 }
 
+impl<T: BaseFloat> OBB<T> {
+    /// Creates a new OBB with the specified half sizes along its axes. The center, orientation and
+    /// scale of the box are taken from `transform`.
+    pub fn new(half_size: Vector3<T>, transform: Transformer<T>) -> Self {
+        OBB { half_size, transform }
+    }
+
+    /// Sets the half sizes of the OBB along its axes, without the scale of the transform.
+    pub fn set_half_size(&mut self, half_size: Vector3<T>) {
+        self.half_size = half_size;
+    }
+
+    /// Fits an OBB around the specified point cloud using the principal axes of the points.
+    ///
+    /// The axes of the box are the eigenvectors of the covariance matrix of the points, so that
+    /// the box aligns with the directions of the largest spread. The half sizes are chosen such
+    /// that the box tightly wraps the projections of the points onto these axes. For elongated,
+    /// diagonal objects, this results in far tighter bounds than an axis aligned box. If no points
+    /// are specified, an empty box at the origin is returned.
+    pub fn fit_points(points: &[Vector3<T>]) -> Self {
+        if points.is_empty() {
+            return OBB::from(AABB { min: Vector3::zeros(), max: Vector3::zeros() });
+        }
+
+        let r_len = T::one() / nalgebra::convert::<f64, T>(points.len() as f64);
+        let mean = points.iter().fold(Vector3::zeros(), |sum, p| sum + p) * r_len;
+        let covariance = points.iter()
+            .fold(Matrix3::zeros(), |sum, p| sum + (p - mean) * (p - mean).transpose()) * r_len;
+
+        // the eigenvectors of the symmetric covariance matrix are orthonormal; flip the last axis
+        // if necessary, so that they form a proper rotation
+        let mut axes = covariance.symmetric_eigen().eigenvectors;
+        if axes.determinant() < T::zero() {
+            axes.set_column(2, &-axes.column(2));
+        }
+        let rot = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(axes));
+
+        // bounds of the points in the frame of the principal axes
+        let mut bounds = AABB::new();
+        for p in points {
+            bounds.grow(&(axes.transpose() * (p - mean)));
+        }
+
+        OBB {
+            half_size: bounds.half_size(),
+            transform: Transformer::new(
+                mean + axes * bounds.center(),
+                rot,
+                Vector3::repeat(T::one()),
+                Vector3::zeros()
+            ),
+        }
+    }
+
+    /// Fits an OBB around the vertices of the specified vertex buffer, like `fit_points`. This
+    /// derives the bounds of a body from the geometry of its mesh.
+    ///
+    /// Degenerate vertex sets are handled like any other: the box of vertices that lie in a plane
+    /// or on a line has a zero half size along the axes without spread.
+    pub fn fit(vbo: &VertexBuffer<T, 3>) -> Self {
+        Self::fit_points(vbo.vertices())
+    }
+
+    /// Returns the half extents of the smallest AABB that wraps all eight corners of the OBB.
+    ///
+    /// Since the box is symmetric around its center, the extent along each world axis is the sum
+    /// of the absolute projections of the (transformed) half size axes onto that axis.
+    fn world_extent(&self) -> Vector3<T> {
+        let mat = self.transform.tsro();
+        let mut extent = Vector3::zeros();
+        for i in 0..3 {
+            for j in 0..3 {
+                extent[i] += <T as Signed>::abs(&mat[(i, j)]) * self.half_size[j];
+            }
+        }
+        extent
+    }
+
+    /// Returns the edge lengths of the OBB in the laboratory frame, which includes the scale of
+    /// the transform.
+    fn world_size(&self) -> Vector3<T> {
+        let mat = self.transform.tsro();
+        let mut size = Vector3::zeros();
+        for j in 0..3 {
+            let axis = Vector3::new(mat[(0, j)], mat[(1, j)], mat[(2, j)]);
+            size[j] = axis.norm() * self.half_size[j] * T::two();
+        }
+        size
+    }
+
+    /// Returns the half sizes of the OBB along its axes in the laboratory frame, which includes
+    /// the scale of the transform.
+    pub fn world_half_size(&self) -> Vector3<T> {
+        self.world_size() * T::half()
+    }
+
+    /// Returns the true surface area of the OBB in the laboratory frame. Unlike `area()`, which is
+    /// a cost estimate for acceleration structures, this is the geometrically correct value.
+    pub fn surface_area(&self) -> T {
+        let size = self.world_size();
+        (size.x * size.y + size.y * size.z + size.z * size.x) * T::two()
+    }
+
+    /// Returns the point on or inside the OBB that lies closest to the point `p`, together with the
+    /// signed distance of `p` to the surface of the OBB. Both the point and the distance are
+    /// specified in the laboratory frame.
+    ///
+    /// The point is transformed into the reference frame of the OBB, clamped to the half sizes and
+    /// transformed back. For points outside of the box, the distance is the positive distance to
+    /// the closest point. Points inside of the box are their own closest point; their distance is
+    /// the negative distance to the nearest face of the box.
+    pub fn closest_point(&self, p: &Vector3<T>) -> (Vector3<T>, T) {
+        let rel = self.transform.inv_trafo_point(p);
+        let mut clamped = rel;
+        let mut inside = true;
+        for i in 0..3 {
+            if rel[i] < -self.half_size[i] {
+                clamped[i] = -self.half_size[i];
+                inside = false;
+            } else if rel[i] > self.half_size[i] {
+                clamped[i] = self.half_size[i];
+                inside = false;
+            }
+        }
+        let closest = self.transform.trafo_point(&clamped);
+
+        if inside {
+            // distance to the nearest face, scaled into the laboratory frame
+            let depth = (0..3)
+                .map(|i| (self.half_size[i] - <T as Signed>::abs(&rel[i]))
+                    * <T as Signed>::abs(&self.transform.scale[i]))
+                .fold(T::MAX, T::min);
+            (closest, -depth)
+        } else {
+            (closest, (p - closest).norm())
+        }
+    }
+
+    /// Returns the distances along the ray `ray` at which it enters and exits the box, or `None` if
+    /// the ray misses the box. Rays that start inside of the box enter it at zero.
+    ///
+    /// The ray is moved into the reference frame of the box, where the box is axis-aligned. Since
+    /// the transformation is affine, the distances along the ray are the same in both frames.
+    pub fn intersect_ray(&self, ray: &Ray<T, 3>) -> Option<(T, T)> {
+        let local = Ray {
+            origin: self.transform.inv_trafo_point(&ray.origin),
+            dir: self.transform.inv_trafo_vec(&ray.dir),
+            d: ray.d,
+            intersection: None,
+        };
+        local.clip(&AABB { min: -self.half_size, max: self.half_size })
+    }
+
+    /// Returns the volume of the OBB in the laboratory frame.
+    pub fn volume(&self) -> T {
+        let size = self.world_size();
+        size.x * size.y * size.z
+    }
+}
+
 impl<T: BaseFloat> BoundingVolume<T, 3> for OBB<T> {
     fn center(&self) -> Vector3<T> {
         self.transform.pos + self.transform.trafo_vec(&self.transform.offset)
@@ -22,23 +194,11 @@ impl<T: BaseFloat> BoundingVolume<T, 3> for OBB<T> {
     }
 
     fn min(&self) -> Vector3<T> {
-        let min = self.transform.trafo_point(&(-self.half_size));
-        let max = self.transform.trafo_point(&self.half_size);
-        Vector3::new(
-            T::min(min.x, max.x),
-            T::min(min.y, max.y),
-            T::min(min.z, max.z),
-        )
+        self.center() - self.world_extent()
     }
 
     fn max(&self) -> Vector3<T> {
-        let min = self.transform.trafo_point(&(-self.half_size));
-        let max = self.transform.trafo_point(&self.half_size);
-        Vector3::new(
-            T::max(min.x, max.x),
-            T::max(min.y, max.y),
-            T::max(min.z, max.z),
-        )
+        self.center() + self.world_extent()
     }
 
     fn size(&self) -> Vector3<T> {
@@ -48,8 +208,14 @@ impl<T: BaseFloat> BoundingVolume<T, 3> for OBB<T> {
     fn half_size(&self) -> Vector3<T> {
         self.half_size
     }
+
+    fn distance_to(&self, point: &Vector3<T>) -> T {
+        self.closest_point(point).1.max(T::zero())
+    }
 }
 
+impl<T: BaseFloat> DistanceTo<T, 3> for OBB<T> {}
+
 impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for OBB<T> {
     fn intersects(&self, other: &OBB<T>) -> bool {
         separated_axis::intersects_obb_obb(
@@ -92,3 +258,451 @@ impl<T: BaseFloat> BVIntersector<T, SVector<T, 3>, 3> for OBB<T> {
         true
     }
 }
+
+impl<T: BaseFloat> BVIntersector<T, Sphere<T, 3>, 3> for OBB<T> {
+    fn intersects(&self, other: &Sphere<T, 3>) -> bool {
+        // the signed distance is negative for sphere centers inside of the box, so deep
+        // penetrations are reported as well
+        self.closest_point(&other.center).1 <= other.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Capsule<T>, 3> for OBB<T> {
+    fn intersects(&self, other: &Capsule<T>) -> bool {
+        other.intersects(self)
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, ShapeVolume<T>, 3> for OBB<T> {
+    fn intersects(&self, other: &ShapeVolume<T>) -> bool {
+        match other {
+            ShapeVolume::Box(obb) => self.intersects(obb),
+            ShapeVolume::Sphere(sphere) => self.intersects(sphere),
+            ShapeVolume::Capsule(capsule) => self.intersects(capsule),
+        }
+    }
+}
+
+impl<T: BaseFloat> From<AABB<T, 3>> for OBB<T> {
+    /// Creates an axis aligned OBB that covers the same volume as the AABB.
+    fn from(aabb: AABB<T, 3>) -> Self {
+        OBB {
+            half_size: aabb.half_size(),
+            transform: Transformer::new(
+                aabb.center(),
+                UnitQuaternion::identity(),
+                Vector3::repeat(T::one()),
+                Vector3::zeros()
+            ),
+        }
+    }
+}
+
+impl<T: BaseFloat> From<&OBB<T>> for AABB<T, 3> {
+    /// Wraps an AABB around all eight corners of the OBB.
+    fn from(obb: &OBB<T>) -> Self {
+        AABB {
+            min: obb.min(),
+            max: obb.max(),
+        }
+    }
+}
+
+
+
+/// An implementation for an oriented bounding box in two spacial dimensions. The box is rotated
+/// counter-clockwise by `rotation` (in radians) around its center.
+#[derive(Clone, Copy, Debug)]
+pub struct OBB2D<T> {
+    pub half_size: Vector2<T>,
+    pub center: Vector2<T>,
+    pub rotation: T,
+}
+
+impl<T: BaseFloat> OBB2D<T> {
+    pub fn new(half_size: Vector2<T>, center: Vector2<T>, rotation: T) -> Self {
+        OBB2D { half_size, center, rotation }
+    }
+
+    /// Returns the two axes of the box in the laboratory frame, which are the x and y axes rotated
+    /// by the rotation of the box.
+    pub fn axes(&self) -> (Vector2<T>, Vector2<T>) {
+        let (sin, cos) = self.rotation.sin_cos();
+        (Vector2::new(cos, sin), Vector2::new(-sin, cos))
+    }
+
+    /// Returns the half extents of the smallest AABB that wraps all four corners of the box.
+    fn world_extent(&self) -> Vector2<T> {
+        let (a0, a1) = self.axes();
+        a0.abs() * self.half_size.x + a1.abs() * self.half_size.y
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 2> for OBB2D<T> {
+    fn center(&self) -> Vector2<T> {
+        self.center
+    }
+
+    fn area(&self) -> T {
+        self.half_size.x + self.half_size.y
+    }
+
+    fn min(&self) -> Vector2<T> {
+        self.center - self.world_extent()
+    }
+
+    fn max(&self) -> Vector2<T> {
+        self.center + self.world_extent()
+    }
+
+    fn size(&self) -> Vector2<T> {
+        self.half_size * T::two()
+    }
+
+    fn half_size(&self) -> Vector2<T> {
+        self.half_size
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB2D<T>, 2> for OBB2D<T> {
+    fn intersects(&self, other: &OBB2D<T>) -> bool {
+        let (a0, a1) = self.axes();
+        let (b0, b1) = other.axes();
+        separated_axis::intersects_obb_obb_2d(
+            &a0, &a1,
+            &b0, &b1,
+            &(other.center - self.center),
+            self.half_size.x, self.half_size.y,
+            other.half_size.x, other.half_size.y
+        )
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 2>, 2> for OBB2D<T> {
+    fn intersects(&self, other: &AABB<T, 2>) -> bool {
+        let (a0, a1) = self.axes();
+        let other_half_size = other.half_size();
+        separated_axis::intersects_obb_aabb_2d(
+            &a0, &a1,
+            &(other.center() - self.center),
+            self.half_size.x, self.half_size.y,
+            other_half_size.x, other_half_size.y
+        )
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, SVector<T, 2>, 2> for OBB2D<T> {
+    fn intersects(&self, other: &SVector<T, 2>) -> bool {
+        // project the point onto the axes of the box
+        let (a0, a1) = self.axes();
+        let rel = other - self.center;
+        <T as Signed>::abs(&a0.dot(&rel)) <= self.half_size.x
+            && <T as Signed>::abs(&a1.dot(&rel)) <= self.half_size.y
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector2, Vector3};
+    use crate::collision::intersection::Ray;
+    use crate::collision::model::VertexBuffer;
+    use crate::system::inertia::Transformer;
+    use crate::volume::aabb::AABB;
+    use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+    use crate::volume::oriented::{OBB, OBB2D};
+    use crate::volume::sphere::Sphere;
+
+    #[test]
+    fn aabb_round_trip() {
+        let obb = OBB {
+            half_size: Vector3::new(0.5, 1.0, 2.0),
+            transform: Transformer::new(
+                Vector3::new(1.0, 2.0, 3.0),
+                UnitQuaternion::identity(),
+                Vector3::repeat(1.0),
+                Vector3::zeros()
+            ),
+        };
+        let aabb = AABB::from(&obb);
+        assert_eq!(aabb.min, Vector3::new(0.5, 1.0, 1.0));
+        assert_eq!(aabb.max, Vector3::new(1.5, 3.0, 5.0));
+
+        let round_trip = OBB::from(aabb);
+        assert_eq!(round_trip.half_size, obb.half_size);
+        assert_eq!(round_trip.center(), obb.center());
+        assert_eq!(round_trip.transform.rot, obb.transform.rot);
+    }
+
+    #[test]
+    fn new() {
+        let quarter = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2);
+        let mut obb = OBB::new(
+            Vector3::new(1.0, 0.5, 2.0),
+            Transformer::new(Vector3::new(1.0, 2.0, 3.0), quarter, Vector3::repeat(1.0), Vector3::zeros()),
+        );
+        // the quarter turn swaps the extents along the x- and y-axis
+        assert!((obb.min() - Vector3::new(0.5, 1.0, 1.0)).norm() < 1e-12);
+        assert!((obb.max() - Vector3::new(1.5, 3.0, 5.0)).norm() < 1e-12);
+        assert_eq!(obb.center(), Vector3::new(1.0, 2.0, 3.0));
+
+        obb.set_half_size(Vector3::repeat(1.0));
+        assert!((obb.max() - Vector3::new(2.0, 3.0, 4.0)).norm() < 1e-12);
+        assert_eq!(obb.half_size(), Vector3::repeat(1.0));
+    }
+
+    #[test]
+    fn closest_point() {
+        let obb: OBB<f64> = OBB {
+            half_size: Vector3::new(1.0, 0.5, 0.5),
+            transform: Transformer::new(
+                Vector3::new(1.0, 0.0, 0.0),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+                Vector3::new(1.0, 2.0, 1.0),
+                Vector3::zeros()
+            ),
+        };
+        // the box covers x in [0, 2], y in [-1, 1] and z in [-0.5, 0.5]
+        let assert_closest = |p: Vector3<f64>, closest: Vector3<f64>, d: f64| {
+            let (c, dist) = obb.closest_point(&p);
+            assert!((c - closest).norm() < 1e-12, "{c} != {closest}");
+            assert!((dist - d).abs() < 1e-12, "{dist} != {d}");
+        };
+
+        // outside of a face, an edge and a corner
+        assert_closest(Vector3::new(3.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0), 1.0);
+        assert_closest(Vector3::new(1.0, 4.0, 4.5), Vector3::new(1.0, 1.0, 0.5), 5.0);
+        assert_closest(Vector3::new(-1.0, -2.0, -1.5), Vector3::new(0.0, -1.0, -0.5), 3f64.sqrt());
+        // on the surface and inside
+        assert_closest(Vector3::new(1.0, 1.0, 0.0), Vector3::new(1.0, 1.0, 0.0), 0.0);
+        assert_closest(Vector3::new(1.5, 0.0, 0.0), Vector3::new(1.5, 0.0, 0.0), -0.5);
+        assert_closest(Vector3::new(1.0, 0.0, 0.25), Vector3::new(1.0, 0.0, 0.25), -0.25);
+    }
+
+    #[test]
+    fn sphere_cast() {
+        let obb = OBB {
+            half_size: Vector3::repeat(0.5),
+            transform: Transformer::new(
+                Vector3::zeros(),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4),
+                Vector3::repeat(1.0),
+                Vector3::zeros()
+            ),
+        };
+        // the sphere touches the edge of the rotated box, which points towards it
+        let t = obb.sphere_cast(&Vector3::new(-5.0, 0.0, 0.0), &Vector3::x(), 0.25, 10.0).unwrap();
+        assert!((t - (5.0 - 0.5f64.sqrt() - 0.25)).abs() < 1e-6);
+        assert!(obb.sphere_cast(&Vector3::new(-5.0, 1.0, 0.0), &Vector3::x(), 0.25, 10.0).is_none());
+        assert!(obb.sphere_cast(&Vector3::new(-5.0, 0.0, 0.0), &Vector3::x(), 0.25, 4.0).is_none());
+    }
+
+    #[test]
+    fn sphere_intersection() {
+        let obb = OBB {
+            half_size: Vector3::new(0.5, 0.5, 0.5),
+            transform: Transformer::new(
+                Vector3::new(1.0, 2.0, 3.0),
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.3),
+                Vector3::repeat(1.0),
+                Vector3::zeros()
+            ),
+        };
+        let touches = |local: Vector3<f64>, radius: f64| {
+            let sphere = Sphere::new(obb.transform.trafo_point(&local), radius);
+            assert_eq!(obb.intersects(&sphere), sphere.intersects(&obb));
+            obb.intersects(&sphere)
+        };
+
+        // just touching a face, an edge and a corner
+        let face = Vector3::new(1.0, 0.0, 0.0);
+        assert!(touches(face, 0.5 + 1e-9) && !touches(face, 0.5 - 1e-9));
+        let edge = Vector3::new(0.75, 0.75, 0.0);
+        let d = 0.25 * 2f64.sqrt();
+        assert!(touches(edge, d + 1e-9) && !touches(edge, d - 1e-9));
+        let corner = Vector3::repeat(0.75);
+        let d = 0.25 * 3f64.sqrt();
+        assert!(touches(corner, d + 1e-9) && !touches(corner, d - 1e-9));
+
+        // a small sphere deep inside of the box
+        assert!(touches(Vector3::new(0.1, -0.1, 0.0), 0.01));
+    }
+
+    #[test]
+    fn rotated_wrap() {
+        let obb = OBB {
+            half_size: Vector3::new(1.0, 1.0, 1.0),
+            transform: Transformer::new(
+                Vector3::zeros(),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4),
+                Vector3::repeat(1.0),
+                Vector3::zeros()
+            ),
+        };
+        let aabb = AABB::from(&obb);
+        let expected = Vector3::new(2f64.sqrt(), 2f64.sqrt(), 1.0);
+        assert!((aabb.max - expected).norm() < 1e-12);
+        assert!((aabb.min + expected).norm() < 1e-12);
+    }
+
+    #[test]
+    fn surface_area_and_volume() {
+        let aabb = AABB {
+            min: Vector3::new(0.0, 0.0, 0.0),
+            max: Vector3::new(1.0, 2.0, 3.0),
+        };
+        assert_eq!(aabb.surface_area(), 22.0);
+        assert_eq!(aabb.volume(), 6.0);
+
+        // rotating the box does not change its geometry, while scaling it does
+        let obb: OBB<f64> = OBB {
+            half_size: Vector3::new(0.5, 1.0, 1.5),
+            transform: Transformer::new(
+                Vector3::new(1.0, 2.0, 3.0),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.3),
+                Vector3::new(2.0, 1.0, 1.0),
+                Vector3::zeros()
+            ),
+        };
+        assert!((obb.surface_area() - 32.0).abs() < 1e-12);
+        assert!((obb.volume() - 12.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fit_points() {
+        // points along a thin, diagonal rod
+        let dir = Vector3::new(1.0, 1.0, 1.0).normalize();
+        let side = Vector3::new(1.0, -1.0, 0.0).normalize();
+        let points: Vec<_> = (0..=10)
+            .flat_map(|i| {
+                let p = Vector3::new(1.0, 2.0, 3.0) + dir * (i as f64);
+                [p + side * 0.1, p - side * 0.1]
+            })
+            .collect();
+        let obb = OBB::fit_points(&points);
+
+        // the box is much tighter than the axis aligned box around the points
+        assert!(obb.volume() < 0.01 * AABB::from(&obb).volume());
+        assert!((obb.center() - (Vector3::new(1.0, 2.0, 3.0) + dir * 5.0)).norm() < 1e-9);
+        let mut half_size: Vec<_> = obb.half_size.iter().copied().collect();
+        half_size.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!(half_size[0].abs() < 1e-9);
+        assert!((half_size[1] - 0.1).abs() < 1e-9);
+        assert!((half_size[2] - 5.0).abs() < 1e-9);
+
+        // all points are contained
+        for p in &points {
+            let local = obb.transform.inv_trafo_point(p);
+            assert!((0..3).all(|i| local[i].abs() <= obb.half_size[i] + 1e-9));
+        }
+    }
+
+    #[test]
+    fn fit() {
+        // corners of a box with distinct extents, such that the principal axes are unique
+        let half_size = Vector3::<f64>::new(2.0, 1.0, 0.5);
+        let rot = UnitQuaternion::from_euler_angles(0.3, -0.7, 1.2);
+        let center = Vector3::new(-1.0, 4.0, 2.0);
+        let corners: Vec<_> = (0..8)
+            .map(|i| {
+                let sign = Vector3::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                center + rot * half_size.component_mul(&sign)
+            })
+            .collect();
+        let obb = OBB::fit(&VertexBuffer::new(corners.clone()));
+
+        assert!((obb.center() - center).norm() < 1e-9);
+        assert!((obb.volume() - 8.0).abs() < 1e-9);
+        // the axes of the box match the original axes up to their order and direction
+        for (axis, extent) in [(Vector3::x(), 2.0), (Vector3::y(), 1.0), (Vector3::z(), 0.5)] {
+            let axis = rot * axis;
+            let k = (0..3)
+                .find(|k| (obb.transform.rot * Vector3::ith(*k, 1.0)).dot(&axis).abs() > 1.0 - 1e-9)
+                .unwrap();
+            assert!((obb.half_size[k] - extent).abs() < 1e-9);
+        }
+
+        // the corners of a single face lie in a plane, which results in a flat box
+        let face: Vec<_> = corners.iter().step_by(2).copied().collect();
+        let flat = OBB::fit(&VertexBuffer::new(face));
+        let mut half_size: Vec<_> = flat.half_size.iter().copied().collect();
+        half_size.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!(half_size[0].abs() < 1e-9);
+        assert!((half_size[1] - 0.5).abs() < 1e-9 && (half_size[2] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn obb_2d() {
+        let quarter = std::f64::consts::FRAC_PI_4;
+        let a = OBB2D::new(Vector2::new(2.0, 0.5), Vector2::zeros(), quarter);
+        let extent = 2.5 * quarter.cos();
+        assert!((a.max() - Vector2::repeat(extent)).norm() < 1e-12);
+        assert!((a.min() + Vector2::repeat(extent)).norm() < 1e-12);
+
+        // the tip of the diagonal box reaches into the first box, but not into the second one,
+        // even though the bounding boxes of both overlap
+        let touching = OBB2D::new(Vector2::repeat(0.5), Vector2::new(1.5, 1.5), 0.2);
+        let separated = OBB2D::new(Vector2::repeat(0.5), Vector2::new(1.5, -1.5), 0.2);
+        assert!(a.intersects(&touching) && touching.intersects(&a));
+        assert!(!a.intersects(&separated) && !separated.intersects(&a));
+        let (bounds_a, bounds_b) = (AABB { min: a.min(), max: a.max() }, AABB { min: separated.min(), max: separated.max() });
+        assert!(bounds_a.intersects(&bounds_b));
+
+        // axis aligned boxes that overlap along one axis only
+        let b = OBB2D::new(Vector2::repeat(1.0), Vector2::zeros(), 0.0);
+        assert!(b.intersects(&OBB2D::new(Vector2::repeat(1.0), Vector2::new(0.0, 1.5), 0.0)));
+        assert!(!b.intersects(&OBB2D::new(Vector2::repeat(1.0), Vector2::new(0.0, 2.5), 0.0)));
+
+        let aabb = AABB { min: Vector2::new(1.0, 1.0), max: Vector2::new(2.0, 2.0) };
+        assert!(a.intersects(&aabb) && aabb.intersects(&a));
+        let aabb = AABB { min: Vector2::new(1.0, -2.0), max: Vector2::new(2.0, -1.0) };
+        assert!(!a.intersects(&aabb) && !aabb.intersects(&a));
+
+        assert!(a.intersects(&Vector2::new(1.2, 1.2)));
+        assert!(!a.intersects(&Vector2::new(1.2, 0.0)));
+        assert!(Vector2::new(-1.2, -1.2).intersects(&a));
+    }
+
+    #[test]
+    fn intersect_ray() {
+        let obb: OBB<f64> = OBB {
+            half_size: Vector3::repeat(0.5),
+            transform: Transformer::new(
+                Vector3::zeros(),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4),
+                Vector3::repeat(1.0),
+                Vector3::zeros()
+            ),
+        };
+        let corner = 0.5f64.sqrt();
+        let assert_hit = |ray: Ray<f64, 3>, entry: f64, exit: f64| {
+            let (t0, t1) = obb.intersect_ray(&ray).expect("the ray should hit the box");
+            assert!((t0 - entry).abs() < 1e-12, "{t0} != {entry}");
+            assert!((t1 - exit).abs() < 1e-12, "{t1} != {exit}");
+        };
+
+        assert_hit(Ray::new(Vector3::new(-2.0, 0.0, 0.0), Vector3::x(), 10.0), 2.0 - corner, 2.0 + corner);
+        // rays that start inside of the box enter it at zero
+        assert_hit(Ray::new(Vector3::zeros(), Vector3::x(), 10.0), 0.0, corner);
+        // rays that end before the box do not hit it
+        assert!(obb.intersect_ray(&Ray::new(Vector3::new(-2.0, 0.0, 0.0), Vector3::x(), 1.0)).is_none());
+
+        // the ray passes through the corner of the world AABB, which the rotated box leaves empty
+        let ray = Ray::new(Vector3::new(-1.0, 2.0, 0.0), Vector3::new(1.0, -1.0, 0.0), 10.0);
+        assert!(ray.intersects(&AABB { min: obb.min(), max: obb.max() }));
+        assert!(obb.intersect_ray(&ray).is_none());
+        assert!(!ray.intersects(&obb));
+
+        // distances are measured in multiples of the ray direction, also for scaled boxes
+        let scaled: OBB<f64> = OBB {
+            half_size: Vector3::repeat(0.5),
+            transform: Transformer::new(Vector3::zeros(), UnitQuaternion::identity(), Vector3::new(2.0, 1.0, 1.0), Vector3::zeros()),
+        };
+        let ray = Ray { d: 10.0, origin: Vector3::new(-3.0, 0.0, 0.0), dir: Vector3::new(2.0, 0.0, 0.0), intersection: None };
+        assert_eq!(scaled.intersect_ray(&ray), Some((1.0, 2.0)));
+    }
+}
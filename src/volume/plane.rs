@@ -0,0 +1,138 @@
+use nalgebra::{SVector, Vector3};
+use num::Signed;
+use crate::helper::BaseFloat;
+use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::aabb::AABB;
+use crate::volume::capsule::Capsule;
+use crate::volume::oriented::OBB;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
+
+/// Infinite plane, which bounds the solid half-space behind it. The plane consists of all points
+/// `p` with `normal · p = d`, where the normal has unit length and points out of the half-space.
+/// Volumes intersect the plane, if any part of them touches or lies behind the plane.
+///
+/// A plane has no finite AABB, so it cannot be a leaf of a TLAS or BVH. It is meant as a special
+/// case for static colliders, like the floor of a scene, that are tested against the bounding
+/// volumes of the other bodies directly instead of through the acceleration structures.
+#[derive(Clone, Copy, Debug)]
+pub struct Plane<T> {
+    pub normal: Vector3<T>,
+    pub d: T,
+}
+
+impl<T: BaseFloat> Plane<T> {
+    /// Creates a new plane from its normal and its distance `d` from the origin along the normal.
+    /// The normal is normalized, such that it does not need to have unit length.
+    pub fn new(normal: Vector3<T>, d: T) -> Self {
+        Plane { normal: normal.normalize(), d }
+    }
+
+    /// Creates a new plane with the specified normal that passes through `point`.
+    pub fn from_point(normal: Vector3<T>, point: &Vector3<T>) -> Self {
+        let normal = normal.normalize();
+        Plane { d: normal.dot(point), normal }
+    }
+
+    /// Returns the signed distance of the point to the plane. The distance is positive in front of
+    /// the plane and negative behind it, inside of the half-space.
+    pub fn signed_distance(&self, point: &Vector3<T>) -> T {
+        self.normal.dot(point) - self.d
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for Plane<T> {
+    /// Compares the signed distance of the box center with the radius of the box projected onto
+    /// the normal of the plane.
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        let mat = other.transform.tsro();
+        let mut radius = T::zero();
+        for j in 0..3 {
+            let axis = Vector3::new(mat[(0, j)], mat[(1, j)], mat[(2, j)]);
+            radius += <T as Signed>::abs(&self.normal.dot(&axis)) * other.half_size[j];
+        }
+        self.signed_distance(&other.center()) <= radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for Plane<T> {
+    /// Tests the corner of the box that lies farthest behind the plane, which is the corner at
+    /// the minimum of the box along the axes where the normal is positive and at the maximum
+    /// elsewhere. The box intersects the half-space, if that corner lies behind the plane.
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        let mut corner = other.max;
+        for i in 0..3 {
+            if self.normal[i] > T::zero() {
+                corner[i] = other.min[i];
+            }
+        }
+        self.signed_distance(&corner) <= T::zero()
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Sphere<T, 3>, 3> for Plane<T> {
+    fn intersects(&self, other: &Sphere<T, 3>) -> bool {
+        self.signed_distance(&other.center) <= other.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Capsule<T>, 3> for Plane<T> {
+    fn intersects(&self, other: &Capsule<T>) -> bool {
+        T::min(self.signed_distance(&other.a), self.signed_distance(&other.b)) <= other.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, ShapeVolume<T>, 3> for Plane<T> {
+    fn intersects(&self, other: &ShapeVolume<T>) -> bool {
+        match other {
+            ShapeVolume::Box(obb) => self.intersects(obb),
+            ShapeVolume::Sphere(sphere) => self.intersects(sphere),
+            ShapeVolume::Capsule(capsule) => self.intersects(capsule),
+        }
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, SVector<T, 3>, 3> for Plane<T> {
+    fn intersects(&self, other: &SVector<T, 3>) -> bool {
+        self.signed_distance(other) <= T::zero()
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::test_util::unit_box;
+    use crate::volume::BVIntersector;
+    use crate::volume::oriented::OBB;
+    use crate::volume::plane::Plane;
+    use crate::volume::sphere::Sphere;
+
+    fn cube(y: f64, angle: f64) -> OBB<f64> {
+        unit_box(Vector3::new(3.0, y, -2.0), UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle))
+    }
+
+    #[test]
+    fn intersects() {
+        // floor at y = 1, the normal does not need to be normalized
+        let plane = Plane::new(Vector3::new(0.0, 2.0, 0.0), 1.0);
+        assert_eq!(plane.signed_distance(&Vector3::new(5.0, 3.0, 1.0)), 2.0);
+        assert_eq!(Plane::from_point(Vector3::y(), &Vector3::new(4.0, 1.0, 0.0)).d, 1.0);
+
+        // boxes straddling, above and below the plane
+        assert!(plane.intersects(&cube(1.25, 0.0)));
+        assert!(!plane.intersects(&cube(1.75, 0.0)));
+        assert!(plane.intersects(&cube(-3.0, 0.0)));
+        // a rotated box reaches further down
+        let reach = 0.5 * 2f64.sqrt();
+        assert!(plane.intersects(&cube(1.0 + reach - 0.01, std::f64::consts::FRAC_PI_4)));
+        assert!(!plane.intersects(&cube(1.0 + reach + 0.01, std::f64::consts::FRAC_PI_4)));
+
+        assert!(plane.intersects(&Sphere::new(Vector3::new(0.0, 1.9, 0.0), 1.0)));
+        assert!(!plane.intersects(&Sphere::new(Vector3::new(0.0, 2.1, 0.0), 1.0)));
+        assert!(plane.intersects(&Vector3::new(0.0, 1.0, 0.0)));
+        assert!(plane.intersects(&Vector3::new(0.0, -5.0, 0.0)));
+        assert!(!plane.intersects(&Vector3::new(0.0, 1.01, 0.0)));
+    }
+}
@@ -2,7 +2,7 @@ use nalgebra::SVector;
 use crate::helper::BaseFloat;
 use crate::volume::{BoundingVolume, BVIntersector};
 use crate::volume::aabb::AABB;
-use crate::volume::oriented::OBB;
+use crate::volume::oriented::{OBB, OBB2D};
 
 impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for SVector<T, DIM> {
     fn center(&self) -> SVector<T, DIM> {
@@ -48,3 +48,9 @@ impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for SVector<T, 3> {
         other.intersects(self)
     }
 }
+
+impl<T: BaseFloat> BVIntersector<T, OBB2D<T>, 2> for SVector<T, 2> {
+    fn intersects(&self, other: &OBB2D<T>) -> bool {
+        other.intersects(self)
+    }
+}
@@ -0,0 +1,171 @@
+use nalgebra::Vector3;
+use crate::helper::BaseFloat;
+use crate::system::inertia::Transformer;
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+use crate::volume::aabb::AABB;
+use crate::volume::capsule::Capsule;
+use crate::volume::oriented::OBB;
+use crate::volume::sphere::Sphere;
+
+/// Bounding volume of a physics entity, which can be any of the supported collision shapes.
+/// Intersection tests between two shape volumes are dispatched to the intersector of the
+/// respective pair of shapes.
+#[derive(Clone, Debug)]
+pub enum ShapeVolume<T> {
+    Box(OBB<T>),
+    Sphere(Sphere<T, 3>),
+    Capsule(Capsule<T>),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $v:ident => $e:expr) => {
+        match $self {
+            ShapeVolume::Box($v) => $e,
+            ShapeVolume::Sphere($v) => $e,
+            ShapeVolume::Capsule($v) => $e,
+        }
+    };
+}
+
+impl<T: BaseFloat> ShapeVolume<T> {
+    /// Returns this volume, which is specified in the reference frame of a body, in the laboratory
+    /// frame for the transformer state `t` of the body.
+    ///
+    /// The transform of a box is composed with the transformer state, such that boxes keep their
+    /// offset and rotation relative to the body. Spheres and capsules cannot be scaled
+    /// non-uniformly, so their radius is scaled by the largest scale component, which keeps them
+    /// conservative.
+    pub fn transformed(&self, t: &Transformer<T>) -> Self {
+        let scale = t.scale.abs().max();
+        match self {
+            ShapeVolume::Box(obb) => ShapeVolume::Box(OBB {
+                half_size: obb.half_size,
+                transform: t.trafo(&obb.transform),
+            }),
+            ShapeVolume::Sphere(sphere) => ShapeVolume::Sphere(Sphere::new(
+                t.trafo_point(&sphere.center),
+                sphere.radius * scale,
+            )),
+            ShapeVolume::Capsule(capsule) => ShapeVolume::Capsule(Capsule::new(
+                t.trafo_point(&capsule.a),
+                t.trafo_point(&capsule.b),
+                capsule.radius * scale,
+            )),
+        }
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 3> for ShapeVolume<T> {
+    fn center(&self) -> Vector3<T> {
+        dispatch!(self, v => v.center())
+    }
+
+    fn area(&self) -> T {
+        dispatch!(self, v => v.area())
+    }
+
+    fn min(&self) -> Vector3<T> {
+        dispatch!(self, v => v.min())
+    }
+
+    fn max(&self) -> Vector3<T> {
+        dispatch!(self, v => v.max())
+    }
+
+    fn size(&self) -> Vector3<T> {
+        dispatch!(self, v => v.size())
+    }
+
+    fn half_size(&self) -> Vector3<T> {
+        dispatch!(self, v => v.half_size())
+    }
+
+    fn distance_to(&self, point: &Vector3<T>) -> T {
+        dispatch!(self, v => v.distance_to(point))
+    }
+}
+
+impl<T: BaseFloat> DistanceTo<T, 3> for ShapeVolume<T> {}
+
+impl<T: BaseFloat> BVIntersector<T, ShapeVolume<T>, 3> for ShapeVolume<T> {
+    fn intersects(&self, other: &ShapeVolume<T>) -> bool {
+        match other {
+            ShapeVolume::Box(o) => dispatch!(self, v => v.intersects(o)),
+            ShapeVolume::Sphere(o) => dispatch!(self, v => v.intersects(o)),
+            ShapeVolume::Capsule(o) => dispatch!(self, v => v.intersects(o)),
+        }
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for ShapeVolume<T> {
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        dispatch!(self, v => v.intersects(other))
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::system::inertia::Transformer;
+    use crate::volume::{BoundingVolume, BVIntersector};
+    use crate::volume::aabb::AABB;
+    use crate::volume::capsule::Capsule;
+    use crate::volume::oriented::OBB;
+    use crate::volume::shape::ShapeVolume;
+    use crate::volume::sphere::Sphere;
+
+    #[test]
+    fn dispatch() {
+        let rot = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        let t = Transformer::new(Vector3::new(2.0, 0.0, 0.0), rot, Vector3::repeat(1.0), Vector3::zeros());
+        let shapes = [
+            ShapeVolume::Box(OBB::from(AABB { min: Vector3::repeat(-0.5), max: Vector3::repeat(0.5) })),
+            ShapeVolume::Sphere(Sphere::new(Vector3::zeros(), 0.5)),
+            ShapeVolume::Capsule(Capsule::new(Vector3::new(-0.5, 0.0, 0.0), Vector3::new(0.5, 0.0, 0.0), 0.25)),
+        ].map(|shape| shape.transformed(&t));
+
+        // the capsule is rotated onto the y-axis
+        let ShapeVolume::Capsule(capsule) = &shapes[2] else { unreachable!() };
+        assert!((capsule.a - Vector3::new(2.0, -0.5, 0.0)).norm() < 1e-12);
+        assert!((shapes[1].max() - Vector3::new(2.5, 0.5, 0.5)).norm() < 1e-12);
+
+        // radii are scaled by the largest scale component
+        let scaled = Transformer::new(Vector3::zeros(), rot, Vector3::new(1.0, 3.0, 2.0), Vector3::zeros());
+        let ShapeVolume::Sphere(sphere) = ShapeVolume::Sphere(Sphere::new(Vector3::zeros(), 0.5))
+            .transformed(&scaled) else { unreachable!() };
+        assert!((sphere.radius - 1.5).abs() < 1e-12);
+
+        let probes = [
+            (ShapeVolume::Sphere(Sphere::new(Vector3::new(3.0, 0.0, 0.0), 0.55)), [true, true, false]),
+            (ShapeVolume::Sphere(Sphere::new(Vector3::new(2.0, 0.8, 0.0), 0.1)), [false, false, true]),
+            (ShapeVolume::Capsule(Capsule::new(Vector3::new(2.0, 0.9, -1.0), Vector3::new(2.0, 0.9, 1.0), 0.25)), [false, false, true]),
+        ];
+        for (probe, expected) in &probes {
+            for (shape, expected) in shapes.iter().zip(expected) {
+                assert_eq!(shape.intersects(probe), *expected);
+                assert_eq!(probe.intersects(shape), *expected);
+            }
+        }
+
+        let aabb = AABB { min: Vector3::new(2.4, 0.4, -1.0), max: Vector3::new(4.0, 4.0, 1.0) };
+        assert_eq!(shapes.each_ref().map(|shape| shape.intersects(&aabb)), [true, false, false]);
+    }
+
+    #[test]
+    fn offset_box() {
+        let rot = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        let t = Transformer::new(Vector3::new(2.0, 0.0, 0.0), rot, Vector3::repeat(1.0), Vector3::zeros());
+        let shape = ShapeVolume::Box(OBB::from(AABB { min: Vector3::repeat(1.0), max: Vector3::repeat(2.0) }));
+
+        // the box keeps its offset from the origin of the body, which is rotated with the body
+        let transformed = shape.transformed(&t);
+        assert!((transformed.center() - Vector3::new(0.5, 1.5, 1.5)).norm() < 1e-12);
+        assert!((transformed.min() - Vector3::new(0.0, 1.0, 1.0)).norm() < 1e-12);
+        assert!((transformed.max() - Vector3::new(1.0, 2.0, 2.0)).norm() < 1e-12);
+        let probe = |center: Vector3<f64>| AABB { min: center.add_scalar(-0.1), max: center.add_scalar(0.1) };
+        assert!(transformed.intersects(&probe(Vector3::new(0.5, 1.5, 1.5))));
+        assert!(!transformed.intersects(&probe(Vector3::new(2.0, 0.0, 0.0))));
+    }
+}
@@ -0,0 +1,158 @@
+use nalgebra::SVector;
+use crate::helper::BaseFloat;
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo};
+use crate::volume::aabb::AABB;
+use crate::volume::capsule::Capsule;
+use crate::volume::oriented::OBB;
+
+/// Bounding sphere, or bounding circle in two dimensions.
+#[derive(Clone, Copy, Debug)]
+pub struct Sphere<T, const DIM: usize> {
+    pub center: SVector<T, DIM>,
+    pub radius: T,
+}
+
+impl<T: BaseFloat, const DIM: usize> Sphere<T, DIM> {
+    pub fn new(center: SVector<T, DIM>, radius: T) -> Self {
+        Sphere { center, radius }
+    }
+
+    /// Fits a sphere around the specified points using Ritter's algorithm. If no points are
+    /// specified, an empty sphere at the origin is returned.
+    ///
+    /// The first pass estimates the diameter of the point cloud from a point that lies far away
+    /// from an arbitrary start point, and from the point farthest away from that one. The second
+    /// pass grows the sphere just enough to include every point outside of it. The resulting
+    /// sphere is not minimal, but usually within a few percent of the minimal enclosing sphere.
+    pub fn from_points(points: &[SVector<T, DIM>]) -> Self {
+        let Some(first) = points.first() else {
+            return Sphere::new(SVector::zeros(), T::zero());
+        };
+        let farthest = |from: &SVector<T, DIM>| points.iter()
+            .fold((first, T::zero()), |(best, dist), p| {
+                let d = (p - from).norm_squared();
+                if d > dist { (p, d) } else { (best, dist) }
+            }).0;
+        let a = farthest(first);
+        let b = farthest(a);
+
+        let mut sphere = Sphere::new((a + b) * T::half(), (b - a).norm() * T::half());
+        for p in points {
+            let dist = (p - sphere.center).norm();
+            if dist > sphere.radius {
+                // move the center towards the point, such that the opposite side of the sphere
+                // stays in place
+                let radius = (sphere.radius + dist) * T::half();
+                sphere.center += (p - sphere.center) * ((radius - sphere.radius) / dist);
+                sphere.radius = radius;
+            }
+        }
+        sphere
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BoundingVolume<T, DIM> for Sphere<T, DIM> {
+    fn center(&self) -> SVector<T, DIM> {
+        self.center
+    }
+
+    fn area(&self) -> T {
+        // proportional to the surface area in three dimensions; the constant factor does not
+        // matter for cost estimates
+        self.radius * self.radius
+    }
+
+    fn min(&self) -> SVector<T, DIM> {
+        self.center - SVector::repeat(self.radius)
+    }
+
+    fn max(&self) -> SVector<T, DIM> {
+        self.center + SVector::repeat(self.radius)
+    }
+
+    fn size(&self) -> SVector<T, DIM> {
+        SVector::repeat(self.radius * T::two())
+    }
+
+    fn half_size(&self) -> SVector<T, DIM> {
+        SVector::repeat(self.radius)
+    }
+
+    fn distance_to(&self, point: &SVector<T, DIM>) -> T {
+        ((point - self.center).norm() - self.radius).max(T::zero())
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> DistanceTo<T, DIM> for Sphere<T, DIM> {}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for Sphere<T, 3> {
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        // sphere-OBB intersections are already implemented for the OBB struct. Use that
+        // implementation here to avoid duplications.
+        other.intersects(self)
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BVIntersector<T, Sphere<T, DIM>, DIM> for Sphere<T, DIM> {
+    fn intersects(&self, other: &Sphere<T, DIM>) -> bool {
+        (other.center - self.center).norm() <= self.radius + other.radius
+    }
+}
+
+impl<T: BaseFloat, const DIM: usize> BVIntersector<T, AABB<T, DIM>, DIM> for Sphere<T, DIM> {
+    fn intersects(&self, other: &AABB<T, DIM>) -> bool {
+        other.distance_to(&self.center) <= self.radius
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Capsule<T>, 3> for Sphere<T, 3> {
+    fn intersects(&self, other: &Capsule<T>) -> bool {
+        other.intersects(self)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{SVector, Vector3};
+    use crate::volume::sphere::Sphere;
+
+    #[test]
+    fn from_points() {
+        // pseudo random points on and inside of the unit sphere around (1, 2, 3)
+        let mut seed = 7u64;
+        let mut random = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+        };
+        let center = Vector3::new(1.0, 2.0, 3.0);
+        let points: Vec<_> = (0..500)
+            .map(|i| {
+                let dir = Vector3::new(random(), random(), random()).normalize();
+                let radius = if i % 2 == 0 { 1.0 } else { random().abs() };
+                center + dir * radius
+            })
+            .collect();
+
+        let sphere = Sphere::from_points(&points);
+        assert!(points.iter().all(|p| (p - sphere.center).norm() <= sphere.radius + 1e-12));
+        // the minimal enclosing sphere is the unit sphere, up to the sampling of its surface
+        assert!(sphere.radius < 1.1);
+        assert!((sphere.center - center).norm() < 0.1);
+
+        // two dimensions
+        let square = [
+            SVector::<f64, 2>::new(0.0, 0.0),
+            SVector::<f64, 2>::new(2.0, 0.0),
+            SVector::<f64, 2>::new(0.0, 2.0),
+            SVector::<f64, 2>::new(2.0, 2.0),
+        ];
+        let circle = Sphere::from_points(&square);
+        assert!((circle.center - SVector::<f64, 2>::new(1.0, 1.0)).norm() < 1e-12);
+        assert!((circle.radius - 2.0f64.sqrt()).abs() < 1e-12);
+
+        let empty = Sphere::<f64, 3>::from_points(&[]);
+        assert_eq!(empty.radius, 0.0);
+    }
+}
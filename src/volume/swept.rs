@@ -0,0 +1,186 @@
+use nalgebra::Vector3;
+use num::Signed;
+use crate::helper::BaseFloat;
+use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::aabb::AABB;
+use crate::volume::capsule::Capsule;
+use crate::volume::oriented::OBB;
+use crate::volume::shape::ShapeVolume;
+use crate::volume::sphere::Sphere;
+
+/// Volume that an OBB covers while it moves along `translation`, i.e. all points that the box
+/// touches during the motion. This is used as a broad phase query for moving objects, like a
+/// character that should know what it will hit during the next step.
+///
+/// The query does not tell where along the motion an object is hit; this is left to the narrow
+/// phase.
+pub struct SweptOBB<T> {
+    pub obb: OBB<T>,
+    pub translation: Vector3<T>,
+}
+
+impl<T: BaseFloat> SweptOBB<T> {
+    pub fn new(obb: OBB<T>, translation: Vector3<T>) -> Self {
+        SweptOBB { obb, translation }
+    }
+
+    /// Returns the axes of the box in the laboratory frame, scaled by the half sizes of the box.
+    fn half_axes(obb: &OBB<T>) -> [Vector3<T>; 3] {
+        let half_size = obb.world_half_size();
+        [
+            obb.transform.right() * half_size.x,
+            obb.transform.up() * half_size.y,
+            obb.transform.forward() * half_size.z,
+        ]
+    }
+
+    /// Returns the radius of the projection of the box with the specified half axes onto `axis`.
+    fn radius(half_axes: &[Vector3<T>; 3], axis: &Vector3<T>) -> T {
+        half_axes.iter().fold(T::zero(), |sum, a| sum + <T as Signed>::abs(&a.dot(axis)))
+    }
+}
+
+impl<T: BaseFloat> BoundingVolume<T, 3> for SweptOBB<T> {
+    fn center(&self) -> Vector3<T> {
+        self.obb.center() + self.translation * T::half()
+    }
+
+    fn area(&self) -> T {
+        let size = self.size();
+        size.x * size.y + size.y * size.z + size.z * size.x
+    }
+
+    fn min(&self) -> Vector3<T> {
+        self.obb.min() + self.translation.inf(&Vector3::zeros())
+    }
+
+    fn max(&self) -> Vector3<T> {
+        self.obb.max() + self.translation.sup(&Vector3::zeros())
+    }
+
+    fn size(&self) -> Vector3<T> {
+        self.max() - self.min()
+    }
+
+    fn half_size(&self) -> Vector3<T> {
+        self.size() * T::half()
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, AABB<T, 3>, 3> for SweptOBB<T> {
+    /// Conservative test, which checks the AABB that wraps the box at the start and at the end of
+    /// the motion. This keeps the test cheap for the inner nodes of acceleration structures.
+    fn intersects(&self, other: &AABB<T, 3>) -> bool {
+        AABB { min: self.min(), max: self.max() }.intersects(other)
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, OBB<T>, 3> for SweptOBB<T> {
+    /// Separating axis test between the swept box and the `other` box.
+    ///
+    /// The swept box is a convex polytope, whose faces are the faces of the box and the faces
+    /// spanned by the edges of the box and the translation. Projected onto an axis, it covers the
+    /// projection of the box, grown by the projection of the translation. Besides the face axes of
+    /// both boxes and the cross products of their edges, the cross products of the translation
+    /// with the edges of both boxes are therefore tested as well. Cross products of (nearly)
+    /// parallel edges are skipped, which may report separated volumes as intersecting.
+    fn intersects(&self, other: &OBB<T>) -> bool {
+        let a = Self::half_axes(&self.obb);
+        let b = Self::half_axes(other);
+        let rel = other.center() - self.center();
+        let half_translation = self.translation * T::half();
+        let tolerance = T::default_epsilon().sqrt();
+
+        let edges = a.iter().chain(b.iter());
+        let mut axes = edges.clone().copied()
+            .chain(edges.map(|edge| self.translation.cross(edge)))
+            .chain(a.iter().flat_map(|ea| b.iter().map(move |eb| ea.cross(eb))));
+
+        !axes.any(|axis| {
+            let len = axis.norm();
+            if len <= tolerance {
+                return false;
+            }
+            let axis = axis / len;
+            let r = Self::radius(&a, &axis) + Self::radius(&b, &axis)
+                + <T as Signed>::abs(&half_translation.dot(&axis));
+            <T as Signed>::abs(&rel.dot(&axis)) > r
+        })
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Sphere<T, 3>, 3> for SweptOBB<T> {
+    /// Exact test: relative to the box, the sphere moves along the negated translation, so the
+    /// swept box touches the sphere if the box touches the capsule that the sphere sweeps.
+    fn intersects(&self, other: &Sphere<T, 3>) -> bool {
+        Capsule::new(other.center, other.center - self.translation, other.radius).intersects(&self.obb)
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, Capsule<T>, 3> for SweptOBB<T> {
+    /// Conservative test against the axis aligned box that wraps the capsule.
+    fn intersects(&self, other: &Capsule<T>) -> bool {
+        self.intersects(&OBB::from(AABB { min: other.min(), max: other.max() }))
+    }
+}
+
+impl<T: BaseFloat> BVIntersector<T, ShapeVolume<T>, 3> for SweptOBB<T> {
+    fn intersects(&self, other: &ShapeVolume<T>) -> bool {
+        match other {
+            ShapeVolume::Box(obb) => self.intersects(obb),
+            ShapeVolume::Sphere(sphere) => self.intersects(sphere),
+            ShapeVolume::Capsule(capsule) => self.intersects(capsule),
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use crate::engine::PhysicsEngine;
+    use crate::test_util::{cube_entity, unit_box};
+    use crate::volume::aabb::AABB;
+    use crate::volume::BVIntersector;
+    use crate::volume::sphere::Sphere;
+    use crate::volume::swept::SweptOBB;
+
+    #[test]
+    fn intersects() {
+        let swept = SweptOBB::new(unit_box(Vector3::zeros(), UnitQuaternion::identity()), Vector3::new(4.0, 4.0, 0.0));
+        let eighth = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_4);
+
+        // boxes along the path are hit, unlike boxes next to the end points
+        assert!(swept.intersects(&unit_box(Vector3::new(2.0, 2.0, 0.0), eighth)));
+        assert!(swept.intersects(&unit_box(Vector3::new(4.5, 4.5, 0.0), UnitQuaternion::identity())));
+        assert!(!swept.intersects(&unit_box(Vector3::new(-1.5, 0.0, 0.0), UnitQuaternion::identity())));
+        assert!(!swept.intersects(&unit_box(Vector3::new(0.0, 0.0, 1.5), eighth)));
+        // the box lies within the bounds of the motion, but beside the diagonal path
+        let beside = unit_box(Vector3::new(3.5, 0.5, 0.0), eighth);
+        assert!(swept.intersects(&AABB { min: Vector3::new(3.0, 0.0, -0.5), max: Vector3::new(4.0, 1.0, 0.5) }));
+        assert!(!swept.intersects(&beside));
+
+        assert!(swept.intersects(&Sphere::new(Vector3::new(2.0, 2.8, 0.0), 0.5)));
+        assert!(!swept.intersects(&Sphere::new(Vector3::new(3.5, 0.5, 0.0), 0.5)));
+
+        // without motion, the swept box behaves like the box itself
+        let resting = SweptOBB::new(unit_box(Vector3::zeros(), eighth), Vector3::zeros());
+        assert!(resting.intersects(&unit_box(Vector3::new(1.1, 0.0, 0.0), UnitQuaternion::identity())));
+        assert!(!resting.intersects(&unit_box(Vector3::new(1.3, 0.0, 0.0), UnitQuaternion::identity())));
+    }
+
+    #[test]
+    fn query_world() {
+        let mut engine = PhysicsEngine::<f64>::new();
+        for (i, x) in [2.0, 6.0, 12.0].into_iter().enumerate() {
+            engine.spawn(cube_entity(i, Vector3::new(x, 0.0, 0.0)));
+        }
+        engine.build();
+
+        let swept = SweptOBB::new(unit_box(Vector3::zeros(), UnitQuaternion::identity()), Vector3::new(8.0, 0.0, 0.0));
+        let mut hits: Vec<_> = engine.worlds[&0].intersect(&swept, 0).iter().map(|e| e.is.state.pos.x).collect();
+        hits.sort_by(f64::total_cmp);
+        assert_eq!(hits, [2.0, 6.0]);
+    }
+}
@@ -1,17 +1,21 @@
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Index, IndexMut};
-use nalgebra::{SVector};
+use std::ops::{Index, IndexMut, Range};
+use nalgebra::SVector;
+use crate::collision::intersection::{Ray, RayCast};
 use crate::helper::BaseFloat;
 use crate::volume::aabb::AABB;
 use crate::volume::bvh::VecPool;
-use crate::volume::{BoundingVolume, BVIntersector};
+use crate::volume::{BoundingVolume, BVIntersector, DistanceTo, IntersectIter, IntersectTraversal};
 
 #[derive(Clone, Debug)]
 pub struct TLASNode<T: BaseFloat, const DIM: usize> {
     aabb: AABB<T, DIM>,
     left_right: u32,
     blas: u32,
+    /// Pool index of the parent node, or zero for the topmost node of the tree.
+    parent: u32,
 }
 
 impl<T: BaseFloat, const DIM: usize> TLASNode<T, DIM> {
@@ -22,6 +26,7 @@ impl<T: BaseFloat, const DIM: usize> TLASNode<T, DIM> {
             aabb: AABB::new(),
             left_right: 0,
             blas: 0,
+            parent: 0,
         }
     }
 
@@ -30,6 +35,7 @@ impl<T: BaseFloat, const DIM: usize> TLASNode<T, DIM> {
         self.aabb = other.aabb.clone();
         self.left_right = other.left_right.clone();
         self.blas = other.blas.clone();
+        self.parent = other.parent;
     }
 
     pub fn aabb(&self) -> &AABB<T, DIM> {
@@ -50,8 +56,19 @@ impl<T: BaseFloat, const DIM: usize> TLASNode<T, DIM> {
     pub fn get_right_child(&self) -> u16 {
         (&self.left_right & 0xFFFF) as u16
     }
+
+    /// Returns true, if the node is a leaf node that no longer references a BLAS element, as its
+    /// element was removed from the TLAS.
+    pub fn is_removed(&self) -> bool {
+        self.is_leaf() && self.blas == REMOVED_BLAS
+    }
 }
 
+/// BLAS index of leaf nodes, whose element was removed from the TLAS.
+const REMOVED_BLAS: u32 = u32::MAX;
+/// Node index of BLAS elements that are not referenced by any leaf node.
+const NO_LEAF: u32 = u32::MAX;
+
 
 
 pub trait TLASPool<T: Sized> : Index<usize, Output=T> + IndexMut<usize, Output=T> {
@@ -62,6 +79,10 @@ pub trait TLASPool<T: Sized> : Index<usize, Output=T> + IndexMut<usize, Output=T
     /// Pops and returns the last element of the pool. If the pool is empty, `None` is returned.
     fn pop(&mut self) -> Option<T>;
 
+    /// Removes the element at index `idx` from the pool and returns it. The last element of the
+    /// pool takes the place of the removed element.
+    fn swap_remove(&mut self, idx: usize) -> T;
+
     /// Returns the amount of elements that is currently storged in the pool.
     fn size(&self) -> usize;
 
@@ -71,6 +92,11 @@ pub trait TLASPool<T: Sized> : Index<usize, Output=T> + IndexMut<usize, Output=T
     /// Trims the pool to the specified target length.
     fn trim(&mut self, target_len: usize);
 
+    /// Removes all elements from the pool, while keeping the capacity of the pool.
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
     /// Returns a shared reference to the first element in the pool. If the pool is emtpy, `None` is
     /// returned.
     fn front(&self) -> Option<&T>;
@@ -94,6 +120,13 @@ pub trait TLASElement<T: BaseFloat, const DIM: usize> {
     /// Returns an AABB that fully wraps around the TLAS element.
     fn wrap(&self) -> AABB<T, DIM>;
     fn bounding_volume(&self) -> &Self::BV;
+
+    /// Returns true, if the element never moves, like the level geometry of a scene. Static
+    /// elements are kept in a separate subtree of the TLAS, which is neither refit nor rebuilt
+    /// unless static elements are added or removed (see `TLAS::build`).
+    fn is_static(&self) -> bool {
+        false
+    }
 }
 
 
@@ -106,6 +139,10 @@ impl<T: Sized> TLASPool<T> for VecPool<T> {
         self.vec.pop()
     }
 
+    fn swap_remove(&mut self, idx: usize) -> T {
+        self.vec.swap_remove(idx)
+    }
+
     fn size(&self) -> usize {
         self.vec.len()
     }
@@ -114,12 +151,12 @@ impl<T: Sized> TLASPool<T> for VecPool<T> {
         self.vec.capacity()
     }
 
-    fn trim(&mut self, target_len: usize) {
-        assert_eq!(target_len, 1);
-
-        let first = self.vec.remove(0);
+    fn clear(&mut self) {
         self.vec.clear();
-        self.vec.push(first);
+    }
+
+    fn trim(&mut self, target_len: usize) {
+        self.vec.truncate(target_len);
     }
 
     fn front(&self) -> Option<&T> {
@@ -145,9 +182,25 @@ impl<T: Sized> TLASPool<T> for VecPool<T> {
 /// that contains instances of `BoundingVolume`. The tree structure can be traversed in O(log n) to
 /// query for elements that intersect an appropriate intersector instance. This is useful for
 /// rending (specially but not limited to ray tracing), culling and collision detection.
+///
+/// Static elements (see `TLASElement::is_static`) and dynamic elements are clustered into two
+/// separate subtrees, which are joined by the topmost node of the tree. The static subtree is
+/// stored at the front of the node pool and is kept by `build` and skipped by `refit`, such that
+/// only the dynamic elements have to be processed every frame.
 pub struct TLAS<T: BaseFloat, B: Sized, NodePool: TLASPool<TLASNode<T, DIM>>, BlasPool: TLASPool<B>, const DIM: usize> {
     nodes: NodePool,
     blas: BlasPool,
+    /// Maps the index of each BLAS element to the pool index of its leaf node.
+    leaves: Vec<u32>,
+    dirty: bool,
+    /// Pool index of the first node after the static subtree. The nodes of the static subtree lie
+    /// between the root node and this index.
+    static_end: usize,
+    /// Pool index of the topmost node of the static subtree, if there are static elements.
+    static_top: Option<usize>,
+    /// True, if static elements were removed since the last build, such that the static subtree
+    /// has to be rebuilt.
+    static_dirty: bool,
 
     _t: PhantomData<T>,
     _b: PhantomData<B>,
@@ -161,13 +214,19 @@ where T: BaseFloat,
         let mut tlas = TLAS {
             nodes: VecPool::with_capacity(cap * 2),
             blas: VecPool::with_capacity(cap),
+            leaves: Vec::with_capacity(cap),
+            dirty: false,
+            static_end: 1,
+            static_top: None,
+            static_dirty: false,
             _t: PhantomData::default(),
             _b: PhantomData::default(),
         };
         tlas.nodes.push(TLASNode {
             aabb: AABB::new(),
-            blas: 0,
-            left_right: 0
+            blas: REMOVED_BLAS,
+            left_right: 0,
+            parent: 0,
         });
 
         tlas
@@ -233,12 +292,142 @@ where T: BaseFloat,
         &mut self.blas
     }
 
+    /// Returns true, if the tree structure is outdated and should be rebuild using `build`. This
+    /// is the case after BLAS elements have been removed from the TLAS, or after an element that
+    /// was added since the last build has been updated with `update_leaf`.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the bounds of all elements in the TLAS. For an up to date tree, these are the bounds
+    /// of the root node. If the tree is dirty, or elements were pushed since the last build, the
+    /// bounds are recomputed from the elements instead. An empty TLAS yields the empty box of
+    /// `AABB::new()`.
+    pub fn scene_bounds(&self) -> AABB<T, DIM> {
+        if !self.dirty && self.leaves.len() == self.blas.size() {
+            return self.nodes[0].aabb;
+        }
+        let mut aabb = AABB::new();
+        for i in 0..self.blas.size() {
+            aabb.grow_other(&self.blas[i].wrap());
+        }
+        aabb
+    }
+
+    /// Removes all BLAS elements and resets the tree to an empty root node. The capacity of the
+    /// node and BLAS pools is kept, such that the TLAS can be filled again without reallocating.
+    pub fn clear(&mut self) {
+        self.blas.clear();
+        // the tree over no elements is just the empty root
+        self.static_dirty = true;
+        self.build();
+    }
+
+    /// Removes the BLAS element at `blas_index` from the TLAS and returns it.
+    ///
+    /// The element is removed from the BLAS pool by swapping in the last element of the pool, so
+    /// the index of the last element changes to `blas_index`. Since the agglomerative clustering
+    /// used by `build` does not support incremental removal, the tree is only patched: the leaf of
+    /// the removed element is marked as removed and is skipped by all queries, while the bounds of
+    /// its ancestors are left as they are. The TLAS is flagged as dirty, so that it can be rebuild
+    /// with `build` at a convenient time.
+    pub fn remove_blas(&mut self, blas_index: usize) -> B {
+        let last = self.blas.size() - 1;
+        let removed = self.blas.swap_remove(blas_index);
+        if removed.is_static() {
+            self.static_dirty = true;
+        }
+
+        if let Some(leaf) = self.leaf_node(blas_index) {
+            let node = &mut self.nodes[leaf];
+            node.blas = REMOVED_BLAS;
+            node.aabb.reset();
+        }
+        if last != blas_index {
+            let moved = self.leaf_node(last);
+            if let Some(leaf) = moved {
+                self.nodes[leaf].blas = blas_index as u32;
+            }
+            if blas_index < self.leaves.len() {
+                self.leaves[blas_index] = moved.map_or(NO_LEAF, |leaf| leaf as u32);
+            }
+        }
+        self.leaves.truncate(last);
+        self.sync_root();
+        self.dirty = true;
+        removed
+    }
+
+    /// Updates the leaf of the BLAS element at `blas_index` after the element has changed, for
+    /// instance after it was moved. The element is wrapped again and only the ancestors of its leaf
+    /// are refit, which takes O(log n) for a balanced tree, instead of O(n) for `refit`. As with
+    /// `refit`, the structure of the tree is not changed, so the tree may become less efficient if
+    /// the element moves far away from its original position.
+    ///
+    /// Elements that were pushed to the BLAS pool since the last `build` are not part of the tree
+    /// yet. For those, the TLAS is only flagged as dirty.
+    pub fn update_leaf(&mut self, blas_index: usize) {
+        let Some(leaf) = self.leaf_node(blas_index) else {
+            self.dirty = true;
+            return;
+        };
+        self.nodes[leaf].aabb = self.blas[blas_index].wrap();
+
+        let mut node_idx = self.nodes[leaf].parent as usize;
+        while node_idx != 0 {
+            let node = &self.nodes[node_idx];
+            let mut aabb = AABB::new();
+            aabb.adjust(
+                &self.nodes[node.get_left_child() as usize].aabb,
+                &self.nodes[node.get_right_child() as usize].aabb,
+            );
+            self.nodes[node_idx].aabb = aabb;
+            node_idx = self.nodes[node_idx].parent as usize;
+        }
+        self.sync_root();
+    }
+
+    /// Returns the pool index of the leaf node of the BLAS element at `blas_index`, if the element
+    /// is part of the tree.
+    fn leaf_node(&self, blas_index: usize) -> Option<usize> {
+        match self.leaves.get(blas_index) {
+            Some(&leaf) if leaf != NO_LEAF => Some(leaf as usize),
+            _ => None,
+        }
+    }
+
+    /// Returns true, if the BLAS element at `blas_index` has a leaf in the static subtree.
+    fn is_static_leaf(&self, blas_index: usize) -> bool {
+        self.leaf_node(blas_index).is_some_and(|leaf| leaf < self.static_end)
+    }
+
+    /// Returns true, if the node at `node_idx` is the topmost node of the static subtree, or the
+    /// root node while the tree only consists of the static subtree.
+    fn is_static_top(&self, node_idx: usize) -> bool {
+        self.static_top.is_some_and(|top| node_idx == top || (node_idx == 0 && top == self.nodes.size() - 1))
+    }
+
+    /// Copies the topmost node of the tree into the root node at index zero. The topmost node is
+    /// always the last node that is created by `build`.
+    fn sync_root(&mut self) {
+        let last = self.nodes.size() - 1;
+        if last > 0 {
+            let top = self.nodes[last].clone();
+            self.nodes[0].copy_from(&top);
+        }
+    }
+
+    /// Recomputes the bounds of all nodes from the current bounds of the BLAS elements, without
+    /// changing the structure of the tree. The static subtree is skipped, as its elements never
+    /// move; static elements that were moved anyway can be refit with `update_leaf`.
     pub fn refit(&mut self) {
         // since a parent node is always further to the back of the tree, we can loop through here
         // front-to-back
-        for i in 1..self.nodes.size() {
+        for i in self.static_end..self.nodes.size() {
             let node = &self.nodes[i];
-            if node.is_leaf() {
+            if node.is_removed() {
+                continue;
+            } else if node.is_leaf() {
                 self.nodes[i].aabb = self.blas[node.blas as usize].wrap();
             } else {
                 let left_child = &self.nodes[node.get_left_child() as usize].aabb;
@@ -249,72 +438,247 @@ where T: BaseFloat,
                 self.nodes[i].aabb = aabb;
             }
         }
+        self.sync_root();
     }
 
     /// Rebuilds the TLAS bottom up.
+    ///
+    /// The tree is built through agglomerative clustering. To avoid searching for the best
+    /// clustering partner of every node over and over again, the best match of each node is cached
+    /// and only recomputed for the nodes that are affected by a merge.
+    ///
+    /// Static and dynamic elements are clustered separately. The static subtree is only rebuilt if
+    /// static elements were pushed or removed, or if an element changed its classification since
+    /// the last build; otherwise only the dynamic subtree is rebuilt. Both subtrees are joined by a
+    /// common topmost node.
+    ///
+    /// The build is deterministic and does not depend on the order in which the elements were
+    /// pushed to the BLAS pool: the leaves are sorted by their bounds first (see `build_leaves`),
+    /// and ties between equally cheap clustering partners are broken in favour of the partner that
+    /// comes first. The same set of bounding volumes therefore always yields the same tree and the
+    /// same traversal order. Only elements with exactly the same bounds keep their push order.
     pub fn build(&mut self) {
-        let mut node_idx = Vec::<usize>::with_capacity(self.blas.size());
-        let mut node_indices = self.blas.size();
+        self.build_with(Self::cluster);
+    }
 
-        // set leaf nodes
-        self.nodes.trim(1);
-        for i in 0..self.blas.size() {
-            node_idx.push(self.nodes.size());
-            self.nodes.push(TLASNode {
-                aabb: self.blas[i].wrap(),
-                blas: i as u32,
-                left_right: 0,
-            });
+    /// Rebuilds the TLAS bottom up, without caching the best clustering partners. This is the
+    /// reference implementation for `build`, which has to produce the exact same tree.
+    #[cfg(test)]
+    fn build_uncached(&mut self) {
+        self.build_with(Self::cluster_uncached);
+    }
+
+    /// Rebuilds the dynamic subtree, and the static subtree if its elements changed, with `cluster`
+    /// and joins both subtrees. If the TLAS is empty, the tree only consists of a root node that
+    /// does not reference any element.
+    fn build_with(&mut self, cluster: fn(&mut Self, Vec<usize>) -> usize) {
+        self.dirty = false;
+        let rebuild_static = self.static_dirty
+            || (0..self.blas.size()).any(|i| self.blas[i].is_static() != self.is_static_leaf(i));
+        if rebuild_static {
+            self.static_dirty = false;
+            self.nodes.trim(1);
+            self.leaves.clear();
+            self.leaves.resize(self.blas.size(), NO_LEAF);
+            self.static_top = self.build_leaves(true).map(|leaves| cluster(self, leaves));
+            self.static_end = self.nodes.size();
+        } else {
+            // keep the static subtree, together with the leaves of the static elements
+            self.nodes.trim(self.static_end);
+            let static_end = self.static_end as u32;
+            for leaf in self.leaves.iter_mut().filter(|leaf| **leaf >= static_end) {
+                *leaf = NO_LEAF;
+            }
+            self.leaves.resize(self.blas.size(), NO_LEAF);
         }
+        let dynamic_top = self.build_leaves(false).map(|leaves| cluster(self, leaves));
 
-        // eprintln!("init node len: {}", self.nodes.size());
+        match (self.static_top, dynamic_top) {
+            (Some(static_top), Some(dynamic_top)) => {
+                let parent = self.nodes.size();
+                let mut aabb = AABB::new();
+                aabb.adjust(&self.nodes[static_top].aabb, &self.nodes[dynamic_top].aabb);
+                self.nodes[static_top].parent = parent as u32;
+                self.nodes[dynamic_top].parent = parent as u32;
+                self.nodes.push(TLASNode {
+                    left_right: dynamic_top as u32 + ((static_top as u32) << 16),
+                    aabb,
+                    blas: 0,
+                    parent: 0,
+                });
+            },
+            (Some(static_top), None) => self.nodes[static_top].parent = 0,
+            (None, Some(_)) => {},
+            (None, None) => {
+                self.nodes[0] = TLASNode {
+                    aabb: AABB::new(),
+                    blas: REMOVED_BLAS,
+                    left_right: 0,
+                    parent: 0,
+                };
+                return;
+            },
+        }
+
+        // set root node
+        self.sync_root();
+    }
+
+    /// Clusters the leaves with the pool indices `node_idx` into a subtree and returns the pool
+    /// index of the topmost node of the subtree.
+    fn cluster(&mut self, mut node_idx: Vec<usize>) -> usize {
+        let mut node_indices = node_idx.len();
+
+        // best clustering partner and the respective cost for each entry in `node_idx`
+        let mut best = (0..node_indices)
+            .map(|i| self.find_best_match(&node_idx, node_indices, i as i32))
+            .collect::<Vec<_>>();
 
         // use agglomerative clustering to build the TLAS (bottom-to-top)
         let mut a = 0_i32;
-        let mut b = self.find_best_match(&node_idx, node_indices, a);
+        let mut b = best[0].0;
         while node_indices > 1 {
-            let c = self.find_best_match(&node_idx, node_indices, b);
+            let c = best[b as usize].0;
             if a == c {
-                let node_idx_a = node_idx[a as usize];
-                let node_idx_b = node_idx[b as usize];
+                self.merge(&mut node_idx, node_indices, a, b);
+                let last = node_indices as i32 - 1;
+                node_indices -= 1;
 
-                let node_a = &self.nodes[node_idx_a];
-                let node_b = &self.nodes[node_idx_b];
-                node_idx[a as usize] = self.nodes.size();
-                node_idx[b as usize] = node_idx[node_indices - 1];
+                // The entries whose content changed by the merge. If `a` was the last entry, the
+                // merged node ends up in `b`, otherwise it is in `a` and the last entry moved to `b`.
+                let changed: &[i32] = if a == last {
+                    &[b]
+                } else if b == last {
+                    &[a]
+                } else {
+                    &[a, b]
+                };
+                for i in 0..node_indices {
+                    let match_i = best[i].0;
+                    if changed.contains(&(i as i32)) || match_i == a || match_i == b {
+                        // the node itself or its partner changed
+                        best[i] = self.find_best_match(&node_idx, node_indices, i as i32);
+                    } else if match_i == last {
+                        // the partner only moved; as it was the first entry with the lowest
+                        // cost, it still is the unique best match
+                        best[i].0 = b;
+                    } else {
+                        // Merged nodes are never cheaper partners than their children and the
+                        // moved node keeps its cost. They may only tie with the cached match, in
+                        // which case the entry that comes first is preferred.
+                        for &p in changed {
+                            let cost_p = self.cluster_cost(node_idx[i], node_idx[p as usize]);
+                            if cost_p < best[i].1 || (cost_p == best[i].1 && p < best[i].0) {
+                                best[i] = (p, cost_p);
+                            }
+                        }
+                    }
+                }
 
+                b = if (a as usize) < node_indices {
+                    best[a as usize].0
+                } else {
+                    // `a` no longer lies within the active entries; it still refers to the merged
+                    // node, which is not covered by the cache
+                    self.find_best_match(&node_idx, node_indices, a).0
+                };
+            } else {
+                a = b;
+                b = c;
+            }
+        }
+        node_idx[0]
+    }
 
-                let mut aabb = AABB::new();
-                aabb.adjust(&node_a.aabb, &node_b.aabb);
-                self.nodes.push(TLASNode {
-                    left_right: node_idx_a as u32 + ((node_idx_b as u32) << 16),
-                    aabb,
-                    blas: 0
-                });
+    /// Clusters the leaves with the pool indices `node_idx` into a subtree like `cluster`, without
+    /// caching the best clustering partners.
+    #[cfg(test)]
+    fn cluster_uncached(&mut self, mut node_idx: Vec<usize>) -> usize {
+        let mut node_indices = node_idx.len();
 
+        let mut a = 0_i32;
+        let mut b = self.find_best_match(&node_idx, node_indices, a).0;
+        while node_indices > 1 {
+            let c = self.find_best_match(&node_idx, node_indices, b).0;
+            if a == c {
+                self.merge(&mut node_idx, node_indices, a, b);
                 node_indices -= 1;
-                b = self.find_best_match(&node_idx, node_indices, a);
+                b = self.find_best_match(&node_idx, node_indices, a).0;
             } else {
                 a = b;
                 b = c;
             }
         }
-        // eprintln!("nodes.len() = {}", self.nodes.size());
+        node_idx[0]
+    }
 
-        // set root node
-        self.nodes[0] = self.nodes[node_idx[a as usize]].clone();
-        // eprintln!("nodes:");
-        // for i in 0..self.nodes.size() {
-        //     eprintln!("  [{}]: {:?}     >>   left={},    >>   right={}",
-        //               i, self.nodes[i],
-        //               self.nodes[i].get_left_child(),
-        //               self.nodes[i].get_right_child());
-        // }
+    /// Creates a leaf node for every BLAS element that is static or dynamic, as specified by
+    /// `is_static`, and maps the elements to their leaves. Returns the pool indices of the leaf
+    /// nodes, or `None` if there are no such elements.
+    ///
+    /// The leaves are created in the lexicographic order of the min and max corners of their
+    /// bounds, such that the order of the leaves does not depend on the order of the BLAS pool.
+    /// Coordinates that are NaN are sorted after all others, and elements with identical bounds
+    /// stay in the order of the pool.
+    fn build_leaves(&mut self, is_static: bool) -> Option<Vec<usize>> {
+        let mut bounds = (0..self.blas.size())
+            .filter(|i| self.blas[*i].is_static() == is_static)
+            .map(|i| (i, self.blas[i].wrap()))
+            .collect::<Vec<_>>();
+        if bounds.is_empty() {
+            return None;
+        }
+        bounds.sort_by(|(i, a), (j, b)| {
+            a.min.iter().chain(a.max.iter())
+                .zip(b.min.iter().chain(b.max.iter()))
+                .map(|(x, y)| cmp_nan_last(x, y))
+                .find(|ord| ord.is_ne())
+                .unwrap_or_else(|| i.cmp(j))
+        });
+
+        let mut node_idx = Vec::<usize>::with_capacity(bounds.len());
+        for (i, aabb) in bounds {
+            node_idx.push(self.nodes.size());
+            self.leaves[i] = self.nodes.size() as u32;
+            self.nodes.push(TLASNode {
+                aabb,
+                blas: i as u32,
+                left_right: 0,
+                parent: 0,
+            });
+        }
+        Some(node_idx)
+    }
+
+    /// Merges the nodes referenced by the entries `a` and `b` in `list` into a new parent node.
+    /// The parent takes the place of `a` and the last of the `n` entries is moved into `b`.
+    fn merge(&mut self, list: &mut [usize], n: usize, a: i32, b: i32) {
+        let node_idx_a = list[a as usize];
+        let node_idx_b = list[b as usize];
+
+        let node_a = &self.nodes[node_idx_a];
+        let node_b = &self.nodes[node_idx_b];
+        let parent = self.nodes.size();
+        list[a as usize] = parent;
+        list[b as usize] = list[n - 1];
+
+        let mut aabb = AABB::new();
+        aabb.adjust(&node_a.aabb, &node_b.aabb);
+        self.nodes[node_idx_a].parent = parent as u32;
+        self.nodes[node_idx_b].parent = parent as u32;
+        self.nodes.push(TLASNode {
+            left_right: node_idx_a as u32 + ((node_idx_b as u32) << 16),
+            aabb,
+            blas: 0,
+            parent: 0,
+        });
     }
 
     /// Finds the most cost-effective clustering partner for the node with id `list[a]`. For this,
-    /// the `n` first entries in `list` are considered.
-    fn find_best_match(&self, list: &Vec<usize>, n: usize, a: i32) -> i32 {
+    /// the `n` first entries in `list` are considered. Returns the entry of the partner together
+    /// with the cost of clustering both nodes. If several partners are equally cheap, the one with
+    /// the lowest entry wins.
+    fn find_best_match(&self, list: &[usize], n: usize, a: i32) -> (i32, T) {
         let mut smallest = T::MAX;
         let mut best_b = -1_i32;
 
@@ -323,36 +687,114 @@ where T: BaseFloat,
                 continue;
             }
 
-            let a_node = &self.nodes[list[a as usize]];
-            let b_node = &self.nodes[list[b]];
-
-            // calc wrapping node sizes
-            let mut size = SVector::<T, DIM>::zeros();
-            for i in 0..DIM {
-                size[i] = T::max(a_node.aabb.max[i], b_node.aabb.max[i])
-                    - T::min(a_node.aabb.min[i], b_node.aabb.min[i]);
+            let surface_area = self.cluster_cost(list[a as usize], list[b]);
+            if surface_area < smallest {
+                smallest = surface_area;
+                best_b = b as i32;
             }
+        }
+        (best_b, smallest)
+    }
 
-            // calc surface area estimate for cost analysis
-            let mut surface_area = T::zero();
-            for i in 0..DIM {
-                surface_area += size[i] * size[(i + 1) % DIM];
-            }
+    /// Calculates the cost of clustering the nodes with the pool indices `a` and `b`, which is the
+    /// SAH cost estimate of the AABB that wraps both nodes (see `AABB::sah_cost`).
+    fn cluster_cost(&self, a: usize, b: usize) -> T {
+        let mut aabb = AABB::new();
+        aabb.adjust(&self.nodes[a].aabb, &self.nodes[b].aabb);
+        aabb.sah_cost()
+    }
 
+    /// Checks the invariants of the tree and returns a description of the first violation that is
+    /// found, like `BVH::validate`.
+    ///
+    /// The checks are that the root node is a copy of the topmost node, that every interior node
+    /// references children that were created before it and point back to it as their parent, that
+    /// the bounds of every node contain the bounds of its children, and that the leaves and the
+    /// leaf map reference each other consistently. Elements that were pushed since the last build
+    /// are not part of the tree yet and are not checked.
+    pub fn validate(&self) -> Result<(), String> {
+        let size = self.nodes.size();
+        if size == 0 {
+            return Err(String::from("the node pool does not contain a root node"));
+        }
+        if self.leaves.len() > self.blas.size() {
+            return Err(format!(
+                "the leaf map has {} entries, but there are only {} BLAS elements",
+                self.leaves.len(), self.blas.size()
+            ));
+        }
 
-            if surface_area < smallest {
-                smallest = surface_area;
-                best_b = b as i32;
+        let top = size - 1;
+        let (root, top_node) = (&self.nodes[0], &self.nodes[top]);
+        if root.left_right != top_node.left_right || root.blas != top_node.blas
+            || root.aabb.min != top_node.aabb.min || root.aabb.max != top_node.aabb.max {
+            return Err(format!("the root node is not a copy of the topmost node {top}"));
+        }
+
+        let mut live_leaves = 0usize;
+        let mut stack = if top > 0 { vec![top] } else { Vec::new() };
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.is_removed() {
+                continue;
+            }
+            if node.is_leaf() {
+                let blas = node.blas as usize;
+                if blas >= self.blas.size() {
+                    return Err(format!(
+                        "leaf {node_idx} references BLAS element {blas}, but there are only {} elements",
+                        self.blas.size()
+                    ));
+                }
+                if self.leaf_node(blas) != Some(node_idx) {
+                    return Err(format!("BLAS element {blas} is not mapped to its leaf {node_idx}"));
+                }
+                live_leaves += 1;
+                continue;
+            }
+
+            for child in [node.get_left_child() as usize, node.get_right_child() as usize] {
+                if child == 0 || child >= node_idx {
+                    return Err(format!("node {node_idx} references child {child}, which is out of range"));
+                }
+                if self.nodes[child].parent as usize != node_idx {
+                    return Err(format!("the parent of node {child} is not its parent node {node_idx}"));
+                }
+                if !node.aabb.contains(&self.nodes[child].aabb) {
+                    return Err(format!("node {node_idx} does not contain its child {child}"));
+                }
+                stack.push(child);
             }
         }
-        return best_b;
+
+        let mapped = (0..self.leaves.len()).filter(|i| self.leaf_node(*i).is_some()).count();
+        if live_leaves != mapped {
+            return Err(format!(
+                "the tree has {live_leaves} leaves with elements, but {mapped} elements are mapped to leaves"
+            ));
+        }
+        Ok(())
     }
 
+    /// Returns a `Vec` to references of the BLAS elements whose bounding volumes intersect the
+    /// specified intersector, starting the search at the node with index `node_idx`.
     pub fn intersect<I: BVIntersector<T, B::BV, DIM> + BVIntersector<T, AABB<T, DIM>, DIM>>(
         &self, intersector: &I, node_idx: usize
     ) -> Vec<&B> {
 
         let mut v = Vec::<&B>::with_capacity(64);
+        self.intersect_into(intersector, node_idx, &mut v);
+        v
+    }
+
+    /// Pushes references to the BLAS elements whose bounding volumes intersect the specified
+    /// intersector into `out`, like `intersect`. Elements that are already in `out` are kept.
+    ///
+    /// Unlike `intersect`, this method does not allocate when `out` has enough capacity, such that
+    /// the buffer can be reused for many queries (see `QueryBuffer`).
+    pub fn intersect_into<'a, I: BVIntersector<T, B::BV, DIM> + BVIntersector<T, AABB<T, DIM>, DIM>>(
+        &'a self, intersector: &I, node_idx: usize, out: &mut Vec<&'a B>
+    ) {
 
         let mut node = &self.nodes[node_idx];
         let mut stack = [node; 64];
@@ -360,8 +802,9 @@ where T: BaseFloat,
 
         loop {
             if node.is_leaf() {
-                if intersector.intersects(self.blas[node.blas as usize].bounding_volume()) {
-                    v.push(&self.blas[node.blas as usize]);
+                if !node.is_removed()
+                    && intersector.intersects(self.blas[node.blas as usize].bounding_volume()) {
+                    out.push(&self.blas[node.blas as usize]);
                 }
 
                 if stack_ptr == 0 {
@@ -401,6 +844,833 @@ where T: BaseFloat,
                 }
             }
         }
-        v
+    }
+
+    /// Moves a sphere with the specified `radius` from `origin` along the direction `dir` and
+    /// returns the first BLAS element it touches, together with the distance the sphere travelled
+    /// until the contact. Elements that are farther away than `max_dist` are not found. The
+    /// direction does not need to be normalized.
+    ///
+    /// Nodes are pruned by sweeping the bounding box of the sphere against their bounds, which is
+    /// the same as intersecting a ray with the node AABBs expanded by the radius. The nearer child
+    /// of each node is visited first and nodes that are not closer than the best hit so far are
+    /// skipped. The exact contact with the bounding volumes of the elements is found through
+    /// `DistanceTo::sphere_cast`.
+    pub fn spherecast(
+        &self,
+        origin: &SVector<T, DIM>,
+        dir: &SVector<T, DIM>,
+        radius: T,
+        max_dist: T
+    ) -> Option<(&B, T)>
+    where B::BV: DistanceTo<T, DIM> {
+        let len = dir.norm();
+        if len == T::zero() {
+            return None;
+        }
+        let dir = dir / len;
+        let sphere = AABB {
+            min: origin - SVector::repeat(radius),
+            max: origin + SVector::repeat(radius),
+        };
+        let velocity = dir.scale(max_dist);
+        let entry = |node: usize| sphere.sweep(&velocity, &self.nodes[node].aabb)
+            .map(|fraction| fraction * max_dist);
+
+        let mut best: Option<(usize, T)> = None;
+        let mut stack = Vec::with_capacity(64);
+        if let Some(t) = entry(0) {
+            stack.push((0usize, t));
+        }
+        while let Some((node_idx, t)) = stack.pop() {
+            if best.is_some_and(|(_, best_t)| t >= best_t) {
+                continue;
+            }
+
+            let node = &self.nodes[node_idx];
+            if node.is_removed() {
+                continue;
+            } else if node.is_leaf() {
+                let limit = best.map_or(max_dist, |(_, best_t)| best_t);
+                let hit = self.blas[node.blas as usize].bounding_volume()
+                    .sphere_cast(origin, &dir, radius, limit);
+                if let Some(hit) = hit.filter(|hit| best.is_none_or(|(_, best_t)| *hit < best_t)) {
+                    best = Some((node.blas as usize, hit));
+                }
+            } else {
+                let left = node.get_left_child() as usize;
+                let right = node.get_right_child() as usize;
+                let mut children = [(left, entry(left)), (right, entry(right))];
+                if let (Some(t_left), Some(t_right)) = (children[0].1, children[1].1) {
+                    if t_left < t_right {
+                        // visit the nearer child first
+                        children.swap(0, 1);
+                    }
+                }
+                for (child, t) in children {
+                    if let Some(t) = t {
+                        stack.push((child, t));
+                    }
+                }
+            }
+        }
+        best.map(|(blas, t)| (&self.blas[blas], t))
+    }
+
+    /// Intersects the ray with the BLAS elements and returns the nearest element that is hit, if
+    /// any. The ray is shortened to the nearest hit, whose intersection is stored in the ray by
+    /// the element (see `RayCast`).
+    ///
+    /// Nodes are pruned with a slab test against their bounds. The nearer child of each node is
+    /// visited first, such that the ray is shortened early on and nodes behind the nearest hit so
+    /// far are skipped.
+    pub fn raycast(&self, ray: &mut Ray<T, DIM>) -> Option<&B>
+    where B: RayCast<T, DIM> {
+        let mut best = None;
+        let mut stack = Vec::with_capacity(64);
+        if let Some(t) = ray.entry(&self.nodes[0].aabb) {
+            stack.push((0usize, t));
+        }
+        while let Some((node_idx, t)) = stack.pop() {
+            if t > ray.d {
+                continue;
+            }
+
+            let node = &self.nodes[node_idx];
+            if node.is_removed() {
+                continue;
+            } else if node.is_leaf() {
+                if self.blas[node.blas as usize].raycast(ray) {
+                    best = Some(node.blas as usize);
+                }
+            } else {
+                let left = node.get_left_child() as usize;
+                let right = node.get_right_child() as usize;
+                let mut children = [
+                    (left, ray.entry(&self.nodes[left].aabb)),
+                    (right, ray.entry(&self.nodes[right].aabb)),
+                ];
+                if let (Some(t_left), Some(t_right)) = (children[0].1, children[1].1) {
+                    if t_left < t_right {
+                        // visit the nearer child first
+                        children.swap(0, 1);
+                    }
+                }
+                for (child, t) in children {
+                    if let Some(t) = t {
+                        stack.push((child, t));
+                    }
+                }
+            }
+        }
+        best.map(|blas| &self.blas[blas])
+    }
+
+    /// Returns all pairs of BLAS elements whose bounding volumes intersect each other, as pairs of
+    /// BLAS indices. The tree is traversed once against itself, such that every pair is reported
+    /// exactly once and elements are never paired with themselves. The first index of a pair is
+    /// always the smaller one.
+    ///
+    /// Pairs of two static elements are not reported, as static elements never move relative to
+    /// each other. The static subtree is therefore only traversed against the dynamic subtree.
+    pub fn overlapping_pairs(&self) -> Vec<(usize, usize)>
+    where B::BV: BVIntersector<T, B::BV, DIM> {
+        let mut pairs = Vec::new();
+        let mut stack = vec![(0usize, 0usize)];
+        while let Some((a, b)) = stack.pop() {
+            let (node_a, node_b) = (&self.nodes[a], &self.nodes[b]);
+            if node_a.is_removed() || node_b.is_removed() {
+                continue;
+            }
+
+            if a == b {
+                // pairs within the subtree of a single node
+                if !node_a.is_leaf() && !self.is_static_top(a) {
+                    let (left, right) = (node_a.get_left_child() as usize, node_a.get_right_child() as usize);
+                    stack.push((left, left));
+                    stack.push((right, right));
+                    stack.push((left, right));
+                }
+            } else if node_a.aabb.intersects(&node_b.aabb) {
+                match (node_a.is_leaf(), node_b.is_leaf()) {
+                    (true, true) => {
+                        let (blas_a, blas_b) = (node_a.blas as usize, node_b.blas as usize);
+                        if self.blas[blas_a].bounding_volume().intersects(self.blas[blas_b].bounding_volume()) {
+                            pairs.push((usize::min(blas_a, blas_b), usize::max(blas_a, blas_b)));
+                        }
+                    },
+                    // descend into the inner node, or the larger one if both are inner nodes
+                    (true, false) => {
+                        stack.push((a, node_b.get_left_child() as usize));
+                        stack.push((a, node_b.get_right_child() as usize));
+                    },
+                    (false, true) => {
+                        stack.push((node_a.get_left_child() as usize, b));
+                        stack.push((node_a.get_right_child() as usize, b));
+                    },
+                    (false, false) => if node_a.aabb.sah_cost() >= node_b.aabb.sah_cost() {
+                        stack.push((node_a.get_left_child() as usize, b));
+                        stack.push((node_a.get_right_child() as usize, b));
+                    } else {
+                        stack.push((a, node_b.get_left_child() as usize));
+                        stack.push((a, node_b.get_right_child() as usize));
+                    },
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Returns the bounds of all nodes in the tree as pairs of min and max points, in depth-first
+    /// order starting at the root. This is meant for debugging, e.g. to draw the nodes of the tree
+    /// as wireframe boxes. Leaves of removed BLAS elements are skipped.
+    pub fn debug_boxes(&self) -> Vec<(SVector<T, DIM>, SVector<T, DIM>)> {
+        self.debug_boxes_with_depth().into_iter()
+            .map(|(min, max, _)| (min, max))
+            .collect()
+    }
+
+    /// Returns the bounds of all nodes in the tree, like `debug_boxes`, together with the depth of
+    /// each node within the tree. The root node has a depth of zero.
+    pub fn debug_boxes_with_depth(&self) -> Vec<(SVector<T, DIM>, SVector<T, DIM>, usize)> {
+        let mut boxes = Vec::with_capacity(self.nodes.size());
+        let mut stack = vec![(0usize, 0usize)];
+        while let Some((node_idx, depth)) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.is_removed() {
+                continue;
+            }
+            boxes.push((node.aabb.min, node.aabb.max, depth));
+            if !node.is_leaf() {
+                stack.push((node.get_right_child() as usize, depth + 1));
+                stack.push((node.get_left_child() as usize, depth + 1));
+            }
+        }
+        boxes
+    }
+
+    /// Returns an iterator over the BLAS elements that intersect the specified intersector. Unlike
+    /// `intersect`, the tree is traversed lazily while iterating, which avoids the allocation of
+    /// the result and allows to stop the query early.
+    pub fn iter_intersect<'a, I>(&'a self, intersector: &'a I, node_idx: usize) -> IntersectIter<'a, I, B>
+    where I: BVIntersector<T, B::BV, DIM> + BVIntersector<T, AABB<T, DIM>, DIM> {
+        IntersectIter::new(self, intersector, Some(node_idx))
+    }
+}
+
+impl<T, B, NodePool, BlasPool, I, const DIM: usize> IntersectTraversal<I, B> for TLAS<T, B, NodePool, BlasPool, DIM>
+where T: BaseFloat,
+      B: TLASElement<T, DIM> + Sized,
+      NodePool: TLASPool<TLASNode<T, DIM>>,
+      BlasPool: TLASPool<B>,
+      I: BVIntersector<T, B::BV, DIM> + BVIntersector<T, AABB<T, DIM>, DIM> {
+
+    fn node_intersects(&self, intersector: &I, node: usize) -> bool {
+        intersector.intersects(&self.nodes[node].aabb)
+    }
+
+    fn children(&self, node: usize) -> Option<(usize, usize)> {
+        let node = &self.nodes[node];
+        if node.is_leaf() {
+            None
+        } else {
+            Some((node.get_left_child() as usize, node.get_right_child() as usize))
+        }
+    }
+
+    fn leaf_elements(&self, node: usize) -> Range<usize> {
+        let node = &self.nodes[node];
+        if node.is_removed() {
+            0..0
+        } else {
+            node.blas as usize..node.blas as usize + 1
+        }
+    }
+
+    fn intersecting_element(&self, intersector: &I, element: usize) -> Option<&B> {
+        let element = &self.blas[element];
+        if intersector.intersects(element.bounding_volume()) {
+            Some(element)
+        } else {
+            None
+        }
+    }
+}
+
+
+/// Compares two coordinates like `partial_cmp`, but orders NaN after all numbers and equal to
+/// itself, such that the order is total.
+fn cmp_nan_last<T: BaseFloat>(a: &T, b: &T) -> Ordering {
+    let is_nan = |x: &T| x.partial_cmp(x).is_none();
+    match (is_nan(a), is_nan(b)) {
+        (false, false) => a.partial_cmp(b).unwrap(),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use nalgebra::SVector;
+    use crate::collision::intersection::{Ray, RayCast, RayIntersection};
+    use crate::volume::aabb::AABB;
+    use crate::volume::bvh::VecPool;
+    use crate::volume::tlas::{TLAS, TLASElement, TLASNode, TLASPool};
+
+    struct Test<const DIM: usize> {
+        id: usize,
+        bounds: AABB<f64, DIM>,
+    }
+
+    impl<const DIM: usize> TLASElement<f64, DIM> for Test<DIM> {
+        type BV = AABB<f64, DIM>;
+
+        fn wrap(&self) -> AABB<f64, DIM> {
+            self.bounds
+        }
+
+        fn bounding_volume(&self) -> &Self::BV {
+            &self.bounds
+        }
+    }
+
+    impl<const DIM: usize> RayCast<f64, DIM> for Test<DIM> {
+        fn raycast(&self, ray: &mut Ray<f64, DIM>) -> bool {
+            match ray.entry(&self.bounds) {
+                Some(d) if d < ray.d => {
+                    ray.d = d;
+                    ray.intersection = Some(RayIntersection {
+                        pos: ray.origin + ray.dir.scale(d),
+                        normal: -ray.dir,
+                        prim_id: self.id,
+                        d,
+                    });
+                    true
+                },
+                _ => false,
+            }
+        }
+    }
+
+    type TestTLAS = TLAS<f64, Test<3>, VecPool<TLASNode<f64, 3>>, VecPool<Test<3>>, 3>;
+
+    /// Builds a TLAS with `n` unit boxes, that are placed in a row along the x-axis.
+    fn row(n: usize) -> TestTLAS {
+        let mut tlas = TestTLAS::new(n);
+        for id in 0..n {
+            let min = SVector::<f64, 3>::new(id as f64 * 2.0, 0.0, 0.0);
+            tlas.blas_mut().push(Test {
+                id,
+                bounds: AABB { min, max: min + SVector::repeat(1.0) },
+            });
+        }
+        tlas.build();
+        tlas
+    }
+
+    fn box_at(x: f64) -> AABB<f64, 3> {
+        AABB {
+            min: SVector::<f64, 3>::new(x, 0.2, 0.2),
+            max: SVector::<f64, 3>::new(x + 0.5, 0.8, 0.8),
+        }
+    }
+
+    fn ids(tlas: &TestTLAS, query: &AABB<f64, 3>) -> Vec<usize> {
+        let mut ids: Vec<_> = tlas.intersect(query, 0).iter().map(|e| e.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn remove_blas() {
+        let mut tlas = row(100);
+        let all = AABB {
+            min: SVector::repeat(-1.0),
+            max: SVector::repeat(1000.0),
+        };
+        assert_eq!(ids(&tlas, &all).len(), 100);
+        assert_eq!(ids(&tlas, &box_at(100.2)), vec![50]);
+
+        let removed = tlas.remove_blas(50);
+        assert_eq!(removed.id, 50);
+        assert!(tlas.is_dirty());
+
+        // the removed instance is gone, while the moved last instance is still found
+        assert!(ids(&tlas, &box_at(100.2)).is_empty());
+        assert_eq!(ids(&tlas, &box_at(198.2)), vec![99]);
+        assert_eq!(ids(&tlas, &box_at(102.2)), vec![51]);
+        let remaining = ids(&tlas, &all);
+        assert_eq!(remaining.len(), 99);
+        assert!(!remaining.contains(&50));
+
+        // rebuilding yields the same results
+        tlas.build();
+        assert!(!tlas.is_dirty());
+        assert_eq!(ids(&tlas, &all), remaining);
+        assert_eq!(ids(&tlas, &box_at(198.2)), vec![99]);
+    }
+
+    #[test]
+    fn scene_bounds() {
+        let mut tlas = row(4);
+        let bounds = tlas.scene_bounds();
+        assert_eq!((bounds.min, bounds.max), (SVector::zeros(), SVector::<f64, 3>::new(7.0, 1.0, 1.0)));
+
+        // the bounds of removed and pushed elements are only part of the tree after a rebuild
+        tlas.remove_blas(3);
+        assert_eq!(tlas.scene_bounds().max.x, 5.0);
+        let min = SVector::<f64, 3>::new(-4.0, 0.0, 0.0);
+        tlas.blas_mut().push(Test { id: 4, bounds: AABB { min, max: min + SVector::repeat(1.0) } });
+        assert_eq!(tlas.scene_bounds().min.x, -4.0);
+        tlas.build();
+        let bounds = tlas.scene_bounds();
+        assert_eq!((bounds.min.x, bounds.max.x), (-4.0, 5.0));
+
+        let empty = TestTLAS::new(1).scene_bounds();
+        assert!(empty.min.x > empty.max.x);
+    }
+
+    #[test]
+    fn remove_all() {
+        let mut tlas = row(3);
+        tlas.remove_blas(1);
+        tlas.remove_blas(0);
+        tlas.remove_blas(0);
+        assert!(ids(&tlas, &box_at(0.2)).is_empty());
+
+        tlas.build();
+        assert!(ids(&tlas, &box_at(0.2)).is_empty());
+    }
+
+    #[test]
+    fn update_leaf() {
+        let mut tlas = row(100);
+        let mut refit = row(100);
+
+        // move an element to the far end of the row
+        for t in [&mut tlas, &mut refit] {
+            t.blas_mut()[30].bounds = box_at(250.0);
+        }
+        tlas.update_leaf(30);
+        refit.refit();
+        assert!(!tlas.is_dirty());
+        assert!(ids(&tlas, &box_at(60.2)).is_empty());
+        assert_eq!(ids(&tlas, &box_at(250.0)), vec![30]);
+
+        // the ancestors of the leaf have the same bounds as after a full refit
+        for i in 0..tlas.nodes().size() {
+            assert_eq!(tlas.nodes()[i].aabb().min, refit.nodes()[i].aabb().min);
+            assert_eq!(tlas.nodes()[i].aabb().max, refit.nodes()[i].aabb().max);
+        }
+
+        // the leaf map follows the elements that are moved by a removal
+        tlas.remove_blas(10);
+        tlas.blas_mut()[10].bounds = box_at(300.0);
+        tlas.update_leaf(10);
+        assert_eq!(ids(&tlas, &box_at(300.0)), vec![99]);
+        assert!(ids(&tlas, &box_at(198.2)).is_empty());
+
+        // elements that are not part of the tree yet mark the TLAS as dirty
+        let mut tlas = row(2);
+        tlas.blas_mut().push(Test { id: 2, bounds: box_at(4.0) });
+        tlas.update_leaf(2);
+        assert!(tlas.is_dirty());
+        tlas.build();
+        assert_eq!(ids(&tlas, &box_at(4.0)), vec![2]);
+    }
+
+    #[test]
+    fn update_single_leaf() {
+        let mut tlas = row(1);
+        tlas.blas_mut()[0].bounds = box_at(10.0);
+        tlas.update_leaf(0);
+        assert_eq!(tlas.nodes()[0].aabb().min, box_at(10.0).min);
+        assert_eq!(ids(&tlas, &box_at(10.0)), vec![0]);
+    }
+
+    /// Asserts that building the TLAS with and without the best match cache yields the same tree.
+    fn assert_same_tree(boxes: Vec<AABB<f64, 3>>) {
+        let mut cached = TestTLAS::new(boxes.len());
+        let mut uncached = TestTLAS::new(boxes.len());
+        for (id, bounds) in boxes.into_iter().enumerate() {
+            cached.blas_mut().push(Test { id, bounds });
+            uncached.blas_mut().push(Test { id, bounds });
+        }
+        cached.build();
+        uncached.build_uncached();
+
+        assert_eq!(cached.nodes.size(), uncached.nodes.size());
+        for i in 0..cached.nodes.size() {
+            let (a, b) = (&cached.nodes[i], &uncached.nodes[i]);
+            assert_eq!(a.left_right, b.left_right, "node {i}");
+            assert_eq!(a.blas, b.blas, "node {i}");
+            assert_eq!(a.aabb.min, b.aabb.min, "node {i}");
+            assert_eq!(a.aabb.max, b.aabb.max, "node {i}");
+        }
+    }
+
+    #[test]
+    fn cached_build() {
+        // a regular grid produces lots of ties between clustering candidates
+        let mut grid = Vec::new();
+        for x in 0..6 {
+            for y in 0..5 {
+                for z in 0..4 {
+                    let min = SVector::<f64, 3>::new(x as f64, y as f64, z as f64) * 2.0;
+                    grid.push(AABB { min, max: min + SVector::repeat(1.0) });
+                }
+            }
+        }
+        assert_same_tree(grid);
+
+        // boxes of varying size at pseudo-random positions
+        let mut seed = 0x2545_f491_u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as f64 / (1u64 << 31) as f64
+        };
+        let random = (0..200)
+            .map(|_| {
+                let min = SVector::<f64, 3>::new(next(), next(), next()) * 100.0;
+                let size = SVector::<f64, 3>::new(next(), next(), next()) * 5.0;
+                AABB { min, max: min + size }
+            })
+            .collect();
+        assert_same_tree(random);
+
+        assert_same_tree(vec![box_at(0.0)]);
+        assert_same_tree(vec![box_at(0.0), box_at(3.0)]);
+    }
+
+    #[test]
+    fn cluster_2d() {
+        type TLAS2 = TLAS<f64, Test<2>, VecPool<TLASNode<f64, 2>>, VecPool<Test<2>>, 2>;
+        let boxes = [
+            // small box at the origin
+            ([0.0, 0.0], [0.1, 0.1]),
+            // long, flat box starting at the origin
+            ([0.0, 0.0], [10.0, 0.1]),
+            // unit box close to the origin
+            ([1.0, 1.0], [2.0, 2.0]),
+        ];
+        let mut tlas = TLAS2::new(boxes.len());
+        for (id, (min, max)) in boxes.into_iter().enumerate() {
+            tlas.blas_mut().push(Test {
+                id,
+                bounds: AABB { min: SVector::from(min), max: SVector::from(max) },
+            });
+        }
+        tlas.build();
+
+        // Clustering the small box with the flat box covers the smallest area, but clustering it
+        // with the unit box results in the smallest perimeter, which is the cheaper cluster.
+        let root = &tlas.nodes[0];
+        let left = &tlas.nodes[root.get_left_child() as usize];
+        let right = &tlas.nodes[root.get_right_child() as usize];
+        let (leaf, cluster) = if left.is_leaf() { (left, right) } else { (right, left) };
+        assert!(leaf.is_leaf());
+        assert_eq!(tlas.blas[leaf.blas as usize].id, 1);
+
+        let mut ids = vec![
+            tlas.blas[tlas.nodes[cluster.get_left_child() as usize].blas as usize].id,
+            tlas.blas[tlas.nodes[cluster.get_right_child() as usize].blas as usize].id,
+        ];
+        ids.sort();
+        assert_eq!(ids, vec![0, 2]);
+    }
+
+    #[test]
+    fn push_order() {
+        let boxes = (0..40)
+            .map(|i| {
+                let x = ((i * 7) % 13) as f64 * 1.5;
+                let y = ((i * 5) % 11) as f64 * 0.75;
+                let min = SVector::<f64, 3>::new(x, y, (i % 3) as f64);
+                AABB { min, max: min + SVector::repeat(1.0 + (i % 4) as f64 * 0.5) }
+            })
+            .collect::<Vec<_>>();
+        let build = |order: &[usize]| {
+            let mut tlas = TestTLAS::new(order.len());
+            for &id in order {
+                tlas.blas_mut().push(Test { id, bounds: boxes[id] });
+            }
+            tlas.build();
+            tlas
+        };
+
+        let forward = build(&(0..40).collect::<Vec<_>>());
+        let shuffled = build(&(0..40).map(|i| (i * 17) % 40).collect::<Vec<_>>());
+        let reversed = build(&(0..40).rev().collect::<Vec<_>>());
+
+        for x in 0..20 {
+            let query = AABB {
+                min: SVector::<f64, 3>::new(x as f64, 0.0, 0.0),
+                max: SVector::<f64, 3>::new(x as f64 + 2.0, 4.0, 2.0),
+            };
+            // the results are reported in the same (unsorted) order
+            let order = |tlas: &TestTLAS| tlas.intersect(&query, 0).iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>();
+            assert_eq!(order(&forward), order(&shuffled));
+            assert_eq!(order(&forward), order(&reversed));
+        }
+        for i in 0..forward.nodes().size() {
+            assert_eq!(forward.nodes()[i].aabb().min, reversed.nodes()[i].aabb().min);
+            assert_eq!(forward.nodes()[i].aabb().max, reversed.nodes()[i].aabb().max);
+        }
+
+        // bounds with NaN coordinates do not break the order of the leaves
+        let mut tlas = TestTLAS::new(40);
+        for (id, &bounds) in boxes.iter().enumerate() {
+            let mut bounds = bounds;
+            if id % 3 == 0 {
+                bounds.min[id % 2] = f64::NAN;
+            }
+            tlas.blas_mut().push(Test { id, bounds });
+        }
+        tlas.build();
+        assert_eq!(ids(&tlas, &box_at(5.0)), ids(&forward, &box_at(5.0)).into_iter()
+            .filter(|id| id % 3 != 0)
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iter_intersect() {
+        let mut tlas = row(50);
+        tlas.remove_blas(20);
+        let queries = [
+            AABB { min: SVector::repeat(-1.0), max: SVector::repeat(1000.0) },
+            AABB { min: SVector::<f64, 3>::new(10.5, 0.0, 0.0), max: SVector::<f64, 3>::new(30.5, 1.0, 1.0) },
+            box_at(40.2),
+            box_at(500.0),
+        ];
+        for query in queries {
+            let expected: Vec<_> = tlas.intersect(&query, 0).iter().map(|e| e.id).collect();
+            let actual: Vec<_> = tlas.iter_intersect(&query, 0).map(|e| e.id).collect();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn debug_boxes() {
+        let mut tlas = row(4);
+        let boxes = tlas.debug_boxes_with_depth();
+        // 4 leaves and 3 inner nodes
+        assert_eq!(boxes.len(), 7);
+        assert_eq!(boxes[0], (SVector::zeros(), SVector::<f64, 3>::new(7.0, 1.0, 1.0), 0));
+        assert_eq!(boxes.iter().filter(|(_, _, depth)| *depth == 1).count(), 2);
+
+        tlas.remove_blas(0);
+        assert_eq!(tlas.debug_boxes().len(), 6);
+    }
+
+    #[test]
+    fn overlapping_pairs() {
+        // a 4 x 4 grid of overlapping boxes with a spacing of 0.75
+        let mut tlas = TestTLAS::new(16);
+        for id in 0..16 {
+            let min = SVector::<f64, 3>::new((id % 4) as f64, (id / 4) as f64, 0.0) * 0.75;
+            tlas.blas_mut().push(Test { id, bounds: AABB { min, max: min + SVector::repeat(1.0) } });
+        }
+        tlas.build();
+
+        let mut pairs = tlas.overlapping_pairs();
+        pairs.sort();
+        let mut expected = Vec::new();
+        for a in 0..16usize {
+            for b in (a + 1)..16usize {
+                if (a % 4).abs_diff(b % 4) <= 1 && (a / 4).abs_diff(b / 4) <= 1 {
+                    expected.push((a, b));
+                }
+            }
+        }
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn validate() {
+        let mut tlas = row(50);
+        assert_eq!(tlas.validate(), Ok(()));
+        assert_eq!(TestTLAS::new(0).validate(), Ok(()));
+
+        // removals and leaf updates keep the tree valid
+        tlas.remove_blas(20);
+        tlas.remove_blas(3);
+        tlas.blas_mut()[3].bounds = box_at(500.0);
+        tlas.update_leaf(3);
+        assert_eq!(tlas.validate(), Ok(()));
+        tlas.remove_blas(0);
+        tlas.remove_blas(0);
+        assert_eq!(tlas.validate(), Ok(()));
+
+        // corrupted bounds, parents and leaves are reported
+        let mut corrupt = row(50);
+        let top = corrupt.nodes.size() - 1;
+        let child = corrupt.nodes[top].get_left_child() as usize;
+        corrupt.nodes[child].aabb.max.x += 1000.0;
+        assert!(corrupt.validate().is_err());
+
+        let mut corrupt = row(50);
+        corrupt.nodes[child].parent = 1;
+        assert!(corrupt.validate().is_err());
+
+        let mut corrupt = row(50);
+        corrupt.nodes[1].blas = 60;
+        assert!(corrupt.validate().is_err());
+
+        let mut corrupt = row(50);
+        corrupt.nodes[0].aabb.min.y = -1.0;
+        assert!(corrupt.validate().is_err());
+    }
+
+    #[test]
+    fn spherecast() {
+        let tlas = row(100);
+        let x = SVector::<f64, 3>::x();
+
+        // the first box of the row is hit, and the last one when casting the other way
+        let (hit, t) = tlas.spherecast(&SVector::<f64, 3>::new(-5.0, 0.5, 0.5), &(x * 3.0), 0.25, 100.0).unwrap();
+        assert_eq!(hit.id, 0);
+        assert!((t - 4.75).abs() < 1e-6);
+        let (hit, t) = tlas.spherecast(&SVector::<f64, 3>::new(250.0, 0.5, 0.5), &-x, 0.25, 100.0).unwrap();
+        assert_eq!(hit.id, 99);
+        assert!((t - 50.75).abs() < 1e-6);
+
+        // passing above the row misses it, unless the sphere is large enough
+        assert!(tlas.spherecast(&SVector::<f64, 3>::new(-5.0, 1.5, 0.5), &x, 0.25, 500.0).is_none());
+        let (hit, t) = tlas.spherecast(&SVector::<f64, 3>::new(-5.0, 1.5, 0.5), &x, 0.6, 500.0).unwrap();
+        assert_eq!(hit.id, 0);
+        assert!((t - 5.0 + (0.36f64 - 0.25).sqrt()).abs() < 1e-6);
+
+        // passing along the edges of the boxes touches the expanded node bounds, but not the boxes
+        assert!(tlas.spherecast(&SVector::<f64, 3>::new(-5.0, 1.2, 1.2), &x, 0.25, 500.0).is_none());
+
+        // a sphere that starts within a gap or inside of a box
+        let (hit, t) = tlas.spherecast(&SVector::<f64, 3>::new(11.5, 0.5, 0.5), &x, 0.25, 10.0).unwrap();
+        assert_eq!(hit.id, 6);
+        assert!((t - 0.25).abs() < 1e-6);
+        assert_eq!(tlas.spherecast(&SVector::<f64, 3>::new(12.5, 0.5, 0.5), &x, 0.25, 10.0).unwrap().1, 0.0);
+        assert!(tlas.spherecast(&SVector::<f64, 3>::new(-5.0, 0.5, 0.5), &x, 0.25, 4.0).is_none());
+    }
+
+    #[test]
+    fn raycast() {
+        let tlas = row(100);
+        let ray = |origin: SVector<f64, 3>, dir: SVector<f64, 3>| Ray { d: f64::MAX, origin, dir, intersection: None };
+
+        // all boxes are stacked along the ray, of which the nearest one is hit
+        let mut r = ray(SVector::<f64, 3>::new(-5.0, 0.5, 0.5), SVector::x());
+        assert_eq!(tlas.raycast(&mut r).unwrap().id, 0);
+        assert_eq!(r.d, 5.0);
+        assert_eq!(r.intersection.unwrap().prim_id, 0);
+        let mut r = ray(SVector::<f64, 3>::new(250.0, 0.5, 0.5), -SVector::x());
+        assert_eq!(tlas.raycast(&mut r).unwrap().id, 99);
+        assert_eq!(r.d, 51.0);
+
+        let mut r = ray(SVector::<f64, 3>::new(50.5, 10.0, 0.5), -SVector::y());
+        assert_eq!(tlas.raycast(&mut r).unwrap().id, 25);
+        let mut r = ray(SVector::<f64, 3>::new(51.5, 10.0, 0.5), -SVector::y());
+        assert!(tlas.raycast(&mut r).is_none());
+        assert!(r.intersection.is_none());
+
+        // the ray ends before the first box
+        let mut r = ray(SVector::<f64, 3>::new(-5.0, 0.5, 0.5), SVector::x());
+        r.d = 4.5;
+        assert!(tlas.raycast(&mut r).is_none());
+        assert_eq!(r.d, 4.5);
+    }
+
+    /// Element that counts how often it was wrapped, which tells which parts of the tree were
+    /// processed by a build or refit.
+    struct Level {
+        bounds: AABB<f64, 3>,
+        is_static: bool,
+        wraps: Cell<usize>,
+    }
+
+    impl TLASElement<f64, 3> for Level {
+        type BV = AABB<f64, 3>;
+
+        fn wrap(&self) -> AABB<f64, 3> {
+            self.wraps.set(self.wraps.get() + 1);
+            self.bounds
+        }
+
+        fn bounding_volume(&self) -> &Self::BV {
+            &self.bounds
+        }
+
+        fn is_static(&self) -> bool {
+            self.is_static
+        }
+    }
+
+    #[test]
+    fn static_subtree() {
+        let unit_box = |x: f64, y: f64| {
+            let min = SVector::<f64, 3>::new(x, y, 0.0);
+            AABB { min, max: min + SVector::repeat(1.0) }
+        };
+        let level = |bounds: AABB<f64, 3>, is_static: bool| Level { bounds, is_static, wraps: Cell::new(0) };
+
+        // a floor of overlapping static boxes, with dynamic boxes resting on every other one
+        let mut tlas = TLAS::<f64, Level, VecPool<TLASNode<f64, 3>>, VecPool<Level>, 3>::new(12);
+        for i in 0..8 {
+            tlas.blas_mut().push(level(unit_box(i as f64 * 0.75, 0.0), true));
+        }
+        for i in 0..4 {
+            tlas.blas_mut().push(level(unit_box(i as f64 * 1.5, 0.75), false));
+        }
+        tlas.build();
+        assert_eq!(tlas.validate(), Ok(()));
+        let static_wraps = |tlas: &TLAS<f64, Level, VecPool<TLASNode<f64, 3>>, VecPool<Level>, 3>| {
+            (0..8).map(|i| tlas.blas()[i].wraps.get()).sum::<usize>()
+        };
+        let wraps = static_wraps(&tlas);
+
+        // only pairs with dynamic boxes are reported
+        let pairs = tlas.overlapping_pairs();
+        assert!(pairs.iter().all(|(_, b)| *b >= 8));
+        assert_eq!(pairs.len(), 11);
+
+        // moving the dynamic boxes neither rebuilds nor refits the static subtree
+        for i in 8..12 {
+            tlas.blas_mut()[i].bounds = unit_box((i - 8) as f64 * 1.5, 5.0);
+        }
+        tlas.refit();
+        assert_eq!(tlas.validate(), Ok(()));
+        tlas.build();
+        assert_eq!(tlas.validate(), Ok(()));
+        assert_eq!(static_wraps(&tlas), wraps);
+        assert!(tlas.overlapping_pairs().is_empty());
+        assert_eq!(tlas.intersect(&unit_box(0.0, 0.0), 0).len(), 2);
+        assert_eq!(tlas.intersect(&unit_box(0.0, 5.0), 0).len(), 1);
+
+        // adding or removing static boxes rebuilds the static subtree
+        tlas.blas_mut().push(level(unit_box(0.0, 5.5), true));
+        tlas.build();
+        assert_eq!(tlas.validate(), Ok(()));
+        assert!(static_wraps(&tlas) > wraps);
+        assert_eq!(tlas.overlapping_pairs(), [(8, 12)]);
+        tlas.remove_blas(12);
+        assert!(tlas.is_dirty());
+        tlas.build();
+        assert_eq!(tlas.validate(), Ok(()));
+        assert!(tlas.overlapping_pairs().is_empty());
+
+        // a tree of static boxes only
+        tlas.remove_blas(11);
+        tlas.remove_blas(10);
+        tlas.remove_blas(9);
+        tlas.remove_blas(8);
+        tlas.build();
+        assert_eq!(tlas.validate(), Ok(()));
+        assert!(tlas.overlapping_pairs().is_empty());
+        assert_eq!(tlas.intersect(&unit_box(0.0, 0.0), 0).len(), 2);
+
+        tlas.clear();
+        assert_eq!(tlas.validate(), Ok(()));
+        assert!(tlas.intersect(&unit_box(0.0, 0.0), 0).is_empty());
     }
 }
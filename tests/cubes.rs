@@ -0,0 +1,73 @@
+//! Headless version of the `cubes` demo, which checks that the simulation is deterministic.
+
+use nalgebra::Vector3;
+use corrosive_physics::engine::PhysicsEngine;
+use corrosive_physics::system::object::{PhyEntity, PhyEntityBuilder, PhyEntityID};
+
+/// Builds the scene of the `cubes` demo: a static floor with a stack of 5x3x3 falling cubes above.
+fn cubes_scene() -> (PhysicsEngine<f64>, Vec<PhyEntityID>) {
+    let mut engine = PhysicsEngine::<f64>::new();
+    let entity_id = PhyEntityID {
+        world_id: 0,
+        chunk_id: 0,
+        entity_id: 0,
+        generation: 0,
+    };
+
+    let floor = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::new(20.0, 1.0, 20.0)))
+        .is_static(true)
+        .build();
+    engine.spawn(floor);
+
+    let spacing = 2.0;
+    let mut cubes = Vec::new();
+    for y in 0..5 {
+        for x in 0..3 {
+            for z in 0..3 {
+                let entity = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::repeat(1.0)))
+                    .position(Vector3::new(
+                        x as f64 * spacing - 5.0,
+                        5.0 + y as f64 * spacing,
+                        z as f64 * spacing - 5.0
+                    ))
+                    .linear_velocity(Vector3::new(0.0, -1.0, 0.0))
+                    .build();
+                cubes.push(entity);
+            }
+        }
+    }
+    let ids = engine.spawn_batch(cubes);
+    (engine, ids)
+}
+
+/// Simulates the scene for `steps` fixed steps and returns the bit patterns of the final positions,
+/// rotations and momenta of all cubes.
+fn simulate(steps: usize) -> Vec<u64> {
+    let (mut engine, ids) = cubes_scene();
+    for _ in 0..steps {
+        engine.step(engine.fixed_dt);
+    }
+    ids.iter()
+        .flat_map(|id| {
+            let is = &engine[id.clone()].is;
+            is.state.pos.iter()
+                .chain(is.state.rot.coords.iter())
+                .chain(is.momentum.iter())
+                .chain(is.angular_mom.iter())
+                .map(|v| v.to_bits())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[test]
+fn deterministic() {
+    let snapshot = simulate(240);
+    assert_eq!(snapshot, simulate(240));
+
+    // after four seconds, the cubes rest in stacks of five on top of the floor
+    for (i, values) in snapshot.chunks(13).enumerate() {
+        let y = f64::from_bits(values[1]);
+        assert!((y - (1.0 + (i / 9) as f64)).abs() < 0.25, "cube {} rests at height {}", i, y);
+    }
+}
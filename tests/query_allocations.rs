@@ -0,0 +1,103 @@
+//! Counts the heap allocations of collider queries per frame, with and without a `QueryBuffer`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use nalgebra::Vector3;
+use corrosive_physics::engine::PhysicsEngine;
+use corrosive_physics::system::object::{PhyEntity, PhyEntityBuilder, PhyEntityID};
+use corrosive_physics::volume::QueryBuffer;
+
+/// Global allocator that counts the allocations of the current thread, such that allocations of
+/// the test harness on other threads do not interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations that `f` makes on the current thread.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// Builds the scene of the `cubes` demo, but with the cubes packed closely, such that every cube
+/// overlaps its neighbours.
+fn packed_cubes() -> (PhysicsEngine<f64>, Vec<PhyEntityID>) {
+    let mut engine = PhysicsEngine::<f64>::new();
+    let entity_id = PhyEntityID {
+        world_id: 0,
+        chunk_id: 0,
+        entity_id: 0,
+        generation: 0,
+    };
+
+    let floor = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::new(20.0, 1.0, 20.0)))
+        .is_static(true)
+        .build();
+    engine.spawn(floor);
+
+    let mut cubes = Vec::new();
+    for y in 0..5 {
+        for x in 0..3 {
+            for z in 0..3 {
+                let entity = PhyEntityBuilder::new(PhyEntity::cube(entity_id.clone(), Vector3::repeat(1.0)))
+                    .position(Vector3::new(x as f64 * 0.9, 0.95 + y as f64 * 0.9, z as f64 * 0.9))
+                    .build();
+                cubes.push(entity);
+            }
+        }
+    }
+    let ids = engine.spawn_batch(cubes);
+    engine.build();
+    (engine, ids)
+}
+
+#[test]
+fn query_buffer_allocations() {
+    let (engine, ids) = packed_cubes();
+    let mut buffer = QueryBuffer::new();
+    let mut frame = |buffer: &mut QueryBuffer<_>| {
+        let mut colliders = 0;
+        for id in &ids {
+            buffer.with(|out| {
+                engine.query_colliders_into(id.clone(), out);
+                colliders += out.len();
+            });
+        }
+        colliders
+    };
+
+    // the first frame grows the buffer, after which every frame runs without allocations
+    let colliders = frame(&mut buffer);
+    assert!(colliders > 2 * ids.len(), "{colliders}");
+    assert_eq!(count_allocations(|| assert_eq!(frame(&mut buffer), colliders)), 0);
+
+    // the allocating query allocates a result vector for every entity in every frame
+    let allocating = count_allocations(|| {
+        for id in &ids {
+            assert!(!engine.query_colliders(id.clone()).is_empty());
+        }
+    });
+    assert!(allocating >= ids.len(), "{allocating}");
+}